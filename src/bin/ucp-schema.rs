@@ -2,16 +2,24 @@
 //!
 //! Command-line interface for resolving and validating UCP schemas.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use ucp_schema::{
-    bundle_refs, bundle_refs_with_url_mapping, compose_from_payload, compose_schema,
-    detect_direction, extract_capabilities, extract_capabilities_from_profile,
-    extract_jsonrpc_payload, is_url, lint, load_schema, load_schema_auto, resolve,
-    select_operation_schema, validate, ComposeError, DetectedDirection, Direction, FileStatus,
-    ResolveError, ResolveOptions, SchemaBaseConfig, ValidateError,
+    assert_bundled, bundle_refs, bundle_refs_traced, bundle_refs_with_url_mapping,
+    capability_schema_source, compose_schema, detect_direction, extract_capabilities,
+    extract_capabilities_from_profile, extract_jsonrpc_payload, flatten_for_codegen,
+    inline_internal_refs, is_url, lint_with_options, load_schema, load_schema_as,
+    load_schema_auto_as, load_schema_str, load_schema_str_as, payload_metadata, resolve,
+    resolve_detailed, resolve_layered, select_operation_schema, select_schema_by_id,
+    strip_annotations, to_openapi30, unknown_keywords, validate_against_schema, AnnotationCoverage,
+    Capability, ComposeError, Direction, FileStatus, InputFormat, LintOptions, OmitMatch,
+    PathStyle, PayloadMetadata, RequiredPolicy, ResolveError, ResolveOptions, SchemaBaseConfig,
+    SchemaError, SchemaSource, Severity, ValidateError, LINT_CONFIG_FILES,
 };
 
 /// Errors with associated CLI exit codes.
@@ -68,14 +76,45 @@ fn determine_direction(
     }
 }
 
+/// Short direction name for messages ("request" or "response").
+fn direction_name(direction: Direction) -> &'static str {
+    let key = direction.annotation_key();
+    key.strip_prefix("ucp_").unwrap_or(key)
+}
+
+/// Check that an `--exclude` value is a valid glob pattern.
+fn parse_exclude_pattern(s: &str) -> Result<String, String> {
+    glob::Pattern::new(s)
+        .map(|_| s.to_string())
+        .map_err(|e| format!("invalid glob '{}': {}", s, e))
+}
+
+/// Parse a `--schema-override NAME=PATH` value.
+fn parse_schema_override(s: &str) -> Result<(String, PathBuf), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), PathBuf::from(path)))
+        }
+        _ => Err(format!("expected NAME=PATH, got '{}'", s)),
+    }
+}
+
 #[cfg(feature = "remote")]
 use ucp_schema::bundle_refs_remote;
 
+#[cfg(feature = "patch")]
+use ucp_schema::apply_patch;
+
 #[derive(Parser)]
 #[command(name = "ucp-schema")]
 #[command(about = "Resolve and validate UCP schema annotations")]
 #[command(version)]
 struct Cli {
+    /// Do all the work but report what would be written instead of writing
+    /// files (path and size; stdout output is unaffected)
+    #[arg(long, global = true)]
+    dry_run: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -85,7 +124,8 @@ enum Commands {
     /// Resolve a schema for a specific direction and operation.
     /// Accepts a schema file or a self-describing payload (auto-composes if payload detected).
     Resolve {
-        /// Schema or payload source: file path or URL (http:// or https://)
+        /// Schema or payload source: file path, URL (http:// or https://), or
+        /// - for stdin
         schema: String,
 
         /// Resolve for request direction (auto-inferred for payloads)
@@ -96,9 +136,15 @@ enum Commands {
         #[arg(long, conflicts_with = "request")]
         response: bool,
 
-        /// Operation to resolve for (e.g., create, update, read)
-        #[arg(long, short)]
-        op: String,
+        /// Operation to resolve for (e.g., create, update, read); repeat to
+        /// resolve one superset schema accepting any of the operations
+        #[arg(long, short, required = true)]
+        op: Vec<String>,
+
+        /// With several --op, whether a field is required when required for
+        /// all of them (default) or for any
+        #[arg(long, value_name = "POLICY", default_value = "all")]
+        required_policy: RequiredPolicy,
 
         /// Select an explicit $defs entry to output (e.g. search_response,
         /// business_schema, error_response), overriding the {op}_{direction}
@@ -118,6 +164,41 @@ enum Commands {
         #[arg(long)]
         bundle: bool,
 
+        /// Log each external $ref inlined by --bundle and the file or URL it
+        /// resolved to (stderr; schema files and stdin only, not URL sources)
+        #[arg(long, requires = "bundle")]
+        trace_ref: bool,
+
+        /// Directory relative $refs resolve against with --bundle, instead of
+        /// the schema file's directory (useful for stdin and URL input)
+        #[arg(long, value_name = "DIR", requires = "bundle")]
+        base_dir: Option<PathBuf>,
+
+        /// Fail if any external $ref remains after bundling or composing
+        #[arg(long)]
+        require_bundled: bool,
+
+        /// Inline internal #/... $ref pointers into the output; recursive refs
+        /// stay as $ref
+        #[arg(long)]
+        inline_defs: bool,
+
+        /// Output dialect: jsonschema (2020-12, default) or openapi30 (inline
+        /// $defs, nullable, drop unsupported keywords with a warning)
+        #[arg(long, default_value = "jsonschema", value_parser = ["jsonschema", "openapi30"])]
+        target: String,
+
+        /// Flatten for type generators: inline internal $refs, merge allOf
+        /// branches into one object, drop keywords generators can't map
+        /// (with a warning)
+        #[arg(long, conflicts_with = "target")]
+        flatten_for_codegen: bool,
+
+        /// JSON Patch (RFC 6902) file applied to the schema before bundling and
+        /// resolving (to the composed schema for payload input)
+        #[arg(long, value_name = "FILE")]
+        patch: Option<PathBuf>,
+
         /// Local directory containing schema files (used when input is a payload)
         #[arg(long)]
         schema_local_base: Option<PathBuf>,
@@ -126,6 +207,11 @@ enum Commands {
         #[arg(long, requires = "schema_local_base")]
         schema_remote_base: Option<String>,
 
+        /// Load a capability's schema from a local file instead of its declared
+        /// URL (NAME=PATH, repeatable)
+        #[arg(long = "schema-override", value_name = "NAME=PATH", value_parser = parse_schema_override)]
+        schema_overrides: Vec<(String, PathBuf)>,
+
         /// Strict mode: set additionalProperties=false to reject unknown fields (default: false)
         #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
         strict: bool,
@@ -136,6 +222,30 @@ enum Commands {
         #[arg(long)]
         include_future: bool,
 
+        /// Parse input as json or yaml, overriding detection by file extension
+        #[arg(long, value_name = "FORMAT")]
+        input_format: Option<InputFormat>,
+
+        /// Accept true/false as ucp_request/ucp_response shorthand (false = omit,
+        /// true = include)
+        #[arg(long)]
+        boolean_shorthand: bool,
+
+        /// List each property omitted for this direction/operation, with the
+        /// annotation and operation entry that omitted it (stderr)
+        #[arg(long)]
+        explain_omit: bool,
+
+        /// Summarize annotation decisions (properties omitted, required,
+        /// optional, included; annotations stripped) on stderr
+        #[arg(long)]
+        stats: bool,
+
+        /// With --stats, write the counts as JSON to FILE (- for stderr)
+        /// instead of the summary
+        #[arg(long, value_name = "FILE", requires = "stats")]
+        json_stats: Option<PathBuf>,
+
         /// Print pipeline stages to stderr for debugging
         #[arg(long, short)]
         verbose: bool,
@@ -143,12 +253,14 @@ enum Commands {
 
     /// Validate a payload against a resolved schema
     Validate {
-        /// Payload file to validate
-        payload: PathBuf,
+        /// Payload file(s) to validate
+        #[arg(required = true)]
+        payload: Vec<PathBuf>,
 
-        /// Explicit schema (default: infer from payload's UCP metadata)
+        /// Explicit schema (default: infer from payload's UCP metadata;
+        /// repeatable, all schemas must accept the payload)
         #[arg(long)]
-        schema: Option<String>,
+        schema: Vec<String>,
 
         /// Local directory containing schema files
         #[arg(long)]
@@ -158,10 +270,25 @@ enum Commands {
         #[arg(long, requires = "schema_local_base")]
         schema_remote_base: Option<String>,
 
+        /// Load a capability's schema from a local file instead of its declared
+        /// URL (NAME=PATH, repeatable)
+        #[arg(long = "schema-override", value_name = "NAME=PATH", value_parser = parse_schema_override)]
+        schema_overrides: Vec<(String, PathBuf)>,
+
+        /// JSON Patch (RFC 6902) file applied to the schema before bundling and
+        /// resolving (the first --schema, or the composed schema)
+        #[arg(long, value_name = "FILE")]
+        patch: Option<PathBuf>,
+
         /// Agent profile URL (REST pattern: profile via header, payload is raw object)
         #[arg(long, conflicts_with = "schema")]
         profile: Option<String>,
 
+        /// Each payload file is a combined document {"schema": ..., "payload": ...};
+        /// relative $refs in the schema resolve against the file's directory
+        #[arg(long, conflicts_with_all = ["schema", "profile"])]
+        combined: bool,
+
         /// Validate as request (auto-inferred if omitted)
         #[arg(long, conflicts_with = "response")]
         request: bool,
@@ -170,6 +297,15 @@ enum Commands {
         #[arg(long, conflicts_with = "request")]
         response: bool,
 
+        /// Validate against both the request and response resolutions and
+        /// report each direction (fails unless both accept the payload)
+        #[arg(long, conflicts_with_all = ["request", "response", "def"])]
+        both: bool,
+
+        /// With --both, pass when either direction accepts the payload
+        #[arg(long, requires = "both")]
+        accept_either: bool,
+
         /// Operation to validate for (e.g., create, update, read)
         #[arg(long, short)]
         op: String,
@@ -180,14 +316,67 @@ enum Commands {
         #[arg(long)]
         def: Option<String>,
 
-        /// Output results as JSON (for automation)
-        #[arg(long)]
+        /// Select the schema declaring this $id from a multi-schema bundle
+        /// (applies to the first --schema)
+        #[arg(long, value_name = "URI", requires = "schema")]
+        schema_id: Option<String>,
+
+        /// Output results as JSON (for automation); same as --format json
+        #[arg(long, conflicts_with = "format")]
         json: bool,
 
+        /// Output format: text, json, or ndjson (one line per payload)
+        #[arg(long, default_value = "text", value_parser = ["text", "json", "ndjson"])]
+        format: String,
+
         /// Strict mode: reject unknown fields (default: false)
         #[arg(long, default_value_t = false, action = clap::ArgAction::Set)]
         strict: bool,
 
+        /// Drop the payload's top-level `ucp` metadata object before validating,
+        /// so strict mode doesn't reject the self-describing envelope
+        #[arg(long)]
+        ignore_ucp_envelope: bool,
+
+        /// Parse input as json or yaml, overriding detection by file extension
+        #[arg(long, value_name = "FORMAT")]
+        input_format: Option<InputFormat>,
+
+        /// Accept true/false as ucp_request/ucp_response shorthand (false = omit,
+        /// true = include)
+        #[arg(long)]
+        boolean_shorthand: bool,
+
+        /// Warn on stderr about keywords the schema's `$schema` draft doesn't
+        /// define (typos like `requird` that validators silently ignore)
+        #[arg(long)]
+        warn_unknown_keywords: bool,
+
+        /// Fail if any external $ref remains in the schema after bundling or
+        /// composing
+        #[arg(long)]
+        require_bundled: bool,
+
+        /// Render payload paths as relative (as given), cwd-relative, or absolute
+        #[arg(long, value_name = "STYLE", default_value = "relative")]
+        path_style: PathStyle,
+
+        /// Base envelope schema (the `ucp` object and fields common to every
+        /// capability) added to the composition as an allOf branch
+        /// (self-describing payloads)
+        #[arg(long, value_name = "FILE")]
+        envelope_schema: Option<PathBuf>,
+
+        /// Print where each capability's schema is loaded from (URL, base
+        /// mapping, final path or URL) to stderr before composing
+        #[arg(long)]
+        explain_compose: bool,
+
+        /// Reject payloads larger than this many bytes, reading no further
+        /// than the limit (default: unlimited)
+        #[arg(long, value_name = "BYTES")]
+        max_payload_size: Option<u64>,
+
         /// Print pipeline stages to stderr for debugging
         #[arg(long, short)]
         verbose: bool,
@@ -206,6 +395,50 @@ enum Commands {
         #[arg(long, requires = "schema_local_base")]
         schema_remote_base: Option<String>,
 
+        /// Load a capability's schema from a local file instead of its declared
+        /// URL (NAME=PATH, repeatable)
+        #[arg(long = "schema-override", value_name = "NAME=PATH", value_parser = parse_schema_override)]
+        schema_overrides: Vec<(String, PathBuf)>,
+
+        /// Remove $id and $anchor from inlined extension branches
+        #[arg(long)]
+        strip_extension_ids: bool,
+
+        /// Drop additionalProperties: false from extension branches so the
+        /// composed allOf doesn't reject fields added by other extensions
+        #[arg(long)]
+        relax_closed_extensions: bool,
+
+        /// Fail on an extension branch closed to unknown properties instead
+        /// of composing it
+        #[arg(long, conflicts_with = "relax_closed_extensions")]
+        reject_closed_extensions: bool,
+
+        /// Use an extension's top-level allOf/properties as its branch when it
+        /// has no $defs entry for the extended capability
+        #[arg(long)]
+        top_level_extensions: bool,
+
+        /// Fail when one composed branch requires a property another forbids
+        /// (`"prop": false` or `"not": {"required": [...]}`)
+        #[arg(long)]
+        check_satisfiable: bool,
+
+        /// Require each capability's schema to declare its capability name at
+        /// this JSON Pointer (e.g. /name or /x-ucp-capability)
+        #[arg(long, value_name = "POINTER")]
+        capability_id_pointer: Option<String>,
+
+        /// Base envelope schema (the `ucp` object and fields common to every
+        /// capability) added to the composition as an allOf branch
+        #[arg(long, value_name = "FILE")]
+        envelope_schema: Option<PathBuf>,
+
+        /// Print where each capability's schema is loaded from (URL, base
+        /// mapping, final path or URL) to stderr before composing
+        #[arg(long)]
+        explain: bool,
+
         /// Output file (stdout if not specified)
         #[arg(long)]
         output: Option<PathBuf>,
@@ -235,7 +468,64 @@ enum Commands {
         /// Suppress progress output, only show errors
         #[arg(long, short)]
         quiet: bool,
+
+        /// Only print diagnostics of this severity (error or warning); the
+        /// exit code is unaffected
+        #[arg(long, value_name = "SEVERITY", value_parser = ["error", "warning"])]
+        only: Option<String>,
+
+        /// Fail (exit 1) when no schema files are found under the path
+        #[arg(long)]
+        error_on_empty: bool,
+
+        /// Parse input as json or yaml, overriding detection by file extension
+        #[arg(long, value_name = "FORMAT")]
+        input_format: Option<InputFormat>,
+
+        /// Accept true/false as ucp_request/ucp_response shorthand (false = omit,
+        /// true = include)
+        #[arg(long)]
+        boolean_shorthand: bool,
+
+        /// Render file paths as relative (to the linted path), cwd-relative,
+        /// or absolute [default: relative]
+        #[arg(long, value_name = "STYLE")]
+        path_style: Option<PathStyle>,
+
+        /// Don't warn about files without $id (disables W002)
+        #[arg(long)]
+        no_require_id: bool,
+
+        /// Skip files and directories matching this glob when walking a
+        /// directory (repeatable; patterns without `/` match names)
+        #[arg(long, value_name = "GLOB", value_parser = parse_exclude_pattern)]
+        exclude: Vec<String>,
+
+        /// Report ucp_request/ucp_response annotation coverage per file and
+        /// in total
+        #[arg(long)]
+        coverage: bool,
+
+        /// Warn (W014) about schemas with properties but no ucp_* annotations,
+        /// which resolve the same for every operation
+        #[arg(long)]
+        warn_unannotated: bool,
+
+        /// Lint config file (JSON, or TOML by extension). Without it, the
+        /// nearest .ucp-lint.json or .ucp-lint.toml at or above the path is
+        /// used; flags override its values
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
     },
+
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+
+    /// Resolve embedded sample schemas and check the results (post-install smoke test)
+    Selftest,
 }
 
 fn main() -> ExitCode {
@@ -247,28 +537,57 @@ fn main() -> ExitCode {
             request,
             response,
             op,
+            required_policy,
             def,
             output,
             pretty,
             bundle,
+            trace_ref,
+            base_dir,
+            require_bundled,
+            inline_defs,
+            target,
+            flatten_for_codegen,
+            patch,
             schema_local_base,
             schema_remote_base,
+            schema_overrides,
             strict,
             include_future,
+            input_format,
+            boolean_shorthand,
+            explain_omit,
+            stats,
+            json_stats,
             verbose,
         } => run_resolve(
             &schema,
             request,
             response,
             op,
+            required_policy,
             def,
             output,
+            cli.dry_run,
             pretty,
             bundle,
+            trace_ref,
+            base_dir.as_deref(),
+            require_bundled,
+            inline_defs,
+            &target,
+            flatten_for_codegen,
+            patch.as_deref(),
             schema_local_base,
             schema_remote_base,
+            schema_overrides.into_iter().collect(),
             strict,
             include_future,
+            input_format,
+            boolean_shorthand,
+            explain_omit,
+            stats,
+            json_stats.as_deref(),
             verbose,
         ),
 
@@ -276,6 +595,15 @@ fn main() -> ExitCode {
             payload,
             schema_local_base,
             schema_remote_base,
+            schema_overrides,
+            strip_extension_ids,
+            relax_closed_extensions,
+            reject_closed_extensions,
+            top_level_extensions,
+            check_satisfiable,
+            capability_id_pointer,
+            envelope_schema,
+            explain,
             output,
             pretty,
             verbose,
@@ -283,7 +611,17 @@ fn main() -> ExitCode {
             &payload,
             schema_local_base,
             schema_remote_base,
+            schema_overrides.into_iter().collect(),
+            strip_extension_ids,
+            relax_closed_extensions,
+            reject_closed_extensions,
+            top_level_extensions,
+            check_satisfiable,
+            capability_id_pointer,
+            envelope_schema.as_deref(),
+            explain,
             output,
+            cli.dry_run,
             pretty,
             verbose,
         ),
@@ -293,26 +631,62 @@ fn main() -> ExitCode {
             schema,
             schema_local_base,
             schema_remote_base,
+            schema_overrides,
+            patch,
             profile,
+            combined,
             request,
             response,
+            both,
+            accept_either,
             op,
             def,
+            schema_id,
             json,
+            format,
             strict,
+            ignore_ucp_envelope,
+            input_format,
+            boolean_shorthand,
+            warn_unknown_keywords,
+            require_bundled,
+            path_style,
+            envelope_schema,
+            explain_compose,
+            max_payload_size,
             verbose,
         } => run_validate(ValidateArgs {
-            payload,
-            schema,
+            payloads: payload,
+            schemas: schema,
             schema_local_base,
             schema_remote_base,
+            schema_overrides: schema_overrides.into_iter().collect(),
+            patch,
             profile,
+            combined,
             request,
             response,
+            both,
+            accept_either,
             op,
             def,
-            json_output: json,
+            schema_id,
+            format: match format.as_str() {
+                _ if json => ValidateFormat::Json,
+                "json" => ValidateFormat::Json,
+                "ndjson" => ValidateFormat::Ndjson,
+                _ => ValidateFormat::Text,
+            },
             strict,
+            ignore_ucp_envelope,
+            input_format,
+            boolean_shorthand,
+            warn_unknown_keywords,
+            require_bundled,
+            path_style,
+            envelope_schema,
+            explain_compose,
+            max_payload_size,
             verbose,
         }),
 
@@ -321,7 +695,49 @@ fn main() -> ExitCode {
             format,
             strict,
             quiet,
-        } => run_lint(&path, &format, strict, quiet),
+            only,
+            error_on_empty,
+            input_format,
+            boolean_shorthand,
+            path_style,
+            no_require_id,
+            exclude,
+            coverage,
+            warn_unannotated,
+            config,
+        } => load_lint_config(&path, config.as_deref()).and_then(|mut options| {
+            // Flags override the config file
+            options.strict |= strict;
+            options.boolean_shorthand |= boolean_shorthand;
+            options.input_format = input_format.or(options.input_format);
+            if let Some(path_style) = path_style {
+                options.path_style = path_style;
+            }
+            options.allow_missing_id |= no_require_id;
+            if !exclude.is_empty() {
+                options.exclude = exclude;
+            }
+            options.coverage |= coverage;
+            options.warn_unannotated |= warn_unannotated;
+            run_lint(
+                &path,
+                &format,
+                quiet,
+                only.map(|only| match only.as_str() {
+                    "warning" => Severity::Warning,
+                    _ => Severity::Error,
+                }),
+                error_on_empty,
+                &options,
+            )
+        }),
+
+        Commands::Completions { shell } => {
+            run_completions(shell);
+            Ok(())
+        }
+
+        Commands::Selftest => run_selftest(),
     };
 
     match result {
@@ -340,21 +756,43 @@ fn run_resolve(
     schema_source: &str,
     request: bool,
     response: bool,
-    op: String,
+    op: Vec<String>,
+    required_policy: RequiredPolicy,
     def: Option<String>,
     output: Option<PathBuf>,
+    dry_run: bool,
     pretty: bool,
     bundle: bool,
+    trace_ref: bool,
+    base_dir: Option<&Path>,
+    require_bundled: bool,
+    inline_defs: bool,
+    target: &str,
+    for_codegen: bool,
+    patch: Option<&Path>,
     schema_local_base: Option<PathBuf>,
     schema_remote_base: Option<String>,
+    schema_overrides: HashMap<String, PathBuf>,
     strict: bool,
     include_future: bool,
+    input_format: Option<InputFormat>,
+    boolean_shorthand: bool,
+    explain_omit: bool,
+    stats: bool,
+    json_stats: Option<&Path>,
     verbose: bool,
 ) -> Result<(), u8> {
+    if trace_ref && is_url(schema_source) {
+        report_error(
+            false,
+            "--trace-ref only traces bundling from local files; it does not apply to a URL schema source",
+        );
+        return Err(2);
+    }
     if verbose {
         eprintln!("[load] reading {}", schema_source);
     }
-    let mut input = load_schema_auto(schema_source).map_err(cli_err(false))?;
+    let mut input = load_resolve_input(schema_source, input_format).map_err(cli_err(false))?;
 
     // Auto-detect: is this a payload (needs compose) or a schema (resolve directly)?
     let detected = detect_direction(&input);
@@ -367,19 +805,23 @@ fn run_resolve(
 
     let schema = if detected.is_some() {
         // Input is a self-describing payload — compose schemas from capabilities
-        let config = SchemaBaseConfig {
-            local_base: schema_local_base.as_deref(),
-            remote_base: schema_remote_base.as_deref(),
-        };
+        let mut config = SchemaBaseConfig::default();
+        config.local_base = schema_local_base.as_deref();
+        config.remote_base = schema_remote_base.as_deref();
+        config.overrides = Some(&schema_overrides);
+        let capabilities = extract_capabilities(&input, &config).map_err(cli_err(false))?;
         if verbose {
-            verbose_capabilities(&input, &config);
+            verbose_capabilities(&capabilities, &config);
             eprintln!("[compose] composing schemas from payload capabilities");
         }
-        compose_from_payload(&input, &config).map_err(cli_err(false))?
+        let mut composed = compose_schema(&capabilities, &config).map_err(cli_err(false))?;
+        patch_schema(&mut composed, patch, verbose).map_err(cli_err(false))?;
+        composed
     } else {
         if verbose {
             eprintln!("[detect] input is a schema file (no ucp.capabilities)");
         }
+        patch_schema(&mut input, patch, verbose).map_err(cli_err(false))?;
         // Input is a schema file — bundle $refs if requested
         if bundle {
             if verbose {
@@ -395,13 +837,18 @@ fn run_resolve(
             bundle_local_refs(
                 &mut input,
                 schema_source,
+                base_dir,
                 &schema_local_base,
                 &schema_remote_base,
-                false,
-            )?;
+                trace_ref,
+            )
+            .map_err(cli_err_ctx(false, "bundling refs"))?;
         }
         input
     };
+    if require_bundled {
+        assert_bundled(&schema).map_err(cli_err(false))?;
+    }
 
     // Direction: explicit flag > auto-inferred from payload > require explicit
     let direction = determine_direction(request, response, detected.map(Direction::from))
@@ -413,9 +860,12 @@ fn run_resolve(
             2u8
         })?;
 
-    let options = ResolveOptions::new(direction, &op)
+    let options = ResolveOptions::new(direction, &op[0])
+        .also_operations(op[1..].to_vec())
+        .required_policy(required_policy)
         .strict(strict)
         .include_future(include_future)
+        .boolean_shorthand(boolean_shorthand)
         .def_name(def);
     if verbose {
         let mut flags = Vec::new();
@@ -432,15 +882,51 @@ fn run_resolve(
         };
         eprintln!(
             "[resolve] resolving for {}/{}{}",
-            direction
-                .annotation_key()
-                .strip_prefix("ucp_")
-                .unwrap_or(direction.annotation_key()),
-            op,
+            direction_name(direction),
+            op.join("+"),
             suffix
         );
     }
-    let resolved = resolve(&schema, &options).map_err(cli_err(false))?;
+    let (resolved, omissions, counts) =
+        resolve_detailed(&schema, &options).map_err(cli_err(false))?;
+    if explain_omit {
+        for omission in &omissions {
+            let matched = match &omission.matched {
+                OmitMatch::Operation(op) => format!("operation \"{}\"", op),
+                OmitMatch::Shorthand => "shorthand (all operations)".to_string(),
+                OmitMatch::Inherited => "inherit (enclosing property omitted)".to_string(),
+            };
+            eprintln!(
+                "[omit] {}: {} via {}",
+                omission.path, omission.annotation, matched
+            );
+        }
+    }
+    if stats {
+        match json_stats {
+            Some(path) if path == Path::new("-") => {
+                eprintln!("{}", serde_json::json!(counts));
+            }
+            Some(path) => {
+                write_json_output(
+                    &serde_json::json!(counts),
+                    Some(path.to_path_buf()),
+                    dry_run,
+                    pretty,
+                )?;
+            }
+            None => eprintln!(
+                "[stats] {} properties: {} omitted, {} required, {} optional, {} included; \
+                 {} annotations stripped",
+                counts.properties_total,
+                counts.omitted,
+                counts.required,
+                counts.optional,
+                counts.included,
+                counts.annotations_stripped
+            ),
+        }
+    }
 
     // `resolve` defaults to emitting the full resolved schema (container $defs
     // intact). Only an explicit --def slices to a single $def; auto-derivation
@@ -450,17 +936,60 @@ fn run_resolve(
     } else {
         resolved
     };
+    let output_value = if inline_defs {
+        if verbose {
+            eprintln!("[inline] inlining internal $ref pointers");
+        }
+        inline_internal_refs(&output_value).map_err(cli_err(false))?
+    } else {
+        output_value
+    };
+    let output_value = if target == "openapi30" {
+        if verbose {
+            eprintln!("[target] converting to OpenAPI 3.0");
+        }
+        let converted = to_openapi30(&output_value).map_err(cli_err(false))?;
+        for warning in &converted.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        converted.schema
+    } else {
+        output_value
+    };
+    let output_value = if for_codegen {
+        if verbose {
+            eprintln!("[codegen] flattening for type generators");
+        }
+        let flattened = flatten_for_codegen(&output_value).map_err(cli_err(false))?;
+        for warning in &flattened.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        flattened.schema
+    } else {
+        output_value
+    };
 
-    write_json_output(&output_value, output, pretty)
+    write_json_output(&output_value, output, dry_run, pretty)
 }
 
 /// Pure composition: merge capability schemas from a self-describing payload.
 /// Output preserves UCP annotations (no resolve step).
+#[allow(clippy::too_many_arguments)]
 fn run_compose(
     payload_path: &Path,
     schema_local_base: Option<PathBuf>,
     schema_remote_base: Option<String>,
+    schema_overrides: HashMap<String, PathBuf>,
+    strip_extension_ids: bool,
+    relax_closed_extensions: bool,
+    reject_closed_extensions: bool,
+    top_level_extensions: bool,
+    check_satisfiable: bool,
+    capability_id_pointer: Option<String>,
+    envelope_schema: Option<&Path>,
+    explain: bool,
     output: Option<PathBuf>,
+    dry_run: bool,
     pretty: bool,
     verbose: bool,
 ) -> Result<(), u8> {
@@ -475,116 +1004,582 @@ fn run_compose(
         return Err(2);
     }
 
-    let config = SchemaBaseConfig {
-        local_base: schema_local_base.as_deref(),
-        remote_base: schema_remote_base.as_deref(),
-    };
+    let envelope = load_envelope_schema(envelope_schema, &schema_local_base, &schema_remote_base)
+        .map_err(cli_err_ctx(false, "loading envelope schema"))?;
+    let mut config = SchemaBaseConfig::default();
+    config.local_base = schema_local_base.as_deref();
+    config.remote_base = schema_remote_base.as_deref();
+    config.overrides = Some(&schema_overrides);
+    config.strip_extension_ids = strip_extension_ids;
+    config.relax_closed_extensions = relax_closed_extensions;
+    config.reject_closed_extensions = reject_closed_extensions;
+    config.top_level_extensions = top_level_extensions;
+    config.check_satisfiable = check_satisfiable;
+    config.capability_id_pointer = capability_id_pointer.as_deref();
+    config.envelope = envelope.as_ref();
+    let capabilities = extract_capabilities(&payload, &config).map_err(cli_err(false))?;
     if verbose {
-        verbose_capabilities(&payload, &config);
+        verbose_capabilities(&capabilities, &config);
         eprintln!("[compose] composing schemas (annotations preserved)");
     }
-    let schema = compose_from_payload(&payload, &config).map_err(cli_err(false))?;
+    if explain {
+        explain_capabilities(&capabilities, &config);
+    }
+    let schema = compose_schema(&capabilities, &config).map_err(cli_err(false))?;
 
-    write_json_output(&schema, output, pretty)
+    write_json_output(&schema, output, dry_run, pretty)
 }
 
 struct ValidateArgs {
-    payload: PathBuf,
-    schema: Option<String>,
+    payloads: Vec<PathBuf>,
+    schemas: Vec<String>,
     schema_local_base: Option<PathBuf>,
     schema_remote_base: Option<String>,
+    schema_overrides: HashMap<String, PathBuf>,
+    patch: Option<PathBuf>,
     profile: Option<String>,
+    combined: bool,
     request: bool,
     response: bool,
+    both: bool,
+    accept_either: bool,
     op: String,
     def: Option<String>,
-    json_output: bool,
+    schema_id: Option<String>,
+    format: ValidateFormat,
     strict: bool,
+    ignore_ucp_envelope: bool,
+    input_format: Option<InputFormat>,
+    boolean_shorthand: bool,
+    warn_unknown_keywords: bool,
+    require_bundled: bool,
+    path_style: PathStyle,
+    envelope_schema: Option<PathBuf>,
+    explain_compose: bool,
+    max_payload_size: Option<u64>,
     verbose: bool,
 }
 
-fn run_validate(args: ValidateArgs) -> Result<(), u8> {
-    let ValidateArgs {
-        payload: payload_path,
-        schema: schema_source,
-        schema_local_base,
-        schema_remote_base,
-        profile: profile_url,
-        request,
-        response,
-        op,
-        def,
-        json_output,
-        strict,
-        verbose,
-    } = args;
+/// Output format for `validate`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ValidateFormat {
+    Text,
+    /// One JSON object, or an array of per-file objects for several payloads.
+    Json,
+    /// One compact JSON object per payload per line, flushed as it goes.
+    Ndjson,
+}
+
+/// Why a single payload did not validate.
+#[derive(Clone)]
+enum ValidateFailure {
+    Invalid(Vec<SchemaError>),
+    Error { code: u8, message: String },
+}
+
+/// Per-direction outcomes of a `--both` run, request first.
+type DirectionOutcomes = Vec<(Direction, Result<(), ValidateFailure>)>;
+
+impl ValidateFailure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            ValidateFailure::Invalid(_) => 1,
+            ValidateFailure::Error { code, .. } => *code,
+        }
+    }
+}
+
+fn fail<E: std::fmt::Display + CliExitCode>(e: E) -> ValidateFailure {
+    ValidateFailure::Error {
+        code: e.exit_code(),
+        message: e.to_string(),
+    }
+}
+
+fn fail_ctx<'a, E: std::fmt::Display + CliExitCode>(
+    context: &'a str,
+) -> impl FnOnce(E) -> ValidateFailure + 'a {
+    move |e| ValidateFailure::Error {
+        code: e.exit_code(),
+        message: format!("{}: {}", context, e),
+    }
+}
+
+/// Read a payload of at most `limit` bytes. The read itself is bounded, so
+/// FIFOs and `/dev/stdin` are capped as well as regular files.
+fn read_payload_limited(path: &Path, limit: u64) -> Result<String, ValidateFailure> {
+    let read_error = |source| ResolveError::ReadError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = if path.is_dir() {
+        Err(ResolveError::NotAFile {
+            path: path.to_path_buf(),
+        })
+    } else {
+        std::fs::File::open(path).map_err(|source| match source.kind() {
+            std::io::ErrorKind::NotFound => ResolveError::FileNotFound {
+                path: path.to_path_buf(),
+            },
+            _ => read_error(source),
+        })
+    }
+    .map_err(fail_ctx("loading payload"))?;
+
+    let mut content = Vec::new();
+    file.take(limit.saturating_add(1))
+        .read_to_end(&mut content)
+        .map_err(read_error)
+        .map_err(fail_ctx("loading payload"))?;
+    if content.len() as u64 > limit {
+        return Err(ValidateFailure::Error {
+            code: 2,
+            message: format!(
+                "payload is over the --max-payload-size limit of {} bytes",
+                limit
+            ),
+        });
+    }
+    String::from_utf8(content)
+        .map_err(|e| read_error(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+        .map_err(fail_ctx("loading payload"))
+}
 
+fn run_validate(args: ValidateArgs) -> Result<(), u8> {
     // Note: --schema-local-base/--schema-remote-base apply to both modes:
     // - Self-describing: passed to compose for capability schema URL resolution
     // - Explicit --schema: used for URL-to-local mapping when bundling $ref values
+    let envelope = load_envelope_schema(
+        args.envelope_schema.as_deref(),
+        &args.schema_local_base,
+        &args.schema_remote_base,
+    )
+    .map_err(cli_err_ctx(
+        args.format != ValidateFormat::Text,
+        "loading envelope schema",
+    ))?;
+    let mut config = SchemaBaseConfig::default();
+    config.local_base = args.schema_local_base.as_deref();
+    config.remote_base = args.schema_remote_base.as_deref();
+    config.overrides = Some(&args.schema_overrides);
+    config.envelope = envelope.as_ref();
+
+    let shared = load_shared_schemas(&args, &config);
+    let multiple = args.payloads.len() > 1;
+    let mut exit_code = 0u8;
+    let mut json_results = Vec::new();
+
+    for payload_path in &args.payloads {
+        let shown = args.path_style.render(payload_path, Path::new(""));
+        if args.both {
+            let outcome = validate_payload_both(payload_path, &args, &config, shared.as_ref());
+            exit_code = exit_code.max(both_exit_code(&outcome, args.accept_either));
+            match args.format {
+                ValidateFormat::Text => {
+                    let prefix = if multiple {
+                        format!("{}: ", shown.display())
+                    } else {
+                        String::new()
+                    };
+                    print_both_text(&prefix, &outcome);
+                }
+                ValidateFormat::Json if !multiple => {
+                    println!("{}", both_result_json(None, &outcome, args.accept_either));
+                }
+                ValidateFormat::Json => {
+                    json_results.push(both_result_json(Some(&shown), &outcome, args.accept_either));
+                }
+                ValidateFormat::Ndjson => {
+                    let record = both_result_json(Some(&shown), &outcome, args.accept_either);
+                    println!("{}", record);
+                    let _ = std::io::stdout().flush();
+                }
+            }
+            continue;
+        }
+
+        let outcome = validate_payload(payload_path, &args, &config, shared.as_ref());
+        if let Err(failure) = &outcome {
+            exit_code = exit_code.max(failure.exit_code());
+        }
+
+        match args.format {
+            ValidateFormat::Text => {
+                let prefix = if multiple {
+                    format!("{}: ", shown.display())
+                } else {
+                    String::new()
+                };
+                match outcome {
+                    Ok(()) => println!("{}Valid", prefix),
+                    Err(failure) => print_failure_text(&prefix, &failure),
+                }
+            }
+            ValidateFormat::Json if !multiple => {
+                println!("{}", validate_result_json(None, &outcome));
+            }
+            ValidateFormat::Json => {
+                json_results.push(validate_result_json(Some(&shown), &outcome));
+            }
+            ValidateFormat::Ndjson => {
+                println!("{}", validate_result_json(Some(&shown), &outcome));
+                let _ = std::io::stdout().flush();
+            }
+        }
+    }
+
+    if args.format == ValidateFormat::Json && multiple {
+        println!("{}", serde_json::Value::Array(json_results));
+    }
+
+    if exit_code == 0 {
+        Ok(())
+    } else {
+        Err(exit_code)
+    }
+}
+
+/// JSON `outcome` for an exit code, so consumers need not infer the failure
+/// mode from which fields are present.
+fn outcome_name(exit_code: u8) -> &'static str {
+    match exit_code {
+        0 => "valid",
+        1 => "invalid",
+        3 => "io_error",
+        _ => "schema_error",
+    }
+}
 
-    let config = SchemaBaseConfig {
-        local_base: schema_local_base.as_deref(),
-        remote_base: schema_remote_base.as_deref(),
+/// JSON record for one payload's outcome.
+///
+/// Without a file this is the single-payload `--json` shape
+/// (`{"valid":true,"outcome":"valid"}` on success); with a file, `file` leads
+/// and `errors` is always present.
+fn validate_result_json(
+    file: Option<&Path>,
+    outcome: &Result<(), ValidateFailure>,
+) -> serde_json::Value {
+    let mut record = serde_json::Map::new();
+    if let Some(file) = file {
+        record.insert("file".to_string(), file.display().to_string().into());
+    }
+    record.insert("valid".to_string(), outcome.is_ok().into());
+    let code = outcome.as_ref().err().map_or(0, ValidateFailure::exit_code);
+    record.insert("outcome".to_string(), outcome_name(code).into());
+    let errors = match outcome {
+        Ok(()) if file.is_none() => None,
+        Ok(()) => Some(serde_json::json!([])),
+        Err(failure) => Some(failure_errors_json(failure)),
     };
+    if let Some(errors) = errors {
+        record.insert("errors".to_string(), errors);
+    }
+    serde_json::Value::Object(record)
+}
 
-    // Load payload file
-    if verbose {
-        eprintln!("[load] reading payload {}", payload_path.display());
+/// JSON `errors` array for a failed payload.
+fn failure_errors_json(failure: &ValidateFailure) -> serde_json::Value {
+    match failure {
+        ValidateFailure::Invalid(errors) => serde_json::json!(errors),
+        // Same shape as validation errors for a consistent API
+        ValidateFailure::Error { message, .. } => {
+            serde_json::json!([{"path": "", "message": message}])
+        }
     }
-    let payload_file =
-        load_schema(&payload_path).map_err(cli_err_ctx(json_output, "loading payload"))?;
+}
 
-    // Determine validation mode and extract actual payload to validate:
-    // 1. --profile: REST pattern, payload is raw object
-    // 2. --schema: explicit schema, payload is raw object
-    // 3. JSONRPC: meta.profile in payload, extract nested payload
-    // 4. Response: ucp.capabilities in payload, payload is self-describing
-    let (schema, payload, direction) = if let Some(ref profile) = profile_url {
-        // REST pattern: --profile flag provides profile URL, payload is raw
-        if verbose {
-            eprintln!("[detect] REST pattern: using --profile {}", profile);
+/// Whether a `--both` run passes: every direction must accept the payload,
+/// or just one with `--accept-either`.
+fn both_passed(outcomes: &DirectionOutcomes, accept_either: bool) -> bool {
+    if accept_either {
+        outcomes.iter().any(|(_, outcome)| outcome.is_ok())
+    } else {
+        outcomes.iter().all(|(_, outcome)| outcome.is_ok())
+    }
+}
+
+/// Exit code for a `--both` run: the worst failing direction's code, or 0
+/// when the run passes.
+fn both_exit_code(outcome: &Result<DirectionOutcomes, ValidateFailure>, accept_either: bool) -> u8 {
+    match outcome {
+        Err(failure) => failure.exit_code(),
+        Ok(outcomes) if both_passed(outcomes, accept_either) => 0,
+        Ok(outcomes) => outcomes
+            .iter()
+            .filter_map(|(_, outcome)| outcome.as_ref().err())
+            .map(ValidateFailure::exit_code)
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+/// Text report for a `--both` run: one line per direction, with validation
+/// errors indented beneath the failing direction.
+fn print_both_text(prefix: &str, outcome: &Result<DirectionOutcomes, ValidateFailure>) {
+    let outcomes = match outcome {
+        Ok(outcomes) => outcomes,
+        Err(failure) => return print_failure_text(prefix, failure),
+    };
+    for (direction, outcome) in outcomes {
+        let name = direction_name(*direction);
+        match outcome {
+            Ok(()) => println!("{}{}: Valid", prefix, name),
+            Err(failure) => print_failure_text(&format!("{}{}: ", prefix, name), failure),
         }
-        let direction = determine_direction(request, response, None).unwrap_or(Direction::Request);
+    }
+}
 
-        let capabilities =
-            extract_capabilities_from_profile(profile, &config).map_err(cli_err(json_output))?;
+/// Text report for a failed payload, to stderr.
+fn print_failure_text(prefix: &str, failure: &ValidateFailure) {
+    match failure {
+        ValidateFailure::Invalid(errors) => {
+            eprintln!("{}Validation failed:", prefix);
+            for error in errors {
+                eprintln!("  {}", error);
+            }
+        }
+        ValidateFailure::Error { message, .. } => eprintln!("{}Error: {}", prefix, message),
+    }
+}
 
-        if verbose {
-            eprintln!(
-                "[compose] composing {} capability schemas from profile",
-                capabilities.len()
+/// JSON record for one payload's `--both` outcome: the overall `valid` and
+/// `outcome` plus a `request` and a `response` record shaped like a per-file
+/// result.
+fn both_result_json(
+    file: Option<&Path>,
+    outcome: &Result<DirectionOutcomes, ValidateFailure>,
+    accept_either: bool,
+) -> serde_json::Value {
+    let mut record = serde_json::Map::new();
+    if let Some(file) = file {
+        record.insert("file".to_string(), file.display().to_string().into());
+    }
+    let outcomes = match outcome {
+        Ok(outcomes) => outcomes,
+        Err(failure) => {
+            record.insert("valid".to_string(), false.into());
+            record.insert(
+                "outcome".to_string(),
+                outcome_name(failure.exit_code()).into(),
             );
+            record.insert("errors".to_string(), failure_errors_json(failure));
+            return serde_json::Value::Object(record);
+        }
+    };
+    record.insert(
+        "valid".to_string(),
+        both_passed(outcomes, accept_either).into(),
+    );
+    record.insert(
+        "outcome".to_string(),
+        outcome_name(both_exit_code(outcome, accept_either)).into(),
+    );
+    for (direction, outcome) in outcomes {
+        let (code, errors) = match outcome {
+            Ok(()) => (0, serde_json::json!([])),
+            Err(failure) => (failure.exit_code(), failure_errors_json(failure)),
+        };
+        record.insert(
+            direction_name(*direction).to_string(),
+            serde_json::json!({
+                "valid": outcome.is_ok(),
+                "outcome": outcome_name(code),
+                "errors": errors,
+            }),
+        );
+    }
+    serde_json::Value::Object(record)
+}
+
+/// Validate one payload file, reporting only verbose progress to stderr.
+///
+/// `shared` holds the `--schema`/`--profile` schemas loaded once for the
+/// whole run; payloads that bring their own schemas don't use it.
+fn validate_payload(
+    payload_path: &Path,
+    args: &ValidateArgs,
+    config: &SchemaBaseConfig,
+    shared: Option<&Result<ValidationTarget, ValidateFailure>>,
+) -> Result<(), ValidateFailure> {
+    let (own, payload, direction) = load_for_validation(payload_path, args, config)?;
+    select_target(own.as_ref(), shared)?.validate(&payload, direction, args)
+}
+
+/// Validate one payload file against both the request and the response
+/// resolution (`--both`), in that order.
+///
+/// Loading and composing failures apply to both directions and are returned
+/// as the outer error; each direction's own outcome is reported separately.
+fn validate_payload_both(
+    payload_path: &Path,
+    args: &ValidateArgs,
+    config: &SchemaBaseConfig,
+    shared: Option<&Result<ValidationTarget, ValidateFailure>>,
+) -> Result<DirectionOutcomes, ValidateFailure> {
+    let (own, payload, _) = load_for_validation(payload_path, args, config)?;
+    let target = select_target(own.as_ref(), shared)?;
+    Ok([Direction::Request, Direction::Response]
+        .into_iter()
+        .map(|direction| {
+            let outcome = target.validate(&payload, direction, args);
+            (direction, outcome)
+        })
+        .collect())
+}
+
+/// The payload's own schemas if it brought any, else the shared ones.
+fn select_target<'a>(
+    own: Option<&'a ValidationTarget>,
+    shared: Option<&'a Result<ValidationTarget, ValidateFailure>>,
+) -> Result<&'a ValidationTarget, ValidateFailure> {
+    match (own, shared) {
+        (Some(own), _) => Ok(own),
+        (None, Some(shared)) => shared.as_ref().map_err(Clone::clone),
+        (None, None) => Err(ValidateFailure::Error {
+            code: 2,
+            message: "no schema to validate against".to_string(),
+        }),
+    }
+}
+
+/// Schema(s) payloads validate against, resolved at most once per direction.
+struct ValidationTarget {
+    schemas: Vec<serde_json::Value>,
+    resolved: RefCell<HashMap<Direction, Result<serde_json::Value, ValidateFailure>>>,
+}
+
+impl ValidationTarget {
+    fn new(schemas: Vec<serde_json::Value>) -> Self {
+        ValidationTarget {
+            schemas,
+            resolved: RefCell::new(HashMap::new()),
         }
-        let schema = compose_schema(&capabilities, &config).map_err(cli_err(json_output))?;
+    }
+
+    /// Resolve the schema(s) for `direction`, on first use, and validate the
+    /// payload.
+    fn validate(
+        &self,
+        payload: &serde_json::Value,
+        direction: Direction,
+        args: &ValidateArgs,
+    ) -> Result<(), ValidateFailure> {
+        let mut resolved = self.resolved.borrow_mut();
+        let target = resolved.entry(direction).or_insert_with(|| {
+            let options = ResolveOptions::new(direction, args.op.as_str())
+                .strict(args.strict)
+                .boolean_shorthand(args.boolean_shorthand)
+                .def_name(args.def.clone());
+            if args.verbose {
+                eprintln!(
+                    "[resolve] resolving for {}/{}",
+                    direction_name(direction),
+                    options.operation
+                );
+            }
+            resolve_layered(&self.schemas, &options).map_err(fail)
+        });
+        let target = target.as_ref().map_err(Clone::clone)?;
+        if args.verbose {
+            eprintln!("[validate] validating payload against resolved schema");
+        }
+
+        match validate_against_schema(target, payload) {
+            Ok(()) => Ok(()),
+            Err(ValidateError::Invalid { errors, .. }) => Err(ValidateFailure::Invalid(errors)),
+            Err(ValidateError::Resolve(e)) => Err(fail(e)),
+            Err(ValidateError::Compose(e)) => Err(fail(e)),
+            Err(e) => Err(ValidateFailure::Error {
+                code: e.exit_code() as u8,
+                message: e.to_string(),
+            }),
+        }
+    }
+}
+
+/// Load the `--profile` or `--schema` schema(s), which every payload shares,
+/// once for the run. `None` when each payload brings its own (`--combined` or
+/// self-describing payloads).
+fn load_shared_schemas(
+    args: &ValidateArgs,
+    config: &SchemaBaseConfig,
+) -> Option<Result<ValidationTarget, ValidateFailure>> {
+    if args.combined || (args.profile.is_none() && args.schemas.is_empty()) {
+        return None;
+    }
+    let schemas = match &args.profile {
+        Some(profile) => load_profile_schema(profile, args, config).map(|schema| vec![schema]),
+        None => load_explicit_schemas(args),
+    };
+    Some(
+        schemas
+            .and_then(|schemas| check_schemas(&schemas, args).map(|()| schemas))
+            .map(ValidationTarget::new),
+    )
+}
+
+/// Compose the schema for the `--profile` URL (REST pattern).
+fn load_profile_schema(
+    profile: &str,
+    args: &ValidateArgs,
+    config: &SchemaBaseConfig,
+) -> Result<serde_json::Value, ValidateFailure> {
+    if args.verbose {
+        eprintln!("[detect] REST pattern: using --profile {}", profile);
+    }
+    let capabilities = extract_capabilities_from_profile(profile, config).map_err(fail)?;
+
+    if args.verbose {
+        eprintln!(
+            "[compose] composing {} capability schemas from profile",
+            capabilities.len()
+        );
+    }
+    let mut schema = compose_schema(&capabilities, config).map_err(fail)?;
+    patch_schema(&mut schema, args.patch.as_deref(), args.verbose).map_err(fail)?;
+    Ok(schema)
+}
+
+/// Load and bundle the `--schema` sources, in order.
+fn load_explicit_schemas(args: &ValidateArgs) -> Result<Vec<serde_json::Value>, ValidateFailure> {
+    let ValidateArgs {
+        schemas: schema_sources,
+        schema_local_base,
+        schema_remote_base,
+        patch,
+        schema_id,
+        input_format,
+        verbose,
+        ..
+    } = args;
+    let verbose = *verbose;
 
-        (schema, payload_file, direction)
-    } else if let Some(ref source) = schema_source {
-        // Explicit schema: try to infer direction from payload
+    let mut schemas = Vec::with_capacity(schema_sources.len());
+    for (i, source) in schema_sources.iter().enumerate() {
         if verbose {
             eprintln!("[load] using explicit schema: {}", source);
         }
-        let inferred = detect_direction(&payload_file).map(Direction::from);
-        let direction =
-            determine_direction(request, response, inferred).unwrap_or(Direction::Request);
-
         let mut schema =
-            load_schema_auto(source).map_err(cli_err_ctx(json_output, "loading schema"))?;
+            load_schema_auto_as(source, *input_format).map_err(fail_ctx("loading schema"))?;
+        if i == 0 {
+            patch_schema(&mut schema, patch.as_deref(), verbose).map_err(fail)?;
+        }
 
         // Bundle refs based on source type and available mappings
         #[cfg(feature = "remote")]
         {
             if is_url(source) {
-                bundle_refs_remote(&mut schema, source)
-                    .map_err(cli_err_ctx(json_output, "bundling refs"))?;
+                bundle_refs_remote(&mut schema, source).map_err(fail_ctx("bundling refs"))?;
             } else {
                 bundle_local_refs(
                     &mut schema,
                     source,
-                    &schema_local_base,
-                    &schema_remote_base,
-                    json_output,
-                )?;
+                    None,
+                    schema_local_base,
+                    schema_remote_base,
+                    false,
+                )
+                .map_err(fail_ctx("bundling refs"))?;
             }
         }
         #[cfg(not(feature = "remote"))]
@@ -592,53 +1587,172 @@ fn run_validate(args: ValidateArgs) -> Result<(), u8> {
             bundle_local_refs(
                 &mut schema,
                 source,
-                &schema_local_base,
-                &schema_remote_base,
-                json_output,
-            )?;
+                None,
+                schema_local_base,
+                schema_remote_base,
+                false,
+            )
+            .map_err(fail_ctx("bundling refs"))?;
+        }
+        if let (0, Some(id)) = (i, schema_id) {
+            if verbose {
+                eprintln!("[select] using bundle member with $id {}", id);
+            }
+            schema = select_schema_by_id(&schema, id).map_err(fail)?;
+        }
+        schemas.push(schema);
+    }
+    Ok(schemas)
+}
+
+/// `--warn-unknown-keywords` and `--require-bundled` checks on loaded schemas.
+fn check_schemas(
+    schemas: &[serde_json::Value],
+    args: &ValidateArgs,
+) -> Result<(), ValidateFailure> {
+    if args.warn_unknown_keywords {
+        for warning in schemas.iter().flat_map(unknown_keywords) {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+    if args.require_bundled {
+        for schema in schemas {
+            assert_bundled(schema).map_err(fail)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load a payload, along with the direction given on the command line or
+/// inferred from the payload and, for `--combined` documents and
+/// self-describing payloads, the schema(s) it validates against. `None`
+/// means the payload validates against the run's shared schemas.
+fn load_for_validation(
+    payload_path: &Path,
+    args: &ValidateArgs,
+    config: &SchemaBaseConfig,
+) -> Result<(Option<ValidationTarget>, serde_json::Value, Direction), ValidateFailure> {
+    let ValidateArgs {
+        schemas: schema_sources,
+        schema_local_base,
+        schema_remote_base,
+        patch,
+        profile: profile_url,
+        combined,
+        request,
+        response,
+        ignore_ucp_envelope,
+        input_format,
+        explain_compose,
+        max_payload_size,
+        verbose,
+        ..
+    } = args;
+    let (request, response, verbose) = (*request, *response, *verbose);
+
+    // Load payload file
+    if verbose {
+        eprintln!("[load] reading payload {}", payload_path.display());
+    }
+    let payload_file = match *max_payload_size {
+        Some(limit) => read_payload_limited(payload_path, limit).and_then(|content| {
+            let format = input_format.unwrap_or_else(|| InputFormat::from_path(payload_path));
+            load_schema_str_as(&content, format).map_err(fail_ctx("loading payload"))
+        })?,
+        None => match input_format {
+            Some(format) => load_schema_as(payload_path, *format),
+            None => load_schema(payload_path),
         }
+        .map_err(fail_ctx("loading payload"))?,
+    };
 
-        (schema, payload_file, direction)
+    // Determine validation mode and extract actual payload to validate:
+    // 1. --combined: schema and payload members of one document
+    // 2. --profile: REST pattern, payload is raw object (shared schema)
+    // 3. --schema: explicit schema, payload is raw object (shared schemas)
+    // 4. JSONRPC: meta.profile in payload, extract nested payload
+    // 5. Response: ucp.capabilities in payload, payload is self-describing
+    let (schemas, mut payload, direction) = if *combined {
+        let member = |key: &str| {
+            payload_file
+                .get(key)
+                .cloned()
+                .ok_or_else(|| ValidateFailure::Error {
+                    code: 2,
+                    message: format!("combined document has no \"{}\" member", key),
+                })
+        };
+        let (mut schema, payload) = (member("schema")?, member("payload")?);
+        if verbose {
+            eprintln!("[detect] combined document: using its schema and payload members");
+        }
+        let inferred = detect_direction(&payload).map(Direction::from);
+        let direction =
+            determine_direction(request, response, inferred).unwrap_or(Direction::Request);
+
+        patch_schema(&mut schema, patch.as_deref(), verbose).map_err(fail)?;
+        bundle_local_refs(
+            &mut schema,
+            &payload_path.to_string_lossy(),
+            None,
+            schema_local_base,
+            schema_remote_base,
+            false,
+        )
+        .map_err(fail_ctx("bundling refs"))?;
+
+        (Some(vec![schema]), payload, direction)
+    } else if profile_url.is_some() {
+        let direction = determine_direction(request, response, None).unwrap_or(Direction::Request);
+        (None, payload_file, direction)
+    } else if !schema_sources.is_empty() {
+        // Explicit schema(s): try to infer direction from payload
+        let inferred = detect_direction(&payload_file).map(Direction::from);
+        let direction =
+            determine_direction(request, response, inferred).unwrap_or(Direction::Request);
+        (None, payload_file, direction)
     } else {
-        // Self-describing mode - detect from payload structure
-        match detect_direction(&payload_file) {
-            Some(DetectedDirection::Response) => {
+        // Self-describing mode - locate the metadata once; it gives both the
+        // direction and the capabilities to compose
+        match payload_metadata(&payload_file) {
+            Some(metadata @ PayloadMetadata::Capabilities(_)) => {
                 // Response: ucp.capabilities, compose and validate full payload
+                let capabilities = metadata.capabilities(config).map_err(fail)?;
                 if verbose {
-                    verbose_capabilities(&payload_file, &config);
+                    verbose_capabilities(&capabilities, config);
                     eprintln!("[compose] composing schemas from payload capabilities");
                 }
+                if *explain_compose {
+                    explain_capabilities(&capabilities, config);
+                }
                 let direction = determine_direction(request, response, Some(Direction::Response))
                     .unwrap_or(Direction::Response);
-                let schema =
-                    compose_from_payload(&payload_file, &config).map_err(cli_err(json_output))?;
-                (schema, payload_file, direction)
+                let mut schema = compose_schema(&capabilities, config).map_err(fail)?;
+                patch_schema(&mut schema, patch.as_deref(), verbose).map_err(fail)?;
+                (Some(vec![schema]), payload_file, direction)
             }
-            Some(DetectedDirection::Request) => {
+            Some(metadata @ PayloadMetadata::Profile(_)) => {
                 // JSONRPC request: meta.profile, extract nested payload
                 let direction = determine_direction(request, response, Some(Direction::Request))
                     .unwrap_or(Direction::Request);
 
-                // Get profile URL from meta.profile
-                let profile = payload_file
-                    .get("meta")
-                    .and_then(|m| m.get("profile"))
-                    .and_then(|p| p.as_str())
-                    .ok_or_else(|| {
-                        report_error(json_output, "JSONRPC request missing meta.profile");
-                        2u8
+                let profile = metadata
+                    .profile_url()
+                    .ok_or_else(|| ValidateFailure::Error {
+                        code: 2,
+                        message: "JSONRPC request missing meta.profile".to_string(),
                     })?;
 
                 if verbose {
                     eprintln!("[detect] JSONRPC request: fetching profile {}", profile);
                 }
 
-                let capabilities = extract_capabilities_from_profile(profile, &config)
-                    .map_err(cli_err(json_output))?;
+                let capabilities =
+                    extract_capabilities_from_profile(profile, config).map_err(fail)?;
 
                 // Extract actual payload from envelope (e.g., "checkout" key)
-                let (nested_payload, _key) = extract_jsonrpc_payload(&payload_file, &capabilities)
-                    .map_err(cli_err(json_output))?;
+                let (nested_payload, _key) =
+                    extract_jsonrpc_payload(&payload_file, &capabilities).map_err(fail)?;
 
                 if verbose {
                     eprintln!(
@@ -646,71 +1760,48 @@ fn run_validate(args: ValidateArgs) -> Result<(), u8> {
                         capabilities.len()
                     );
                 }
-                let schema =
-                    compose_schema(&capabilities, &config).map_err(cli_err(json_output))?;
+                if *explain_compose {
+                    explain_capabilities(&capabilities, config);
+                }
+                let mut schema = compose_schema(&capabilities, config).map_err(fail)?;
+                patch_schema(&mut schema, patch.as_deref(), verbose).map_err(fail)?;
 
-                (schema, nested_payload.clone(), direction)
+                (Some(vec![schema]), nested_payload.clone(), direction)
             }
             None => {
-                report_error(
-                    json_output,
-                    "cannot infer direction: payload has no ucp.capabilities (response) or meta.profile (request). Use --schema, --profile, --request, or --response.",
-                );
-                return Err(2);
+                return Err(ValidateFailure::Error {
+                    code: 2,
+                    message: "cannot infer direction: payload has no ucp.capabilities (response) or meta.profile (request). Use --schema, --profile, --request, or --response.".to_string(),
+                });
             }
         }
     };
 
-    let options = ResolveOptions::new(direction, op)
-        .strict(strict)
-        .def_name(def);
-    if verbose {
-        eprintln!(
-            "[resolve] resolving for {}/{}",
-            direction
-                .annotation_key()
-                .strip_prefix("ucp_")
-                .unwrap_or(direction.annotation_key()),
-            options.operation
-        );
-        eprintln!("[validate] validating payload against resolved schema");
-    }
-
-    match validate(&schema, &payload, &options) {
-        Ok(()) => {
-            if json_output {
-                println!(r#"{{"valid":true}}"#);
-            } else {
-                println!("Valid");
+    // The `ucp` envelope describes the payload rather than belonging to the
+    // domain schema; dropping it lets strict mode close the root object.
+    if *ignore_ucp_envelope {
+        if let Some(obj) = payload.as_object_mut() {
+            if obj.remove("ucp").is_some() && verbose {
+                eprintln!("[validate] ignoring top-level ucp envelope");
             }
-            Ok(())
-        }
-        Err(ValidateError::Invalid { errors, .. }) => {
-            if json_output {
-                let output = serde_json::json!({
-                    "valid": false,
-                    "errors": errors
-                });
-                println!("{}", output);
-            } else {
-                eprintln!("Validation failed:");
-                for error in errors {
-                    eprintln!("  {}", error);
-                }
-            }
-            Err(1)
-        }
-        Err(ValidateError::Resolve(e)) => {
-            report_error(json_output, &e.to_string());
-            Err(e.exit_code() as u8)
         }
     }
+
+    let own = match schemas {
+        Some(schemas) => {
+            check_schemas(&schemas, args)?;
+            Some(ValidationTarget::new(schemas))
+        }
+        None => None,
+    };
+    Ok((own, payload, direction))
 }
 
 /// Shared helper: serialize JSON and write to output or stdout.
 fn write_json_output(
     value: &serde_json::Value,
     output: Option<PathBuf>,
+    dry_run: bool,
     pretty: bool,
 ) -> Result<(), u8> {
     let json = if pretty {
@@ -724,6 +1815,19 @@ fn write_json_output(
     })?;
 
     match output {
+        Some(path) if dry_run => {
+            let status = match std::fs::read(&path) {
+                Ok(existing) if existing == json.as_bytes() => "unchanged".to_string(),
+                Ok(existing) => format!("replaces {} bytes", existing.len()),
+                Err(_) => "new file".to_string(),
+            };
+            println!(
+                "[dry-run] would write {} bytes to {} ({})",
+                json.len(),
+                path.display(),
+                status
+            );
+        }
         Some(path) => {
             std::fs::write(&path, &json).map_err(|e| {
                 eprintln!("Error writing to {}: {}", path.display(), e);
@@ -739,42 +1843,151 @@ fn write_json_output(
 }
 
 /// Print capability details to stderr for --verbose mode.
-/// Best-effort: silently skips if extraction fails (errors surface later).
-fn verbose_capabilities(payload: &serde_json::Value, config: &SchemaBaseConfig) {
-    if let Ok(caps) = extract_capabilities(payload, config) {
-        let roots: Vec<_> = caps.iter().filter(|c| c.extends.is_none()).collect();
-        let exts: Vec<_> = caps.iter().filter(|c| c.extends.is_some()).collect();
-        eprintln!(
-            "[detect] payload with {} capabilities ({} root, {} extensions)",
-            caps.len(),
-            roots.len(),
-            exts.len()
-        );
-        for cap in &caps {
-            let kind = if cap.extends.is_some() { "ext" } else { "root" };
-            eprintln!("[detect]   {} {} → {}", kind, cap.name, cap.schema_url);
+fn verbose_capabilities(caps: &[Capability], config: &SchemaBaseConfig) {
+    let roots = caps.iter().filter(|c| c.extends.is_none()).count();
+    eprintln!(
+        "[detect] payload with {} capabilities ({} root, {} extensions)",
+        caps.len(),
+        roots,
+        caps.len() - roots
+    );
+    for cap in caps {
+        let kind = if cap.extends.is_some() { "ext" } else { "root" };
+        match config.overrides.and_then(|o| o.get(&cap.name)) {
+            Some(path) => eprintln!(
+                "[detect]   {} {} → {} (override)",
+                kind,
+                cap.name,
+                path.display()
+            ),
+            None => eprintln!("[detect]   {} {} → {}", kind, cap.name, cap.schema_url),
         }
     }
 }
 
+/// Print where each capability's schema is loaded from, for --explain.
+///
+/// Runs before composing, so on a fetch failure the last lines printed show
+/// what was tried.
+fn explain_capabilities(caps: &[Capability], config: &SchemaBaseConfig) {
+    let file = |path: &Path| {
+        let missing = if path.is_file() { "" } else { " (not found)" };
+        format!("{}{}", path.display(), missing)
+    };
+    for cap in caps {
+        eprintln!("[explain] {}", cap.name);
+        eprintln!("[explain]   schema_url: {}", cap.schema_url);
+        let (mapping, source) = match capability_schema_source(cap, config) {
+            Ok(SchemaSource::Override(path)) => ("--schema-override".to_string(), file(&path)),
+            Ok(SchemaSource::Mapped { path, stripped }) => {
+                let base = config.local_base.unwrap_or(Path::new("")).display();
+                let mapping = match stripped {
+                    Some(prefix) => format!("strip {}, join onto {}", prefix, base),
+                    None => format!("URL path joined onto {}", base),
+                };
+                (mapping, file(&path))
+            }
+            Ok(SchemaSource::Remote(url)) => ("none (HTTP fetch)".to_string(), url),
+            Ok(SchemaSource::File(path)) => ("none (file path)".to_string(), file(&path)),
+            Err(e) => ("failed".to_string(), e.to_string()),
+        };
+        eprintln!("[explain]   mapping:    {}", mapping);
+        eprintln!("[explain]   source:     {}", source);
+    }
+}
+
 /// Bundle refs for a local schema file.
 fn bundle_local_refs(
     schema: &mut serde_json::Value,
     source: &str,
+    base_dir: Option<&Path>,
     schema_local_base: &Option<PathBuf>,
     schema_remote_base: &Option<String>,
-    json_output: bool,
-) -> Result<(), u8> {
-    let schema_dir = Path::new(source).parent().unwrap_or(Path::new("."));
-
-    if let (Some(local_base), Some(remote_base)) = (schema_local_base, schema_remote_base) {
+    trace_ref: bool,
+) -> Result<(), ResolveError> {
+    let schema_dir =
+        base_dir.unwrap_or_else(|| Path::new(source).parent().unwrap_or(Path::new(".")));
+
+    if trace_ref {
+        let mapping = schema_local_base
+            .as_deref()
+            .zip(schema_remote_base.as_deref());
+        bundle_refs_traced(schema, schema_dir, mapping, &mut |reference, resolved| {
+            eprintln!("[trace-ref] {} -> {}", reference, resolved)
+        })
+    } else if let (Some(local_base), Some(remote_base)) = (schema_local_base, schema_remote_base) {
         bundle_refs_with_url_mapping(schema, schema_dir, local_base, remote_base)
-            .map_err(cli_err_ctx(json_output, "bundling refs"))?;
     } else {
-        bundle_refs(schema, schema_dir).map_err(cli_err_ctx(json_output, "bundling refs"))?;
+        bundle_refs(schema, schema_dir)
     }
+}
 
-    Ok(())
+/// Load the `--envelope-schema` file, with its relative `$ref`s bundled.
+fn load_envelope_schema(
+    path: Option<&Path>,
+    schema_local_base: &Option<PathBuf>,
+    schema_remote_base: &Option<String>,
+) -> Result<Option<serde_json::Value>, ResolveError> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let mut schema = load_schema(path)?;
+    bundle_local_refs(
+        &mut schema,
+        &path.to_string_lossy(),
+        None,
+        schema_local_base,
+        schema_remote_base,
+        false,
+    )?;
+    Ok(Some(schema))
+}
+
+/// Load the `resolve` input from a file, a URL, or stdin (`-`).
+///
+/// Stdin has no extension to detect the format from, so it is JSON unless
+/// `--input-format` says otherwise.
+fn load_resolve_input(
+    source: &str,
+    input_format: Option<InputFormat>,
+) -> Result<serde_json::Value, ResolveError> {
+    if source != "-" {
+        return load_schema_auto_as(source, input_format);
+    }
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|source| ResolveError::ReadError {
+            path: PathBuf::from("<stdin>"),
+            source,
+        })?;
+    load_schema_str_as(&content, input_format.unwrap_or(InputFormat::Json))
+}
+
+/// Apply the `--patch` JSON Patch file, if given, to a loaded schema.
+fn patch_schema(
+    schema: &mut serde_json::Value,
+    patch: Option<&Path>,
+    verbose: bool,
+) -> Result<(), ResolveError> {
+    let Some(path) = patch else {
+        return Ok(());
+    };
+    if verbose {
+        eprintln!("[patch] applying {}", path.display());
+    }
+    #[cfg(feature = "patch")]
+    {
+        let document = load_schema(path)?;
+        apply_patch(schema, &document)
+    }
+    #[cfg(not(feature = "patch"))]
+    {
+        let _ = schema;
+        Err(ResolveError::InvalidPatch {
+            message: "this build does not support --patch (enable the `patch` feature)".to_string(),
+        })
+    }
 }
 
 /// Output an error message in plain text or JSON format.
@@ -793,18 +2006,88 @@ fn report_error(json_output: bool, msg: &str) {
     }
 }
 
-fn run_lint(path: &Path, format: &str, strict: bool, quiet: bool) -> Result<(), u8> {
-    use ucp_schema::Severity;
+/// Nearest lint config file in the linted directory (or a linted file's
+/// directory) and its ancestors.
+fn find_lint_config(path: &Path) -> Option<PathBuf> {
+    let start = if path.is_dir() {
+        path
+    } else {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        }
+    };
+    let start = std::fs::canonicalize(start).ok()?;
+    start.ancestors().find_map(|dir| {
+        LINT_CONFIG_FILES
+            .iter()
+            .filter(|name| cfg!(feature = "toml") || !name.ends_with(".toml"))
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Load lint options from `explicit`, or from a discovered config file;
+/// defaults when there is neither.
+fn load_lint_config(path: &Path, explicit: Option<&Path>) -> Result<LintOptions, u8> {
+    let file = match explicit {
+        Some(file) => file.to_path_buf(),
+        None => match find_lint_config(path) {
+            Some(file) => file,
+            None => return Ok(LintOptions::default()),
+        },
+    };
+    let text = std::fs::read_to_string(&file).map_err(|e| {
+        eprintln!("Error: cannot read lint config {}: {}", file.display(), e);
+        2u8
+    })?;
+    let parsed: Result<LintOptions, String> = match file.extension().and_then(|e| e.to_str()) {
+        #[cfg(feature = "toml")]
+        Some("toml") => toml::from_str(&text).map_err(|e| e.to_string()),
+        _ => serde_json::from_str(&text).map_err(|e| e.to_string()),
+    };
+    parsed
+        .and_then(|options| {
+            for pattern in &options.exclude {
+                parse_exclude_pattern(pattern)?;
+            }
+            Ok(options)
+        })
+        .map_err(|e| {
+            eprintln!("Error: invalid lint config {}: {}", file.display(), e);
+            2u8
+        })
+}
+
+/// Lint `path` and report the results in `format`.
+///
+/// `only` limits which diagnostics are printed (text) or listed (JSON);
+/// counts, statuses, and the exit code still reflect every diagnostic.
+fn run_lint(
+    path: &Path,
+    format: &str,
+    quiet: bool,
+    only: Option<Severity>,
+    error_on_empty: bool,
+    options: &LintOptions,
+) -> Result<(), u8> {
+    let strict = options.strict;
 
     if !path.exists() {
         eprintln!("Error: path not found: {}", path.display());
         return Err(2);
     }
 
-    let result = lint(path, strict);
+    let result = lint_with_options(path, options);
 
     if format == "json" {
-        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        let mut shown = result.clone();
+        if let Some(only) = only {
+            for file_result in &mut shown.results {
+                file_result.diagnostics.retain(|diag| diag.severity == only);
+            }
+        }
+        println!("{}", serde_json::to_string_pretty(&shown).unwrap());
     } else {
         // Text output
         if !quiet {
@@ -821,20 +2104,24 @@ fn run_lint(path: &Path, format: &str, strict: bool, quiet: bool) -> Result<(),
             if !quiet || file_result.status != FileStatus::Ok {
                 println!("  {} {}", status_icon, file_result.file.display());
             }
+            if let (false, Some(coverage)) = (quiet, &file_result.coverage) {
+                println!("    coverage: {}", format_coverage(coverage));
+            }
 
             for diag in &file_result.diagnostics {
                 let color = match diag.severity {
                     Severity::Error => "\x1b[31m",
-                    Severity::Warning => "\x1b[33m",
+                    _ => "\x1b[33m",
+                };
+                let shown = match only {
+                    Some(only) => diag.severity == only,
+                    None => !quiet || diag.severity == Severity::Error,
                 };
-                if !quiet || diag.severity == Severity::Error {
+                if shown {
                     println!(
                         "    {}{}[{}]\x1b[0m: {} - {}",
                         color,
-                        match diag.severity {
-                            Severity::Error => "error",
-                            Severity::Warning => "warning",
-                        },
+                        diag.severity.as_str(),
                         diag.code,
                         diag.path,
                         diag.message
@@ -844,7 +2131,14 @@ fn run_lint(path: &Path, format: &str, strict: bool, quiet: bool) -> Result<(),
         }
 
         println!();
-        if result.is_ok() && (!strict || result.warnings == 0) {
+        if result.files_checked == 0 {
+            // Distinguish "nothing to lint" from "everything passed" so a path
+            // that matched nothing doesn't read as a clean run.
+            println!(
+                "\x1b[33m⚠ no schema files found in {}\x1b[0m",
+                path.display()
+            );
+        } else if result.is_ok() && (!strict || result.warnings == 0) {
             println!(
                 "\x1b[32m✓ {} files checked, all passed\x1b[0m",
                 result.files_checked
@@ -855,11 +2149,127 @@ fn run_lint(path: &Path, format: &str, strict: bool, quiet: bool) -> Result<(),
                 result.files_checked, result.passed, result.failed, result.errors, result.warnings
             );
         }
+        if let Some(coverage) = &result.coverage {
+            println!("Annotation coverage: {}", format_coverage(coverage));
+        }
     }
 
-    if result.is_ok() && (!strict || result.warnings == 0) {
+    if result.files_checked == 0 && error_on_empty {
+        Err(1)
+    } else if result.is_ok() && (!strict || result.warnings == 0) {
         Ok(())
     } else {
         Err(1)
     }
 }
+
+/// One-line summary of an [`AnnotationCoverage`].
+fn format_coverage(coverage: &AnnotationCoverage) -> String {
+    format!(
+        "{}/{} properties annotated ({:.1}%); ucp_request {}, ucp_response {}; \
+         {} shorthand, {} per-operation",
+        coverage.annotated,
+        coverage.properties,
+        coverage.percent(),
+        coverage.request,
+        coverage.response,
+        coverage.shorthand,
+        coverage.per_operation
+    )
+}
+
+/// Write a completion script for `shell`, generated from the clap definition.
+fn run_completions(shell: clap_complete::Shell) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+}
+
+/// Sample schemas embedded for `selftest`, by the name cases refer to.
+const SELFTEST_SCHEMAS: &[(&str, &str)] = &[
+    ("checkout", include_str!("selftest/checkout.json")),
+    ("buyer", include_str!("selftest/buyer.json")),
+];
+
+/// Expected `properties` and `required` per (schema, direction, op).
+const SELFTEST_CASES: &str = include_str!("selftest/cases.json");
+
+/// Resolve the embedded schemas for each case and compare against the
+/// expected shape. Needs no files, so it only exercises the binary itself.
+fn run_selftest() -> Result<(), u8> {
+    let cases: Vec<serde_json::Value> =
+        serde_json::from_str(SELFTEST_CASES).expect("embedded selftest cases are valid JSON");
+
+    let mut failed = 0;
+    for case in &cases {
+        let label = format!(
+            "{} {}/{}",
+            case["schema"].as_str().unwrap_or("?"),
+            case["direction"].as_str().unwrap_or("?"),
+            case["op"].as_str().unwrap_or("?")
+        );
+        match check_selftest_case(case) {
+            Ok(()) => println!("  \x1b[32m✓\x1b[0m {}", label),
+            Err(reason) => {
+                failed += 1;
+                println!("  \x1b[31m✗\x1b[0m {}: {}", label, reason);
+            }
+        }
+    }
+
+    println!();
+    if failed == 0 {
+        println!("\x1b[32m✓ {} checks passed\x1b[0m", cases.len());
+        Ok(())
+    } else {
+        println!(
+            "\x1b[31m✗ {} of {} checks failed\x1b[0m",
+            failed,
+            cases.len()
+        );
+        Err(1)
+    }
+}
+
+fn check_selftest_case(case: &serde_json::Value) -> Result<(), String> {
+    let name = case["schema"].as_str().unwrap_or_default();
+    let source = SELFTEST_SCHEMAS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, source)| *source)
+        .ok_or_else(|| format!("no embedded schema '{}'", name))?;
+    let schema = load_schema_str(source).map_err(|e| e.to_string())?;
+
+    let direction = Direction::from_request_flag(case["direction"] == "request");
+    let options = ResolveOptions::new(direction, case["op"].as_str().unwrap_or_default());
+    let resolved = resolve(&schema, &options).map_err(|e| e.to_string())?;
+    if strip_annotations(&resolved) != resolved {
+        return Err("ucp_* annotations left in output".to_string());
+    }
+
+    let at = case["at"].as_str().unwrap_or_default();
+    let target = resolved
+        .pointer(at)
+        .ok_or_else(|| format!("nothing at {}", at))?;
+    let names = |value: &serde_json::Value| -> Vec<String> {
+        let mut names: Vec<String> = match value {
+            serde_json::Value::Object(map) => map.keys().cloned().collect(),
+            serde_json::Value::Array(arr) => arr
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        };
+        names.sort();
+        names
+    };
+
+    for key in ["properties", "required"] {
+        let expected = names(&case[key]);
+        let actual = names(&target[key]);
+        if expected != actual {
+            return Err(format!("{} expected {:?}, got {:?}", key, expected, actual));
+        }
+    }
+    Ok(())
+}
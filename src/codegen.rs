@@ -0,0 +1,301 @@
+//! Flattened output for code generators.
+//!
+//! Type generators in the `json-schema-to-typescript` mould read a schema as
+//! one object tree: they follow `$ref`s poorly across `$defs`, turn every
+//! `allOf` into an intersection type, and reject or mistype 2020-12
+//! applicators. This module rewrites a resolved schema into that shape.
+//!
+//! Simplifications:
+//! - Internal `#/...` refs are inlined and `$defs`/`definitions` removed;
+//!   recursive refs stay as `$ref` and keep `$defs` in place (with a warning)
+//! - `allOf` branches are merged into the schema that holds them:
+//!   `properties` are unioned (a property in several branches is merged the
+//!   same way), `required` is unioned, `additionalProperties: false` in any
+//!   branch wins, and for annotation keywords (`title`, `description`, ...)
+//!   the first value wins. An `allOf` whose branches disagree on any other
+//!   keyword, or still hold a `$ref`, is kept with a warning
+//! - `$schema` and `$id` are removed below the root; `$anchor`, `$comment`,
+//!   `$vocabulary`, and `x-ucp-*` metadata are removed everywhere
+//!
+//! Unsupported keywords that are dropped with a warning: see
+//! [`CODEGEN_UNSUPPORTED_KEYWORDS`]. Everything else — `anyOf`/`oneOf`,
+//! `enum`/`const`, type arrays, formats and bounds — is preserved as is.
+
+use serde_json::{Map, Value};
+
+use crate::error::ResolveError;
+use crate::loader::{collect_refs, escape_pointer_token, inline_local_refs, is_internal_ref};
+
+/// Keywords code generators cannot map to a type, dropped with a warning.
+pub const CODEGEN_UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "prefixItems",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "dependentSchemas",
+    "if",
+    "then",
+    "else",
+    "$dynamicRef",
+    "$dynamicAnchor",
+    "contentSchema",
+];
+
+/// Keywords removed without a warning at every level.
+const SILENT_KEYWORDS: &[&str] = &["$anchor", "$comment", "$vocabulary"];
+
+/// Keywords removed without a warning below the root only.
+const NESTED_SILENT_KEYWORDS: &[&str] = &["$schema", "$id"];
+
+/// Keywords where differing `allOf` branches keep the first value rather
+/// than blocking the merge.
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+/// A schema flattened for code generation and what the flattening gave up.
+#[derive(Debug, Clone)]
+pub struct CodegenFlattening {
+    pub schema: Value,
+    /// One message per dropped keyword or kept construct, prefixed with the
+    /// JSON pointer of the schema it was found in.
+    pub warnings: Vec<String>,
+}
+
+/// Flatten a resolved schema for type generators.
+///
+/// # Errors
+///
+/// Returns `BundleError` if an internal `$ref` points at a missing or
+/// non-schema value.
+pub fn flatten_for_codegen(schema: &Value) -> Result<CodegenFlattening, ResolveError> {
+    let mut schema = schema.clone();
+    let root = schema.clone();
+    inline_local_refs(&mut schema, &root)?;
+
+    let mut warnings = Vec::new();
+    let recursive = collect_refs(&schema)
+        .iter()
+        .any(|(_, reference)| is_internal_ref(reference));
+    if let Value::Object(obj) = &mut schema {
+        if recursive {
+            warnings.push("/: recursive $ref kept; $defs left in place".to_string());
+        } else {
+            obj.remove("$defs");
+            obj.remove("definitions");
+        }
+    }
+
+    flatten_schema(&mut schema, "", &mut warnings);
+    Ok(CodegenFlattening { schema, warnings })
+}
+
+/// Clean one schema object, flatten its subschemas, then merge its `allOf`.
+fn flatten_schema(schema: &mut Value, path: &str, warnings: &mut Vec<String>) {
+    let Value::Object(obj) = schema else {
+        return;
+    };
+    let at = if path.is_empty() { "/" } else { path };
+
+    for key in SILENT_KEYWORDS {
+        obj.remove(*key);
+    }
+    if !path.is_empty() {
+        for key in NESTED_SILENT_KEYWORDS {
+            obj.remove(*key);
+        }
+    }
+    obj.retain(|key, _| !key.starts_with("x-ucp-"));
+    for key in CODEGEN_UNSUPPORTED_KEYWORDS {
+        if obj.remove(*key).is_some() {
+            warnings.push(format!("{}: dropped unsupported keyword `{}`", at, key));
+        }
+    }
+
+    for key in ["properties", "patternProperties", "$defs", "definitions"] {
+        if let Some(Value::Object(map)) = obj.get_mut(key) {
+            for (name, sub) in map.iter_mut() {
+                flatten_schema(
+                    sub,
+                    &format!("{}/{}/{}", path, key, escape_pointer_token(name)),
+                    warnings,
+                );
+            }
+        }
+    }
+    for key in [
+        "items",
+        "additionalProperties",
+        "not",
+        "contains",
+        "propertyNames",
+    ] {
+        if let Some(sub) = obj.get_mut(key) {
+            flatten_schema(sub, &format!("{}/{}", path, key), warnings);
+        }
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(key) {
+            for (i, sub) in branches.iter_mut().enumerate() {
+                flatten_schema(sub, &format!("{}/{}/{}", path, key, i), warnings);
+            }
+        }
+    }
+
+    if let Err(keyword) = merge_all_of(obj) {
+        warnings.push(format!(
+            "{}: allOf kept; branches disagree on `{}`",
+            at, keyword
+        ));
+    }
+}
+
+/// Merge `allOf` branches into `obj`, leaving it untouched on a conflict.
+///
+/// Returns the keyword (as a relative path for nested properties) that
+/// blocked the merge.
+fn merge_all_of(obj: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(Value::Array(branches)) = obj.get("allOf") else {
+        return Ok(());
+    };
+
+    let mut merged = obj.clone();
+    merged.remove("allOf");
+    for branch in branches {
+        match branch {
+            Value::Bool(true) => {}
+            Value::Object(branch) if branch.contains_key("$ref") => {
+                return Err("$ref".to_string());
+            }
+            Value::Object(branch) => merge_into(&mut merged, branch)?,
+            _ => return Err("allOf".to_string()),
+        }
+    }
+    *obj = merged;
+    Ok(())
+}
+
+/// Merge `source` keywords into `target` with `allOf` semantics.
+fn merge_into(target: &mut Map<String, Value>, source: &Map<String, Value>) -> Result<(), String> {
+    for (key, value) in source {
+        let Some(existing) = target.get_mut(key) else {
+            target.insert(key.clone(), value.clone());
+            continue;
+        };
+        if existing == value || ANNOTATION_KEYWORDS.contains(&key.as_str()) {
+            continue;
+        }
+        match (key.as_str(), existing, value) {
+            ("properties", Value::Object(existing), Value::Object(props)) => {
+                for (name, sub) in props {
+                    match existing.get_mut(name) {
+                        None => {
+                            existing.insert(name.clone(), sub.clone());
+                        }
+                        Some(Value::Object(current)) => match sub {
+                            Value::Object(sub) => merge_into(current, sub)
+                                .map_err(|k| format!("properties/{}/{}", name, k))?,
+                            _ => return Err(format!("properties/{}", name)),
+                        },
+                        Some(current) if current == sub => {}
+                        Some(_) => return Err(format!("properties/{}", name)),
+                    }
+                }
+            }
+            ("required", Value::Array(existing), Value::Array(names)) => {
+                for name in names {
+                    if !existing.contains(name) {
+                        existing.push(name.clone());
+                    }
+                }
+            }
+            ("additionalProperties", existing, value)
+                if *existing == Value::Bool(false) || *value == Value::Bool(false) =>
+            {
+                *existing = Value::Bool(false);
+            }
+            _ => return Err(key.clone()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merges_allof_and_inlines_refs() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "https://example.com/checkout.json",
+            "$defs": {
+                "base": {
+                    "$id": "https://example.com/base.json",
+                    "type": "object",
+                    "description": "Base",
+                    "properties": { "id": { "type": "string" } },
+                    "required": ["id"],
+                    "additionalProperties": false
+                }
+            },
+            "allOf": [
+                { "$ref": "#/$defs/base" },
+                {
+                    "type": "object",
+                    "description": "Extension",
+                    "properties": {
+                        "id": { "minLength": 1 },
+                        "total": { "type": "integer", "x-ucp-schema-transition": {} }
+                    },
+                    "required": ["total"],
+                    "if": { "required": ["total"] }
+                }
+            ]
+        });
+
+        let flattened = flatten_for_codegen(&schema).unwrap();
+        assert_eq!(
+            flattened.schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$id": "https://example.com/checkout.json",
+                "type": "object",
+                "description": "Base",
+                "properties": {
+                    "id": { "type": "string", "minLength": 1 },
+                    "total": { "type": "integer" }
+                },
+                "required": ["id", "total"],
+                "additionalProperties": false
+            })
+        );
+        assert_eq!(
+            flattened.warnings,
+            vec!["/allOf/1: dropped unsupported keyword `if`"]
+        );
+    }
+
+    #[test]
+    fn conflicting_allof_is_kept() {
+        let schema = json!({
+            "properties": {
+                "amount": {
+                    "allOf": [{ "type": "integer" }, { "type": "string" }]
+                }
+            }
+        });
+        let flattened = flatten_for_codegen(&schema).unwrap();
+        assert_eq!(flattened.schema, schema);
+        assert_eq!(
+            flattened.warnings,
+            vec!["/properties/amount: allOf kept; branches disagree on `type`"]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Semantic comparison of JSON Schema values.
+//!
+//! Resolution rebuilds `required` arrays and object maps, so two schemas that
+//! mean the same thing can differ in key order or in the order of `required`
+//! entries. Comparing them with `==` then reports false differences.
+//! [`schema_fingerprint`] hashes the same order-insensitive view.
+
+#[cfg(feature = "full")]
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+#[cfg(feature = "full")]
+use sha2::{Digest, Sha256};
+
+/// Keywords whose array value is an unordered set of names or values.
+const SET_KEYWORDS: &[&str] = &["required", "enum", "type"];
+
+/// Keywords whose value is instance data rather than a subschema.
+const INSTANCE_KEYWORDS: &[&str] = &["const", "default", "examples", "enum"];
+
+/// Keywords whose object value maps names to subschemas. The names are not
+/// keywords, so a property called `default` is still a schema.
+const NAME_KEYED_KEYWORDS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+];
+
+/// Compare two JSON Schema values semantically.
+///
+/// - Objects are equal when they have the same key set and equal values,
+///   regardless of key order.
+/// - `required`, `enum`, and array-form `type` are compared as sets.
+/// - Instance data (`const`, `default`, `examples`, `enum` members) is
+///   compared structurally, without set semantics.
+/// - Everything else, including other arrays such as `allOf` branches, is
+///   compared structurally and in order.
+///
+/// ```
+/// use serde_json::json;
+/// use ucp_schema::schemas_equal;
+///
+/// let a = json!({ "type": "object", "required": ["id", "name"] });
+/// let b = json!({ "required": ["name", "id"], "type": "object" });
+/// assert!(schemas_equal(&a, &b));
+/// ```
+pub fn schemas_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            a_map.len() == b_map.len()
+                && a_map.iter().all(|(key, a_val)| {
+                    let Some(b_val) = b_map.get(key) else {
+                        return false;
+                    };
+                    if let (true, Value::Array(a_arr), Value::Array(b_arr)) =
+                        (SET_KEYWORDS.contains(&key.as_str()), a_val, b_val)
+                    {
+                        return set_equal(a_arr, b_arr);
+                    }
+                    if INSTANCE_KEYWORDS.contains(&key.as_str()) {
+                        return a_val == b_val;
+                    }
+                    if let (true, Value::Object(a_map), Value::Object(b_map)) =
+                        (NAME_KEYED_KEYWORDS.contains(&key.as_str()), a_val, b_val)
+                    {
+                        return schema_maps_equal(a_map, b_map);
+                    }
+                    schemas_equal(a_val, b_val)
+                })
+        }
+        (Value::Array(a_arr), Value::Array(b_arr)) => {
+            a_arr.len() == b_arr.len()
+                && a_arr
+                    .iter()
+                    .zip(b_arr.iter())
+                    .all(|(x, y)| schemas_equal(x, y))
+        }
+        _ => a == b,
+    }
+}
+
+/// Compare two name-to-subschema maps: same names, equal subschemas.
+fn schema_maps_equal(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(name, a_val)| b.get(name).is_some_and(|b_val| schemas_equal(a_val, b_val)))
+}
+
+/// Unordered comparison; duplicates are insignificant.
+fn set_equal(a: &[Value], b: &[Value]) -> bool {
+    a.iter().all(|x| b.contains(x)) && b.iter().all(|y| a.contains(y))
+}
+
+/// Stable content hash of a schema: the SHA-256 of its canonical form, as 64
+/// lowercase hex digits.
+///
+/// The canonical form follows [`schemas_equal`], so schemas it considers
+/// equal share a fingerprint: object keys are sorted, `required`, `enum`, and
+/// array-form `type` are sorted and deduplicated, and formatting is compact.
+/// Other arrays, such as `allOf` branches, keep their order. Suitable as a
+/// cache key or for telling a changed resolved schema from a reformatted one.
+///
+/// ```
+/// use serde_json::json;
+/// use ucp_schema::schema_fingerprint;
+///
+/// let a = json!({ "type": "object", "required": ["id", "name"] });
+/// let b = json!({ "required": ["name", "id"], "type": "object" });
+/// assert_eq!(schema_fingerprint(&a), schema_fingerprint(&b));
+/// ```
+#[cfg(feature = "full")]
+pub fn schema_fingerprint(schema: &Value) -> String {
+    let mut canonical = String::new();
+    write_canonical(schema, false, &mut canonical);
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+/// Append the canonical JSON text of `value` to `out`. `instance` marks
+/// payload data (under `const`, `default`, ...), where keyword names carry
+/// no schema meaning.
+#[cfg(feature = "full")]
+fn write_canonical(value: &Value, instance: bool, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                match &map[key] {
+                    Value::Array(members) if !instance && SET_KEYWORDS.contains(&key.as_str()) => {
+                        let mut members: Vec<String> = members
+                            .iter()
+                            .map(|member| {
+                                let mut text = String::new();
+                                write_canonical(member, true, &mut text);
+                                text
+                            })
+                            .collect();
+                        members.sort();
+                        members.dedup();
+                        out.push('[');
+                        out.push_str(&members.join(","));
+                        out.push(']');
+                    }
+                    Value::Object(subschemas)
+                        if !instance && NAME_KEYED_KEYWORDS.contains(&key.as_str()) =>
+                    {
+                        write_schema_map(subschemas, out);
+                    }
+                    child => {
+                        let instance = instance || INSTANCE_KEYWORDS.contains(&key.as_str());
+                        write_canonical(child, instance, out);
+                    }
+                }
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, instance, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// Append the canonical text of a name-to-subschema map: names sorted, each
+/// value canonicalized as a schema.
+#[cfg(feature = "full")]
+fn write_schema_map(subschemas: &Map<String, Value>, out: &mut String) {
+    let mut names: Vec<&String> = subschemas.keys().collect();
+    names.sort();
+    out.push('{');
+    for (i, name) in names.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&Value::String(name.clone()).to_string());
+        out.push(':');
+        write_canonical(&subschemas[name], false, out);
+    }
+    out.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ignores_key_order() {
+        let a = json!({ "type": "object", "properties": { "a": {}, "b": {} } });
+        let b = json!({ "properties": { "b": {}, "a": {} }, "type": "object" });
+        assert!(schemas_equal(&a, &b));
+    }
+
+    #[test]
+    fn required_and_enum_are_sets() {
+        let a = json!({ "required": ["id", "name"], "enum": ["x", "y"] });
+        let b = json!({ "required": ["name", "id"], "enum": ["y", "x"] });
+        assert!(schemas_equal(&a, &b));
+
+        let c = json!({ "required": ["id"], "enum": ["x", "y"] });
+        assert!(!schemas_equal(&a, &c));
+    }
+
+    #[test]
+    fn type_array_is_a_set() {
+        assert!(schemas_equal(
+            &json!({ "type": ["string", "null"] }),
+            &json!({ "type": ["null", "string"] })
+        ));
+    }
+
+    #[test]
+    fn composition_branches_are_ordered() {
+        let a = json!({ "allOf": [{ "type": "string" }, { "minLength": 1 }] });
+        let b = json!({ "allOf": [{ "minLength": 1 }, { "type": "string" }] });
+        assert!(!schemas_equal(&a, &b));
+    }
+
+    #[test]
+    fn instance_data_is_structural() {
+        // A `required` key inside `const` is payload data, not the keyword.
+        let a = json!({ "const": { "required": ["a", "b"] } });
+        let b = json!({ "const": { "required": ["b", "a"] } });
+        assert!(!schemas_equal(&a, &b));
+    }
+
+    #[test]
+    fn property_names_are_not_keywords() {
+        let a = json!({ "properties": { "default": { "type": ["string", "null"] } } });
+        let b = json!({ "properties": { "default": { "type": ["null", "string"] } } });
+        assert!(schemas_equal(&a, &b));
+        #[cfg(feature = "full")]
+        assert_eq!(schema_fingerprint(&a), schema_fingerprint(&b));
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn fingerprint_ignores_formatting_and_set_order() {
+        let a: Value = serde_json::from_str(
+            r#"{"type": "object", "required": ["id", "name"],
+                "properties": {"id": {"type": "string"}, "name": {"type": ["string", "null"]}}}"#,
+        )
+        .unwrap();
+        let b: Value = serde_json::from_str(
+            r#"{"properties":{"name":{"type":["null","string"]},"id":{"type":"string"}},
+                "required":["name","id","id"],"type":"object"}"#,
+        )
+        .unwrap();
+        assert_eq!(schema_fingerprint(&a), schema_fingerprint(&b));
+
+        // SHA-256 of the canonical text "{}"
+        assert_eq!(
+            schema_fingerprint(&json!({})),
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn fingerprint_keeps_ordered_and_instance_arrays() {
+        let a = json!({ "allOf": [{ "type": "string" }, { "minLength": 1 }] });
+        let b = json!({ "allOf": [{ "minLength": 1 }, { "type": "string" }] });
+        assert_ne!(schema_fingerprint(&a), schema_fingerprint(&b));
+
+        let a = json!({ "const": { "required": ["a", "b"] } });
+        let b = json!({ "const": { "required": ["b", "a"] } });
+        assert_ne!(schema_fingerprint(&a), schema_fingerprint(&b));
+    }
+
+    #[test]
+    fn differing_keys_not_equal() {
+        assert!(!schemas_equal(
+            &json!({ "type": "object" }),
+            &json!({ "type": "object", "required": [] })
+        ));
+    }
+}
@@ -39,16 +39,23 @@
 //! simulate this pattern.
 
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
 
-use crate::error::ComposeError;
-use crate::loader::{bundle_refs, bundle_refs_with_url_mapping, is_url, load_schema};
-use crate::types::{Direction, Requires, VersionConstraint};
+use crate::error::{ComposeError, ResolveError};
+use crate::loader::{
+    bundle_refs, bundle_refs_with_url_mapping, inline_local_refs, is_url, load_schema_as,
+    InputFormat, LoadOptions,
+};
+use crate::types::{Direction, Requires, VersionConstraint, UCP_ANNOTATIONS};
 
 #[cfg(feature = "remote")]
-use crate::loader::{bundle_refs_remote, load_schema_url};
+use crate::loader::{bundle_refs_remote, load_schema_url_with, FetchedSchema};
+
+#[cfg(feature = "async")]
+use crate::loader::{bundle_refs_remote_async, load_schema_url_with_async};
 
 /// Configuration for mapping schema URLs to local paths.
 ///
@@ -60,27 +67,158 @@ use crate::loader::{bundle_refs_remote, load_schema_url};
 /// - `local_base`: `source`
 /// - URL: `https://ucp.dev/draft/schemas/checkout.json`
 /// - Result: `source/schemas/checkout.json`
+///
+/// `overrides` maps a capability name to a local schema file that is loaded
+/// instead of the capability's declared schema URL, so one capability can be
+/// pointed at a local draft while the rest resolve normally.
+///
+/// `strip_extension_ids` removes `$id` and `$anchor` from the extension
+/// branches inlined into the composed `allOf`. Each extension file carries its
+/// own `$id`, and some validators treat every one of them as a new base URI;
+/// stripping leaves only the root schema's identifiers in place. `$schema` and
+/// instance data (`const`, `default`, `enum`, `examples`) are left alone.
+///
+/// `relax_closed_extensions` drops `additionalProperties: false` (and
+/// `unevaluatedProperties: false`) from the conjunctive positions of each
+/// extension branch: the branch itself, its `allOf` members, and for a
+/// container each operation shape. Under the composed `allOf` a closed branch
+/// only knows its own properties and rejects every field another branch adds.
+/// Left off, such branches are kept and reported with a `warn` trace event,
+/// or rejected with [`ComposeError::ClosedExtensionBranch`] under
+/// `reject_closed_extensions`.
+///
+/// `top_level_extensions` accepts extensions that express their contribution
+/// as a top-level `allOf` or `properties` instead of a `$defs[<root>]` entry:
+/// when the entry is missing, the extension schema itself (minus its `$defs`,
+/// with internal refs inlined) becomes the branch. Single-object roots only;
+/// off by default, so a missing entry stays [`ComposeError::MissingDefEntry`].
+///
+/// `check_satisfiable` rejects compositions where one branch (the root or an
+/// extension) requires a property that another forbids with `"prop": false`
+/// or `"not": { "required": [...] }`, which no payload can satisfy. Only the
+/// branches' own level is compared (looking through nested `allOf`, not
+/// `$ref`s), and a `required` entry whose property carries a UCP annotation is
+/// skipped, since resolution may drop it. For containers each operation shape
+/// is checked separately. Off by default.
+///
+/// `capability_id_pointer`, when set, is a JSON Pointer (e.g. `/name` or
+/// `/x-ucp-capability`) at which every loaded capability schema must declare
+/// the capability's own name; anything else is
+/// [`ComposeError::CapabilityIdMismatch`]. It catches a capability wired to
+/// another capability's schema. Unset by default.
+///
+/// `envelope`, when set, is a base schema for what every message carries
+/// besides its capabilities: the `ucp` metadata object and common fields such
+/// as `id` or `links`. It is added to the composition as one more `allOf`
+/// branch (for a container, to each `{op}_{direction}` shape), so strict
+/// validation accepts those fields instead of rejecting them as unknown.
+/// Internal refs in it are inlined. Unset by default.
+///
+/// `payload_pointers` says where payloads carry their capabilities and
+/// profile; the default is standard UCP (see [`PayloadPointers`]).
+///
+/// Start from `SchemaBaseConfig::default()` and set the fields you need;
+/// new fields may be added in minor releases.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct SchemaBaseConfig<'a> {
     /// Local directory containing schema files.
     pub local_base: Option<&'a Path>,
     /// URL prefix to strip when mapping to local paths.
     pub remote_base: Option<&'a str>,
+    /// Per-capability schema files, keyed by capability name. Consulted before
+    /// any URL-to-path mapping.
+    pub overrides: Option<&'a HashMap<String, PathBuf>>,
+    /// Drop `$id`/`$anchor` from inlined extension branches.
+    pub strip_extension_ids: bool,
+    /// Remove closing `additionalProperties: false` from extension branches.
+    pub relax_closed_extensions: bool,
+    /// Fail with [`ComposeError::ClosedExtensionBranch`] on a closed extension
+    /// branch instead of warning. Has no effect with `relax_closed_extensions`.
+    pub reject_closed_extensions: bool,
+    /// Fall back to an extension's top-level `allOf`/`properties` when it has
+    /// no `$defs[<root>]` entry.
+    pub top_level_extensions: bool,
+    /// Fail with [`ComposeError::UnsatisfiableComposition`] when one branch
+    /// requires a property another forbids.
+    pub check_satisfiable: bool,
+    /// Where each capability schema declares its capability name, if checked.
+    pub capability_id_pointer: Option<&'a str>,
+    /// Base envelope schema added to the composition as an `allOf` branch.
+    pub envelope: Option<&'a Value>,
+    /// Where [`extract_capabilities`] looks for self-describing metadata.
+    pub payload_pointers: PayloadPointers<'a>,
+    /// How capability schemas are loaded: the redirect limit for URLs, and
+    /// an explicit format for files (see [`LoadOptions`]).
+    pub load_options: LoadOptions,
+}
+
+/// JSON Pointers (RFC 6901) to a payload's self-describing metadata.
+///
+/// The default is standard UCP: inline capabilities at `/ucp/capabilities`
+/// (response pattern) and a profile URL at `/meta/profile` (JSONRPC request
+/// pattern). Near-UCP dialects that nest them elsewhere, e.g.
+/// `/meta/capabilities`, can point there instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadPointers<'a> {
+    /// Location of the inline capabilities object.
+    pub capabilities: &'a str,
+    /// Location of the profile URL.
+    pub profile: &'a str,
+}
+
+impl Default for PayloadPointers<'_> {
+    fn default() -> Self {
+        Self {
+            capabilities: "/ucp/capabilities",
+            profile: "/meta/profile",
+        }
+    }
 }
 
 /// Capability declaration extracted from UCP metadata.
-#[derive(Debug, Clone)]
+///
+/// Serializes as `{"name", "version", "schema", "extends"?}`: `schema_url`
+/// uses the metadata's `schema` key and `extends` is always an array, omitted
+/// for roots. Deserializing also accepts `extends` as a single string, as UCP
+/// metadata allows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Capability {
     /// Reverse-domain capability name (e.g., "dev.ucp.shopping.checkout").
     pub name: String,
     /// Version string (e.g., "2026-01-11").
     pub version: String,
     /// URL to the JSON Schema for this capability.
+    #[serde(rename = "schema")]
     pub schema_url: String,
     /// Parent capability names this extends. None for root capabilities.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_extends"
+    )]
     pub extends: Option<Vec<String>>,
 }
 
+/// Accept `extends` as a string or an array of strings.
+fn deserialize_extends<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Extends {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Extends>::deserialize(deserializer)? {
+        None => None,
+        Some(Extends::One(parent)) => Some(vec![parent]),
+        Some(Extends::Many(parents)) => Some(parents),
+    })
+}
+
 /// Detected payload direction based on UCP metadata structure.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DetectedDirection {
@@ -90,6 +228,17 @@ pub enum DetectedDirection {
     Request,
 }
 
+/// Lets a detected direction feed straight into [`ResolveOptions`](crate::ResolveOptions).
+///
+/// ```
+/// use serde_json::json;
+/// use ucp_schema::{detect_direction, Direction, ResolveOptions};
+///
+/// let payload = json!({ "meta": { "profile": "https://example.com/.well-known/ucp" } });
+/// let direction = detect_direction(&payload).map(Direction::from).unwrap();
+/// let options = ResolveOptions::new(direction, "create");
+/// assert_eq!(options.direction, Direction::Request);
+/// ```
 impl From<DetectedDirection> for Direction {
     fn from(d: DetectedDirection) -> Self {
         match d {
@@ -99,25 +248,93 @@ impl From<DetectedDirection> for Direction {
     }
 }
 
+/// The self-describing metadata located in a payload.
+///
+/// Found once by [`payload_metadata`], it answers both the direction and the
+/// capability set, so callers that need both don't inspect the payload twice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadMetadata<'a> {
+    /// `ucp.capabilities` inline (response pattern).
+    Capabilities(&'a Value),
+    /// `meta.profile` at root (JSONRPC request pattern).
+    Profile(&'a Value),
+}
+
+impl<'a> PayloadMetadata<'a> {
+    /// Direction implied by where the metadata was found.
+    pub fn direction(&self) -> DetectedDirection {
+        match self {
+            PayloadMetadata::Capabilities(_) => DetectedDirection::Response,
+            PayloadMetadata::Profile(_) => DetectedDirection::Request,
+        }
+    }
+
+    /// Profile URL for the request pattern, if it is a string.
+    pub fn profile_url(&self) -> Option<&'a str> {
+        match self {
+            PayloadMetadata::Capabilities(_) => None,
+            PayloadMetadata::Profile(profile) => profile.as_str(),
+        }
+    }
+
+    /// Capabilities declared by the metadata.
+    ///
+    /// Inline capabilities are parsed directly; a profile URL is fetched and
+    /// its `ucp.capabilities` parsed.
+    pub fn capabilities(
+        &self,
+        schema_base: &SchemaBaseConfig,
+    ) -> Result<Vec<Capability>, ComposeError> {
+        match self {
+            PayloadMetadata::Capabilities(caps) => parse_capabilities_object(caps),
+            PayloadMetadata::Profile(_) => match self.profile_url() {
+                Some(url) => extract_capabilities_from_profile(url, schema_base),
+                None => Err(ComposeError::NotSelfDescribing),
+            },
+        }
+    }
+}
+
+/// Locate the self-describing metadata in a payload.
+///
+/// Returns `Capabilities` if `ucp.capabilities` exists, `Profile` if
+/// `meta.profile` exists at root (JSONRPC pattern), `None` if neither is
+/// present. Inline capabilities take precedence.
+pub fn payload_metadata(payload: &Value) -> Option<PayloadMetadata<'_>> {
+    payload_metadata_at(payload, &PayloadPointers::default())
+}
+
+/// Like [`payload_metadata`], looking for the metadata at `pointers`.
+pub fn payload_metadata_at<'a>(
+    payload: &'a Value,
+    pointers: &PayloadPointers,
+) -> Option<PayloadMetadata<'a>> {
+    // Response pattern: ucp.capabilities
+    if let Some(caps) = payload.pointer(pointers.capabilities) {
+        return Some(PayloadMetadata::Capabilities(caps));
+    }
+
+    // JSONRPC request pattern: meta.profile at root (NOT ucp.meta.profile)
+    payload
+        .pointer(pointers.profile)
+        .map(PayloadMetadata::Profile)
+}
+
 /// Detect direction from payload structure.
 ///
 /// Returns `Some(Response)` if `ucp.capabilities` exists,
 /// `Some(Request)` if `meta.profile` exists at root (JSONRPC pattern),
 /// `None` if neither is present.
 pub fn detect_direction(payload: &Value) -> Option<DetectedDirection> {
-    // Response pattern: ucp.capabilities
-    if let Some(ucp) = payload.get("ucp") {
-        if ucp.get("capabilities").is_some() {
-            return Some(DetectedDirection::Response);
-        }
-    }
-
-    // JSONRPC request pattern: meta.profile at root (NOT ucp.meta.profile)
-    if payload.get("meta").and_then(|m| m.get("profile")).is_some() {
-        return Some(DetectedDirection::Request);
-    }
+    payload_metadata(payload).map(|m| m.direction())
+}
 
-    None
+/// Like [`detect_direction`], looking for the metadata at `pointers`.
+pub fn detect_direction_at(
+    payload: &Value,
+    pointers: &PayloadPointers,
+) -> Option<DetectedDirection> {
+    payload_metadata_at(payload, pointers).map(|m| m.direction())
 }
 
 /// Extract capabilities from a self-describing payload.
@@ -125,6 +342,8 @@ pub fn detect_direction(payload: &Value) -> Option<DetectedDirection> {
 /// - Response: extracts from `ucp.capabilities` directly
 /// - JSONRPC Request: fetches `meta.profile` URL, extracts from profile
 ///
+/// Both locations come from `schema_base.payload_pointers`.
+///
 /// # Arguments
 /// * `payload` - The UCP payload to extract capabilities from
 /// * `schema_base` - Configuration for mapping schema URLs to local paths
@@ -132,23 +351,9 @@ pub fn extract_capabilities(
     payload: &Value,
     schema_base: &SchemaBaseConfig,
 ) -> Result<Vec<Capability>, ComposeError> {
-    // Try response pattern first: ucp.capabilities
-    if let Some(ucp) = payload.get("ucp") {
-        if let Some(caps) = ucp.get("capabilities") {
-            return parse_capabilities_object(caps);
-        }
-    }
-
-    // Try JSONRPC request pattern: meta.profile at root
-    if let Some(profile_url) = payload
-        .get("meta")
-        .and_then(|m| m.get("profile"))
-        .and_then(|p| p.as_str())
-    {
-        return extract_capabilities_from_profile(profile_url, schema_base);
-    }
-
-    Err(ComposeError::NotSelfDescribing)
+    payload_metadata_at(payload, &schema_base.payload_pointers)
+        .ok_or(ComposeError::NotSelfDescribing)?
+        .capabilities(schema_base)
 }
 
 /// Extract capabilities from a profile URL.
@@ -159,6 +364,14 @@ pub fn extract_capabilities_from_profile(
     schema_base: &SchemaBaseConfig,
 ) -> Result<Vec<Capability>, ComposeError> {
     let profile = fetch_profile(profile_url, schema_base)?;
+    profile_capabilities(profile_url, &profile)
+}
+
+/// Parse the `ucp.capabilities` of a profile fetched from `profile_url`.
+fn profile_capabilities(
+    profile_url: &str,
+    profile: &Value,
+) -> Result<Vec<Capability>, ComposeError> {
     let caps = profile
         .get("ucp")
         .and_then(|u| u.get("capabilities"))
@@ -215,6 +428,11 @@ pub fn capability_short_name(name: &str) -> String {
 }
 
 /// Parse a capabilities object into a list of Capability structs.
+///
+/// A capability given as an array uses its first entry. One given as a map
+/// keyed by version always uses the newest version (the lexicographically
+/// greatest key, as UCP versions are ISO dates); the other entries are
+/// ignored, and there is no way to ask for an older one.
 fn parse_capabilities_object(caps: &Value) -> Result<Vec<Capability>, ComposeError> {
     let obj = caps.as_object().ok_or(ComposeError::EmptyCapabilities)?;
 
@@ -225,30 +443,51 @@ fn parse_capabilities_object(caps: &Value) -> Result<Vec<Capability>, ComposeErr
     let mut capabilities = Vec::new();
 
     for (name, versions) in obj {
-        // Each capability is an array of version entries
-        let entries = versions
-            .as_array()
-            .ok_or_else(|| ComposeError::InvalidCapability {
-                name: name.clone(),
-                message: "expected array of capability entries".to_string(),
-            })?;
-
-        // Take the first entry (version negotiation already happened)
-        let entry = entries
-            .first()
-            .ok_or_else(|| ComposeError::InvalidCapability {
-                name: name.clone(),
-                message: "empty capability array".to_string(),
-            })?;
+        // Each capability is either an array of version entries or a map from
+        // version string to entry.
+        let (entry, keyed_version) = match versions {
+            // Take the first entry (version negotiation already happened)
+            Value::Array(entries) => {
+                let entry = entries
+                    .first()
+                    .ok_or_else(|| ComposeError::InvalidCapability {
+                        name: name.clone(),
+                        message: "empty capability array".to_string(),
+                    })?;
+                (entry, None)
+            }
+            // Map form: pick the newest version. UCP versions are ISO dates,
+            // so the lexicographic maximum is the latest.
+            Value::Object(by_version) => {
+                let (version, entry) =
+                    by_version
+                        .iter()
+                        .max_by(|a, b| a.0.cmp(b.0))
+                        .ok_or_else(|| ComposeError::InvalidCapability {
+                            name: name.clone(),
+                            message: "empty capability version map".to_string(),
+                        })?;
+                (entry, Some(version.clone()))
+            }
+            _ => {
+                return Err(ComposeError::InvalidCapability {
+                    name: name.clone(),
+                    message: "expected array or version map of capability entries".to_string(),
+                });
+            }
+        };
 
-        let version = entry
-            .get("version")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ComposeError::InvalidCapability {
-                name: name.clone(),
-                message: "missing version field".to_string(),
-            })?
-            .to_string();
+        let version = match keyed_version {
+            Some(version) => version,
+            None => entry
+                .get("version")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ComposeError::InvalidCapability {
+                    name: name.clone(),
+                    message: "missing version field".to_string(),
+                })?
+                .to_string(),
+        };
 
         let schema_url = entry
             .get("schema")
@@ -400,20 +639,124 @@ pub fn check_version_constraints(
 /// 1. Finds root capability (no extends)
 /// 2. Validates graph connectivity
 /// 3. Fetches schemas and extracts $defs[root] entries
-/// 4. Composes using allOf
+/// 4. Composes using allOf, with extensions in capability-name order
 pub fn compose_schema(
     capabilities: &[Capability],
     schema_base: &SchemaBaseConfig,
+) -> Result<Value, ComposeError> {
+    compose_schema_with(capabilities, schema_base, &|cap| {
+        load_capability_schema(cap, schema_base)
+    })
+    .map(|(schema, _)| schema)
+}
+
+/// Compose capabilities that form more than one independent tree.
+///
+/// [`compose_schema`] requires a single root and rejects anything else with
+/// [`ComposeError::MultipleRootCapabilities`]. This is the explicit opt-in for
+/// payloads that intentionally carry several trees (e.g. checkout alongside a
+/// separate loyalty capability): capabilities are grouped by the root(s) they
+/// reach, each group is composed on its own, and the result is
+/// `{ "allOf": [<tree 1>, <tree 2>, ...] }` so a payload must satisfy every
+/// tree. An extension that extends parents in several trees joins each of
+/// those groups and must provide a `$defs` entry for each root.
+///
+/// With a single root the result is exactly that of [`compose_schema`]. The
+/// `allOf` form targets single-object capabilities; with several roots, a
+/// container root fails with [`ComposeError::ContainerInMultiRoot`]. Authority
+/// binding is checked for every capability before any schema is loaded.
+pub fn compose_multi(
+    capabilities: &[Capability],
+    schema_base: &SchemaBaseConfig,
 ) -> Result<Value, ComposeError> {
     if capabilities.is_empty() {
         return Err(ComposeError::EmptyCapabilities);
     }
 
-    // Authority binding: a capability's `schema` URL must originate from the
-    // namespace authority encoded in its name (spec §Authority Binding). Verify
-    // ALL capabilities before dereferencing any of them (validate-before-fetch).
-    // This is unconditional — the spec requires it and there is no opt-out;
-    // non-URL schema values (local paths) carry no origin, so they are skipped.
+    check_authority_binding(capabilities)?;
+    let cap_map: HashMap<&str, &Capability> =
+        capabilities.iter().map(|c| (c.name.as_str(), c)).collect();
+
+    for cap in capabilities {
+        for parent in cap.extends.iter().flatten() {
+            if !cap_map.contains_key(parent.as_str()) {
+                return Err(ComposeError::UnknownParent {
+                    extension: cap.name.clone(),
+                    parent: parent.clone(),
+                });
+            }
+        }
+    }
+
+    let roots: Vec<&Capability> = capabilities
+        .iter()
+        .filter(|c| c.extends.is_none())
+        .collect();
+    match roots.len() {
+        0 => return Err(ComposeError::NoRootCapability),
+        1 => return compose_schema(capabilities, schema_base),
+        _ => {}
+    }
+
+    // Every extension must belong to at least one tree.
+    for cap in capabilities.iter().filter(|c| c.extends.is_some()) {
+        if !roots.iter().any(|r| reaches_root(cap, &cap_map, &r.name)) {
+            return Err(ComposeError::OrphanExtension {
+                extension: cap.name.clone(),
+                root: roots
+                    .iter()
+                    .map(|r| r.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            });
+        }
+    }
+
+    let mut branches = Vec::with_capacity(roots.len());
+    for root in &roots {
+        let in_tree = |name: &str| {
+            name == root.name
+                || cap_map
+                    .get(name)
+                    .is_some_and(|c| reaches_root(c, &cap_map, &root.name))
+        };
+        // Keep only parents inside this tree so each group composes on its own.
+        let group: Vec<Capability> = capabilities
+            .iter()
+            .filter(|c| in_tree(&c.name))
+            .map(|c| Capability {
+                extends: c
+                    .extends
+                    .as_ref()
+                    .map(|parents| parents.iter().filter(|p| in_tree(p)).cloned().collect()),
+                ..c.clone()
+            })
+            .collect();
+        debug!(
+            "composing tree rooted at {} ({} capabilities)",
+            root.name,
+            group.len()
+        );
+        let branch = compose_schema(&group, schema_base)?;
+        if is_container_schema(&branch) {
+            return Err(ComposeError::ContainerInMultiRoot {
+                capability: root.name.clone(),
+            });
+        }
+        branches.push(branch);
+    }
+
+    Ok(json!({ "allOf": branches }))
+}
+
+/// Verify every capability's schema URL against its namespace authority.
+///
+/// A capability's `schema` URL must originate from the namespace authority
+/// encoded in its name (spec §Authority Binding). Callers check ALL
+/// capabilities before dereferencing any of them (validate-before-fetch).
+/// This is unconditional — the spec requires it and there is no opt-out;
+/// non-URL schema values (local paths) carry no origin, so they are skipped.
+fn check_authority_binding(capabilities: &[Capability]) -> Result<(), ComposeError> {
     for cap in capabilities {
         if is_url(&cap.schema_url) {
             if let Err(e) = crate::namespace::validate_binding(&cap.name, &cap.schema_url) {
@@ -424,6 +767,103 @@ pub fn compose_schema(
             }
         }
     }
+    Ok(())
+}
+
+/// Check that a capability's schema declares the capability's name at
+/// `pointer`, when one is configured.
+fn check_capability_id(
+    cap: &Capability,
+    schema: &Value,
+    pointer: Option<&str>,
+) -> Result<(), ComposeError> {
+    let Some(pointer) = pointer else {
+        return Ok(());
+    };
+    let declared = schema.pointer(pointer).and_then(Value::as_str);
+    if declared == Some(cap.name.as_str()) {
+        return Ok(());
+    }
+    Err(ComposeError::CapabilityIdMismatch {
+        name: cap.name.clone(),
+        declared: declared.map(str::to_string),
+    })
+}
+
+/// [`compose_schema`] with the capability schema loader supplied by the caller,
+/// also returning the composition order (see [`compose_capabilities`]).
+fn compose_schema_with(
+    capabilities: &[Capability],
+    schema_base: &SchemaBaseConfig,
+    load: &dyn Fn(&Capability) -> Result<Value, ComposeError>,
+) -> Result<(Value, Vec<Capability>), ComposeError> {
+    let (composed, order) = compose_capabilities(capabilities, schema_base, load)?;
+    let composed = match schema_base.envelope {
+        Some(envelope) => with_envelope(composed, envelope)?,
+        None => composed,
+    };
+    Ok((composed, order))
+}
+
+/// Add the envelope schema as an `allOf` branch of a composed schema, or of
+/// each operation shape of a composed container.
+fn with_envelope(mut composed: Value, envelope: &Value) -> Result<Value, ComposeError> {
+    let mut branch = envelope.clone();
+    inline_local_refs(&mut branch, envelope).map_err(|e| ComposeError::InvalidEnvelopeSchema {
+        message: e.to_string(),
+    })?;
+
+    if is_container_schema(&composed) {
+        let shapes = composed
+            .get_mut("$defs")
+            .and_then(Value::as_object_mut)
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| key.ends_with("_request") || key.ends_with("_response"));
+        for (_, shape) in shapes {
+            add_all_of_branch(shape, &branch);
+        }
+    } else {
+        add_all_of_branch(&mut composed, &branch);
+    }
+    Ok(composed)
+}
+
+/// Put `branch` first in a schema's `allOf`, creating it if needed. The
+/// schema's own keywords stay at its root, so its `$defs` still resolve.
+fn add_all_of_branch(schema: &mut Value, branch: &Value) {
+    let Some(obj) = schema.as_object_mut() else {
+        // A boolean schema: `true` admits everything the envelope does
+        *schema = json!({ "allOf": [branch.clone(), schema.clone()] });
+        return;
+    };
+    match obj.get_mut("allOf") {
+        Some(Value::Array(branches)) => branches.insert(0, branch.clone()),
+        _ => {
+            obj.insert("allOf".to_string(), json!([branch.clone()]));
+        }
+    }
+}
+
+/// The capability composition behind [`compose_schema_with`]. Also returns
+/// the capabilities in the order they were applied: the root, then the
+/// extensions by name, each listed once.
+fn compose_capabilities(
+    capabilities: &[Capability],
+    schema_base: &SchemaBaseConfig,
+    load: &dyn Fn(&Capability) -> Result<Value, ComposeError>,
+) -> Result<(Value, Vec<Capability>), ComposeError> {
+    if capabilities.is_empty() {
+        return Err(ComposeError::EmptyCapabilities);
+    }
+    debug!("composing {} capabilities", capabilities.len());
+
+    check_authority_binding(capabilities)?;
+    let load = |cap: &Capability| {
+        let schema = load(cap)?;
+        check_capability_id(cap, &schema, schema_base.capability_id_pointer)?;
+        Ok::<_, ComposeError>(schema)
+    };
 
     // Build name -> capability map for lookups
     let cap_map: HashMap<&str, &Capability> =
@@ -469,46 +909,58 @@ pub fn compose_schema(
         }
     }
 
-    // Get extensions (all non-root capabilities)
-    let extensions: Vec<&Capability> = capabilities
+    // Get extensions (all non-root capabilities), ordered by name so the
+    // composed `allOf` does not depend on the order capabilities were declared
+    // in. An identical capability listed twice contributes once; the same
+    // name with a different version, schema, or parents is a conflict.
+    let mut extensions: Vec<&Capability> = capabilities
         .iter()
         .filter(|c| c.extends.is_some())
         .collect();
+    extensions.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(pair) = extensions
+        .windows(2)
+        .find(|pair| pair[0].name == pair[1].name && pair[0] != pair[1])
+    {
+        return Err(ComposeError::ConflictingCapability {
+            name: pair[0].name.clone(),
+        });
+    }
+    extensions.dedup();
 
     // No extensions: the capability schema stands alone. For a single-object
     // capability this root is the message body; for a container it is the
     // namespace of `{op}_{direction}` shapes. The operation shape, if any, is
     // chosen downstream by `select_operation_schema`.
-    if extensions.is_empty() {
-        return resolve_schema_url(&root.schema_url, schema_base).map_err(|e| {
-            ComposeError::SchemaFetch {
-                url: root.schema_url.clone(),
-                message: e.to_string(),
-            }
-        });
-    }
+    let composed = if extensions.is_empty() {
+        load(root)?
+    } else {
+        merge_extensions(root, &extensions, capabilities, schema_base, &load)?
+    };
+    let order = std::iter::once(root).chain(extensions).cloned().collect();
+    Ok((composed, order))
+}
 
+/// Load and merge `extensions` onto `root`, the part of
+/// [`compose_capabilities`] after the graph is validated and ordered.
+fn merge_extensions(
+    root: &Capability,
+    extensions: &[&Capability],
+    capabilities: &[Capability],
+    schema_base: &SchemaBaseConfig,
+    load: &dyn Fn(&Capability) -> Result<Value, ComposeError>,
+) -> Result<Value, ComposeError> {
     // Load the root schema to classify the capability (single-object vs
     // container) and, for a container, to seed the per-operation merge with the
     // base's `$defs`.
-    let root_schema = resolve_schema_url(&root.schema_url, schema_base).map_err(|e| {
-        ComposeError::SchemaFetch {
-            url: root.schema_url.clone(),
-            message: e.to_string(),
-        }
-    })?;
+    let root_schema = load(root)?;
     let container = is_container_schema(&root_schema);
 
     // Compose: for each extension, extract its self-contained `$defs[root.name]`.
     let mut ext_defs = Vec::new();
 
-    for ext in &extensions {
-        let ext_schema = resolve_schema_url(&ext.schema_url, schema_base).map_err(|e| {
-            ComposeError::SchemaFetch {
-                url: ext.schema_url.clone(),
-                message: e.to_string(),
-            }
-        })?;
+    for ext in extensions {
+        let ext_schema = load(ext)?;
 
         // Check version constraints: if requires is declared and violated, fail.
         // No requires = backwards compat (composer asserts compatibility).
@@ -523,39 +975,220 @@ pub fn compose_schema(
             });
         }
 
-        // Extract $defs[root.name] and inline any internal refs
-        let defs = ext_schema
-            .get("$defs")
-            .ok_or_else(|| ComposeError::MissingDefEntry {
-                extension: ext.name.clone(),
-                expected_key: root.name.clone(),
-            })?;
+        // Extract $defs[root.name] (or Draft 7 `definitions`, as the resolver
+        // accepts both) and inline any internal refs
+        let ext_def = ["$defs", "definitions"]
+            .iter()
+            .find_map(|key| ext_schema.get(*key)?.get(&root.name));
+        let mut inlined = match ext_def {
+            Some(ext_def) => ext_def.clone(),
+            // Opt-in: the extension's own top-level allOf/properties is the branch
+            None if schema_base.top_level_extensions
+                && !container
+                && (ext_schema.get("allOf").is_some()
+                    || ext_schema.get("properties").is_some()) =>
+            {
+                debug!(
+                    "extension {} has no $defs[{}]; using its top-level schema",
+                    ext.name, root.name
+                );
+                let mut branch = ext_schema.clone();
+                if let Some(obj) = branch.as_object_mut() {
+                    obj.remove("$defs");
+                    obj.remove("definitions");
+                }
+                branch
+            }
+            None => {
+                return Err(ComposeError::MissingDefEntry {
+                    extension: ext.name.clone(),
+                    expected_key: root.name.clone(),
+                })
+            }
+        };
 
-        let ext_def = defs
-            .get(&root.name)
-            .ok_or_else(|| ComposeError::MissingDefEntry {
+        // Inline internal #/$defs/... and #/definitions/... refs so the
+        // extracted def is self-contained
+        inline_local_refs(&mut inlined, &ext_schema).map_err(|e| {
+            ComposeError::InvalidCapability {
+                name: ext.name.clone(),
+                message: e.to_string(),
+            }
+        })?;
+        if schema_base.strip_extension_ids {
+            strip_identifiers(&mut inlined, false);
+        }
+        let mut closed = Vec::new();
+        open_closed_branches(
+            &mut inlined,
+            "",
+            schema_base.relax_closed_extensions,
+            &mut closed,
+        );
+        if let (Some(pointer), false, true) = (
+            closed.first(),
+            schema_base.relax_closed_extensions,
+            schema_base.reject_closed_extensions,
+        ) {
+            return Err(ComposeError::ClosedExtensionBranch {
                 extension: ext.name.clone(),
-                expected_key: root.name.clone(),
-            })?;
-
-        // Inline internal #/$defs/... refs so the extracted def is self-contained
-        let mut inlined = ext_def.clone();
-        inline_internal_refs(&mut inlined, defs);
+                pointer: format!("$defs[{}]{}", root.name, pointer),
+            });
+        }
+        for pointer in &closed {
+            warn!(
+                "extension {} closes $defs[{}]{} to unknown properties; under the composed \
+                 allOf it rejects fields added by other branches (see relax_closed_extensions)",
+                ext.name, root.name, pointer
+            );
+        }
 
         ext_defs.push(inlined);
     }
 
+    if schema_base.check_satisfiable {
+        let names = std::iter::once(root).chain(extensions.iter().copied());
+        if container {
+            let mut op_keys: Vec<&String> = ext_defs
+                .iter()
+                .filter_map(|d| d.get("$defs")?.as_object())
+                .flat_map(|defs| defs.keys())
+                .collect();
+            op_keys.sort();
+            op_keys.dedup();
+            for op_key in op_keys {
+                let shapes = std::iter::once(&root_schema)
+                    .chain(&ext_defs)
+                    .map(|s| s.get("$defs").and_then(|d| d.get(op_key)));
+                let branches: Vec<_> = names
+                    .clone()
+                    .zip(shapes)
+                    .filter_map(|(cap, shape)| Some((cap.name.as_str(), shape?)))
+                    .collect();
+                check_satisfiable(&branches)?;
+            }
+        } else {
+            let branches: Vec<_> = names
+                .zip(std::iter::once(&root_schema).chain(&ext_defs))
+                .map(|(cap, schema)| (cap.name.as_str(), schema))
+                .collect();
+            check_satisfiable(&branches)?;
+        }
+    }
+
     // Composition follows the same single-object vs container split: a
     // single-object body is extended once at the root; a container is extended
     // per operation shape. Both use `allOf`, and in both the base is included
     // because each extension re-`$ref`s it.
     if container {
-        compose_container(&root_schema, &extensions, &ext_defs, &root.name)
+        compose_container(&root_schema, extensions, &ext_defs, &root.name)
     } else {
-        Ok(json!({ "allOf": ext_defs }))
+        // The new root keeps the base's dialect and vocabulary declarations
+        let mut composed = serde_json::Map::new();
+        for key in META_SCHEMA_KEYWORDS {
+            if let Some(value) = root_schema.get(*key) {
+                composed.insert(key.to_string(), value.clone());
+            }
+        }
+        composed.insert("allOf".to_string(), Value::Array(ext_defs));
+        Ok(Value::Object(composed))
+    }
+}
+
+/// Reject branches where one requires a property another forbids.
+///
+/// Each branch is `(capability name, schema)`; all of them apply to the same
+/// instance, as members of the composed `allOf`.
+fn check_satisfiable(branches: &[(&str, &Value)]) -> Result<(), ComposeError> {
+    let declared: Vec<(&str, Vec<&str>, Vec<&str>)> = branches
+        .iter()
+        .map(|(name, schema)| {
+            let (mut required, mut forbidden) = (Vec::new(), Vec::new());
+            collect_required_forbidden(schema, &mut required, &mut forbidden);
+            (*name, required, forbidden)
+        })
+        .collect();
+
+    for (requiring, required, _) in &declared {
+        for property in required {
+            let forbidding = declared
+                .iter()
+                .find(|(_, _, forbidden)| forbidden.contains(property));
+            if let Some((forbidding, ..)) = forbidding {
+                let mut capabilities = vec![requiring.to_string()];
+                if forbidding != requiring {
+                    capabilities.push(forbidding.to_string());
+                }
+                return Err(ComposeError::UnsatisfiableComposition {
+                    property: property.to_string(),
+                    capabilities,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Properties a schema requires and forbids at its own level, including
+/// through nested `allOf` members. Annotated properties don't count as
+/// required: resolution may omit them.
+fn collect_required_forbidden<'a>(
+    schema: &'a Value,
+    required: &mut Vec<&'a str>,
+    forbidden: &mut Vec<&'a str>,
+) {
+    let Some(obj) = schema.as_object() else {
+        return;
+    };
+    let properties = obj.get("properties").and_then(Value::as_object);
+    let annotated = |name: &str| {
+        properties
+            .and_then(|p| p.get(name))
+            .and_then(Value::as_object)
+            .is_some_and(|p| UCP_ANNOTATIONS.iter().any(|k| p.contains_key(*k)))
+    };
+    let names = |value: Option<&'a Value>| {
+        value
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+    };
+
+    required.extend(names(obj.get("required")).filter(|name| !annotated(name)));
+    if let Some(properties) = properties {
+        forbidden.extend(
+            properties
+                .iter()
+                .filter(|(_, schema)| **schema == Value::Bool(false))
+                .map(|(name, _)| name.as_str()),
+        );
+    }
+    // Only the plain `not: { required: [name] }` form forbids a property on
+    // its own; with several names it forbids them only together
+    if let Some(not) = obj.get("not").and_then(Value::as_object) {
+        if let (1, Some([Value::String(name)])) = (
+            not.len(),
+            not.get("required")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice),
+        ) {
+            forbidden.push(name);
+        }
+    }
+    for member in obj
+        .get("allOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        collect_required_forbidden(member, required, forbidden);
     }
 }
 
+/// Root keywords declaring the meta-schema, carried to a composed root.
+const META_SCHEMA_KEYWORDS: &[&str] = &["$schema", "$vocabulary"];
+
 /// Returns true if a capability schema is "container-shaped".
 ///
 /// A UCP capability schema takes one of two structural forms, and the whole
@@ -653,67 +1286,92 @@ fn compose_container(
     Ok(result)
 }
 
-/// Inline internal `#/$defs/...` refs from the parent schema.
-///
-/// When extracting a single definition from a schema, that definition may have
-/// internal refs to other definitions in the same schema. This function
-/// recursively inlines those refs so the extracted definition is self-contained.
-///
-/// # Arguments
-/// * `value` - The value to process (modified in place)
-/// * `defs` - The `$defs` object to resolve refs against
-fn inline_internal_refs(value: &mut Value, defs: &Value) {
-    inline_internal_refs_inner(value, defs, &mut HashSet::new());
-}
-
-fn inline_internal_refs_inner(value: &mut Value, defs: &Value, visited: &mut HashSet<String>) {
-    match value {
-        Value::Object(obj) => {
-            // Check if this object has an internal $ref
-            if let Some(ref_val) = obj.get("$ref").and_then(|v| v.as_str()) {
-                // Only handle internal refs to $defs (not self-root "#" refs)
-                if let Some(def_name) = ref_val.strip_prefix("#/$defs/") {
-                    // Guard against circular refs
-                    if visited.contains(def_name) {
-                        return;
-                    }
+/// Keywords that set a base URI, removed by `strip_extension_ids`.
+const IDENTIFIER_KEYWORDS: &[&str] = &["$id", "$anchor"];
 
-                    // Look up the definition
-                    if let Some(def) = defs.get(def_name) {
-                        visited.insert(def_name.to_string());
+/// Keywords whose values are instance data, not subschemas.
+const DATA_KEYWORDS: &[&str] = &["const", "default", "enum", "examples"];
 
-                        // Clone and recursively inline
-                        let mut inlined = def.clone();
-                        inline_internal_refs_inner(&mut inlined, defs, visited);
+/// Keywords whose object value maps names (not keywords) to subschemas.
+const NAME_MAP_KEYWORDS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+];
 
-                        visited.remove(def_name);
-
-                        // Replace the $ref object with the inlined definition
-                        obj.remove("$ref");
-                        if let Value::Object(def_obj) = inlined {
-                            for (k, v) in def_obj {
-                                obj.entry(k).or_insert(v);
-                            }
-                        }
-                        return;
-                    }
+/// Recursively remove identifier keywords from a schema.
+///
+/// `in_name_map` marks an object whose keys are property or definition names,
+/// so a property literally called `$id` is kept. Data keywords are not
+/// descended into: an `$id` inside a `const` or `examples` value is data.
+fn strip_identifiers(value: &mut Value, in_name_map: bool) {
+    match value {
+        Value::Object(obj) => {
+            if !in_name_map {
+                obj.retain(|k, _| !IDENTIFIER_KEYWORDS.contains(&k.as_str()));
+            }
+            for (key, child) in obj.iter_mut() {
+                if !in_name_map && DATA_KEYWORDS.contains(&key.as_str()) {
+                    continue;
                 }
-            }
-
-            // Recurse into all values
-            for v in obj.values_mut() {
-                inline_internal_refs_inner(v, defs, visited);
+                let child_is_name_map = !in_name_map && NAME_MAP_KEYWORDS.contains(&key.as_str());
+                strip_identifiers(child, child_is_name_map);
             }
         }
         Value::Array(arr) => {
             for item in arr {
-                inline_internal_refs_inner(item, defs, visited);
+                strip_identifiers(item, false);
             }
         }
         _ => {}
     }
 }
 
+/// Keywords that close a schema to properties it does not itself evaluate.
+const CLOSING_KEYWORDS: &[&str] = &["additionalProperties", "unevaluatedProperties"];
+
+/// Find (and with `open`, remove) closing keywords set to `false` in the
+/// conjunctive positions of an extension branch: the branch, its `allOf`
+/// members, and a container extension's per-operation shapes. Records the
+/// JSON pointer of each closed position in `found`.
+fn open_closed_branches(branch: &mut Value, path: &str, open: bool, found: &mut Vec<String>) {
+    let Some(obj) = branch.as_object_mut() else {
+        return;
+    };
+
+    if CLOSING_KEYWORDS
+        .iter()
+        .any(|k| obj.get(*k) == Some(&Value::Bool(false)))
+    {
+        found.push(if path.is_empty() {
+            "/".to_string()
+        } else {
+            path.to_string()
+        });
+        if open {
+            obj.retain(|k, v| {
+                !(CLOSING_KEYWORDS.contains(&k.as_str()) && *v == Value::Bool(false))
+            });
+        }
+    }
+
+    if let Some(Value::Array(members)) = obj.get_mut("allOf") {
+        for (i, member) in members.iter_mut().enumerate() {
+            open_closed_branches(member, &format!("{}/allOf/{}", path, i), open, found);
+        }
+    }
+    // Container extension: { "$defs": { "<op>_<direction>": <shape> } }
+    if path.is_empty() && !obj.contains_key("properties") {
+        if let Some(Value::Object(shapes)) = obj.get_mut("$defs") {
+            for (key, shape) in shapes.iter_mut() {
+                open_closed_branches(shape, &format!("/$defs/{}", key), open, found);
+            }
+        }
+    }
+}
+
 /// Check if a capability transitively reaches the root via extends chain.
 fn reaches_root(cap: &Capability, cap_map: &HashMap<&str, &Capability>, root_name: &str) -> bool {
     let mut visited = HashSet::new();
@@ -745,8 +1403,189 @@ pub fn compose_from_payload(
     payload: &Value,
     schema_base: &SchemaBaseConfig,
 ) -> Result<Value, ComposeError> {
+    compose_from_payload_detailed(payload, schema_base).map(|(schema, _)| schema)
+}
+
+/// [`compose_from_payload`], also returning the capabilities that contributed.
+///
+/// The capabilities come back in composition order: the root first, then the
+/// extensions sorted by name, each listed once, matching the branches of
+/// the composed `allOf`. Saves callers that log or report the active
+/// capabilities a second [`extract_capabilities`] call (and profile fetch).
+pub fn compose_from_payload_detailed(
+    payload: &Value,
+    schema_base: &SchemaBaseConfig,
+) -> Result<(Value, Vec<Capability>), ComposeError> {
     let capabilities = extract_capabilities(payload, schema_base)?;
-    compose_schema(&capabilities, schema_base)
+    compose_schema_with(&capabilities, schema_base, &|cap| {
+        load_capability_schema(cap, schema_base)
+    })
+}
+
+/// Async counterpart of [`compose_from_payload`]. Requires the `async` feature.
+///
+/// Profile and capability schema fetches over HTTP — including the external
+/// `$ref`s they bundle — use the non-blocking client, so this can run on a
+/// tokio runtime without `spawn_blocking`. Local files (overrides, `local_base`
+/// mappings, plain paths) are still read synchronously.
+#[cfg(feature = "async")]
+pub async fn compose_from_payload_async(
+    payload: &Value,
+    schema_base: &SchemaBaseConfig<'_>,
+) -> Result<Value, ComposeError> {
+    let metadata = payload_metadata_at(payload, &schema_base.payload_pointers)
+        .ok_or(ComposeError::NotSelfDescribing)?;
+    let capabilities = match metadata.profile_url() {
+        Some(profile_url) => {
+            let profile = resolve_schema_url_async(profile_url, schema_base)
+                .await
+                .map_err(|e| ComposeError::ProfileFetch {
+                    url: profile_url.to_string(),
+                    message: e.to_string(),
+                })?;
+            profile_capabilities(profile_url, &profile)?
+        }
+        None => metadata.capabilities(schema_base)?,
+    };
+
+    // Check every binding before fetching anything, as `compose_schema` does
+    check_authority_binding(&capabilities)?;
+
+    let mut schemas = HashMap::new();
+    for cap in &capabilities {
+        let schema = match capability_schema_source(cap, schema_base)? {
+            SchemaSource::Remote(url) => fetch_remote_schema_async(&url, schema_base)
+                .await
+                .map_err(|e| ComposeError::SchemaFetch {
+                    url: cap.schema_url.clone(),
+                    message: e.to_string(),
+                })?,
+            _ => load_capability_schema(cap, schema_base)?,
+        };
+        schemas.insert(cap.name.clone(), schema);
+    }
+
+    compose_schema_with(&capabilities, schema_base, &|cap| {
+        Ok(schemas[cap.name.as_str()].clone())
+    })
+    .map(|(schema, _)| schema)
+}
+
+/// Async counterpart of [`resolve_schema_url`]: only the HTTP fetch differs.
+#[cfg(feature = "async")]
+async fn resolve_schema_url_async(
+    url: &str,
+    schema_base: &SchemaBaseConfig<'_>,
+) -> Result<Value, ComposeError> {
+    match schema_url_source(url, schema_base)? {
+        SchemaSource::Remote(url) => fetch_remote_schema_async(&url, schema_base).await,
+        _ => resolve_schema_url(url, schema_base),
+    }
+}
+
+/// Load a capability's schema, honoring `schema_base.overrides`.
+///
+/// An override names a local file that replaces the declared schema URL; its
+/// `$ref`s are bundled relative to the override file's directory (with URL
+/// mapping when `remote_base` is configured). Capabilities without an override
+/// go through [`resolve_schema_url`].
+fn load_capability_schema(
+    cap: &Capability,
+    schema_base: &SchemaBaseConfig,
+) -> Result<Value, ComposeError> {
+    let Some(path) = schema_base.overrides.and_then(|o| o.get(&cap.name)) else {
+        return resolve_schema_url(&cap.schema_url, schema_base).map_err(|e| {
+            ComposeError::SchemaFetch {
+                url: cap.schema_url.clone(),
+                message: e.to_string(),
+            }
+        });
+    };
+
+    debug!("overriding schema for {} with {}", cap.name, path.display());
+    let fetch_err = |message: String| ComposeError::SchemaFetch {
+        url: path.display().to_string(),
+        message,
+    };
+    let mut schema =
+        load_schema_file(path, &schema_base.load_options).map_err(|e| fetch_err(e.to_string()))?;
+    let schema_dir = path.parent().unwrap_or(Path::new("."));
+    match (schema_base.local_base, schema_base.remote_base) {
+        (Some(local_base), Some(remote_base)) => {
+            bundle_refs_with_url_mapping(&mut schema, schema_dir, local_base, remote_base)
+        }
+        _ => bundle_refs(&mut schema, schema_dir),
+    }
+    .map_err(|e| fetch_err(format!("bundling refs: {}", e)))?;
+    Ok(schema)
+}
+
+/// Where a capability's schema is loaded from, as decided before loading it.
+///
+/// Returned by [`capability_schema_source`] so callers can report what
+/// composition will try — and therefore what it tried when a fetch fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaSource {
+    /// A `schema_base.overrides` file replaces the declared URL.
+    Override(PathBuf),
+    /// The URL is mapped to a file under `schema_base.local_base`.
+    Mapped {
+        path: PathBuf,
+        /// The `remote_base` prefix stripped from the URL; `None` when no
+        /// remote base is set or the URL doesn't start with it, in which case
+        /// the URL's path is used.
+        stripped: Option<String>,
+    },
+    /// Fetched over HTTP.
+    Remote(String),
+    /// The schema "URL" is a plain file path.
+    File(PathBuf),
+}
+
+/// Decide where [`compose_schema`] loads `cap`'s schema from, without
+/// loading it.
+///
+/// # Errors
+///
+/// Returns `InvalidSchemaUrl` if a URL mapped under `local_base` has no path.
+pub fn capability_schema_source(
+    cap: &Capability,
+    schema_base: &SchemaBaseConfig,
+) -> Result<SchemaSource, ComposeError> {
+    match schema_base.overrides.and_then(|o| o.get(&cap.name)) {
+        Some(path) => Ok(SchemaSource::Override(path.clone())),
+        None => schema_url_source(&cap.schema_url, schema_base),
+    }
+}
+
+/// [`capability_schema_source`] for a schema URL with no override.
+fn schema_url_source(
+    url: &str,
+    schema_base: &SchemaBaseConfig,
+) -> Result<SchemaSource, ComposeError> {
+    if let Some(base) = schema_base.local_base {
+        Ok(SchemaSource::Mapped {
+            path: map_schema_url(url, base, schema_base.remote_base)?,
+            stripped: schema_base
+                .remote_base
+                .filter(|remote_base| url.starts_with(remote_base))
+                .map(str::to_string),
+        })
+    } else if is_url(url) {
+        Ok(SchemaSource::Remote(url.to_string()))
+    } else {
+        Ok(SchemaSource::File(PathBuf::from(url)))
+    }
+}
+
+/// Load a capability schema file as `options.format`, or by its extension.
+fn load_schema_file(path: &Path, options: &LoadOptions) -> Result<Value, ResolveError> {
+    load_schema_as(
+        path,
+        options
+            .format
+            .unwrap_or_else(|| InputFormat::from_path(path)),
+    )
 }
 
 /// Resolve a schema URL to a Value, bundling any $ref pointers.
@@ -760,26 +1599,19 @@ pub fn compose_from_payload(
 /// This is necessary because extension schemas often have relative refs like
 /// `$ref: "checkout.json"` that need resolution before composition.
 fn resolve_schema_url(url: &str, schema_base: &SchemaBaseConfig) -> Result<Value, ComposeError> {
-    if let Some(base) = schema_base.local_base {
-        // Map URL to local path
-        let path = if let Some(remote_base) = schema_base.remote_base {
-            // Strip remote_base prefix if URL starts with it
-            if let Some(remainder) = url.strip_prefix(remote_base) {
-                // remainder is like "/schemas/checkout.json"
-                remainder.to_string()
-            } else {
-                // URL doesn't match remote_base, fall back to extracting path
-                extract_url_path(url)?
+    let source = schema_url_source(url, schema_base)?;
+    if let (
+        SchemaSource::Mapped {
+            path: local_path, ..
+        },
+        Some(base),
+    ) = (&source, schema_base.local_base)
+    {
+        let mut schema = load_schema_file(local_path, &schema_base.load_options).map_err(|_| {
+            ComposeError::SchemaFetch {
+                url: url.to_string(),
+                message: format!("file not found: {}", local_path.display()),
             }
-        } else {
-            // No remote_base, extract path portion of URL
-            extract_url_path(url)?
-        };
-
-        let local_path = base.join(path.trim_start_matches('/'));
-        let mut schema = load_schema(&local_path).map_err(|_| ComposeError::SchemaFetch {
-            url: url.to_string(),
-            message: format!("file not found: {}", local_path.display()),
         })?;
 
         // Bundle refs - use URL-aware version if remote mapping is configured
@@ -801,22 +1633,11 @@ fn resolve_schema_url(url: &str, schema_base: &SchemaBaseConfig) -> Result<Value
         }
 
         Ok(schema)
-    } else if is_url(url) {
+    } else if let SchemaSource::Remote(url) = &source {
         // HTTP fetch with remote bundling
         #[cfg(feature = "remote")]
         {
-            let mut schema = load_schema_url(url).map_err(|e| ComposeError::SchemaFetch {
-                url: url.to_string(),
-                message: e.to_string(),
-            })?;
-
-            // Bundle refs using the URL as base for resolving relative refs
-            bundle_refs_remote(&mut schema, url).map_err(|e| ComposeError::SchemaFetch {
-                url: url.to_string(),
-                message: format!("bundling refs: {}", e),
-            })?;
-
-            Ok(schema)
+            fetch_remote_schema(url, schema_base)
         }
         #[cfg(not(feature = "remote"))]
         {
@@ -828,9 +1649,11 @@ fn resolve_schema_url(url: &str, schema_base: &SchemaBaseConfig) -> Result<Value
     } else {
         // Treat as local file path
         let local_path = Path::new(url);
-        let mut schema = load_schema(local_path).map_err(|e| ComposeError::SchemaFetch {
-            url: url.to_string(),
-            message: e.to_string(),
+        let mut schema = load_schema_file(local_path, &schema_base.load_options).map_err(|e| {
+            ComposeError::SchemaFetch {
+                url: url.to_string(),
+                message: e.to_string(),
+            }
         })?;
 
         // Bundle refs using the schema's directory as base
@@ -845,6 +1668,85 @@ fn resolve_schema_url(url: &str, schema_base: &SchemaBaseConfig) -> Result<Value
     }
 }
 
+/// Fetch a schema over HTTP with `schema_base.load_options`, bundling its refs
+/// against the final URL so a redirected schema's siblings are found next to it.
+#[cfg(feature = "remote")]
+fn fetch_remote_schema(url: &str, schema_base: &SchemaBaseConfig) -> Result<Value, ComposeError> {
+    let fetched = load_schema_url_with(url, &schema_base.load_options)
+        .map_err(|e| remote_fetch_error(url, e.to_string()))?;
+    let (mut schema, final_url) = fetched_schema(url, fetched);
+    bundle_refs_remote(&mut schema, &final_url)
+        .map_err(|e| remote_fetch_error(url, format!("bundling refs: {}", e)))?;
+    Ok(schema)
+}
+
+/// Async counterpart of [`fetch_remote_schema`]: only the I/O differs.
+#[cfg(feature = "async")]
+async fn fetch_remote_schema_async(
+    url: &str,
+    schema_base: &SchemaBaseConfig<'_>,
+) -> Result<Value, ComposeError> {
+    let fetched = load_schema_url_with_async(url, &schema_base.load_options)
+        .await
+        .map_err(|e| remote_fetch_error(url, e.to_string()))?;
+    let (mut schema, final_url) = fetched_schema(url, fetched);
+    bundle_refs_remote_async(&mut schema, &final_url)
+        .await
+        .map_err(|e| remote_fetch_error(url, format!("bundling refs: {}", e)))?;
+    Ok(schema)
+}
+
+#[cfg(feature = "remote")]
+fn remote_fetch_error(url: &str, message: String) -> ComposeError {
+    ComposeError::SchemaFetch {
+        url: url.to_string(),
+        message,
+    }
+}
+
+/// Split a fetched schema into the schema and the URL it was served from,
+/// logging any redirects.
+#[cfg(feature = "remote")]
+fn fetched_schema(url: &str, fetched: FetchedSchema) -> (Value, String) {
+    if fetched.redirects > 0 {
+        debug!(
+            "capability schema {} served from {} after {} redirect(s)",
+            url, fetched.final_url, fetched.redirects
+        );
+    }
+    (fetched.schema, fetched.final_url)
+}
+
+/// Map a schema URL to a file under `local_base`, stripping `remote_base`
+/// when the URL starts with it and otherwise keeping the URL's path.
+pub(crate) fn map_schema_url(
+    url: &str,
+    local_base: &Path,
+    remote_base: Option<&str>,
+) -> Result<PathBuf, ComposeError> {
+    let path = if let Some(remote_base) = remote_base {
+        // Strip remote_base prefix if URL starts with it
+        if let Some(remainder) = url.strip_prefix(remote_base) {
+            // remainder is like "/schemas/checkout.json"
+            remainder.to_string()
+        } else {
+            // URL doesn't match remote_base, fall back to extracting path
+            warn!(
+                "schema URL {} does not match remote base {}; falling back to its URL path",
+                url, remote_base
+            );
+            extract_url_path(url)?
+        }
+    } else {
+        // No remote_base, extract path portion of URL
+        extract_url_path(url)?
+    };
+
+    let local_path = local_base.join(path.trim_start_matches('/'));
+    debug!("mapped schema URL {} -> {}", url, local_path.display());
+    Ok(local_path)
+}
+
 /// Extract the path portion from a URL.
 ///
 /// E.g., "https://ucp.dev/schemas/shopping/checkout.json" -> "/schemas/shopping/checkout.json"
@@ -906,6 +1808,18 @@ mod tests {
         assert_eq!(detect_direction(&payload), Some(DetectedDirection::Request));
     }
 
+    #[test]
+    fn detected_direction_converts_to_direction() {
+        assert_eq!(
+            Direction::from(DetectedDirection::Response),
+            Direction::Response
+        );
+        assert_eq!(
+            Direction::from(DetectedDirection::Request),
+            Direction::Request
+        );
+    }
+
     #[test]
     fn detect_direction_old_request_format_not_detected() {
         // Old invalid format should NOT be detected as request
@@ -938,6 +1852,78 @@ mod tests {
         assert_eq!(detect_direction(&payload), None);
     }
 
+    #[test]
+    fn payload_metadata_yields_direction_and_capabilities() {
+        let payload = json!({
+            "ucp": {
+                "capabilities": {
+                    "dev.ucp.shopping.checkout": [{
+                        "version": "2026-01-11",
+                        "schema": "https://ucp.dev/schemas/shopping/checkout.json"
+                    }]
+                }
+            }
+        });
+        let metadata = payload_metadata(&payload).unwrap();
+        assert_eq!(metadata.direction(), DetectedDirection::Response);
+        assert_eq!(metadata.profile_url(), None);
+        let caps = metadata.capabilities(&SchemaBaseConfig::default()).unwrap();
+        assert_eq!(caps.len(), 1);
+        assert_eq!(caps[0].name, "dev.ucp.shopping.checkout");
+
+        let request = json!({ "meta": { "profile": "https://agent.example.com/.well-known/ucp" } });
+        let metadata = payload_metadata(&request).unwrap();
+        assert_eq!(metadata.direction(), DetectedDirection::Request);
+        assert_eq!(
+            metadata.profile_url(),
+            Some("https://agent.example.com/.well-known/ucp")
+        );
+
+        // A non-string profile still marks a request, but names no profile.
+        let malformed = json!({ "meta": { "profile": 42 } });
+        let metadata = payload_metadata(&malformed).unwrap();
+        assert_eq!(metadata.direction(), DetectedDirection::Request);
+        assert!(matches!(
+            metadata.capabilities(&SchemaBaseConfig::default()),
+            Err(ComposeError::NotSelfDescribing)
+        ));
+    }
+
+    #[test]
+    fn payload_pointers_locate_custom_metadata() {
+        let payload = json!({
+            "meta": {
+                "capabilities": {
+                    "dev.ucp.shopping.checkout": [{
+                        "version": "2026-01-11",
+                        "schema": "https://ucp.dev/schemas/shopping/checkout.json"
+                    }]
+                }
+            }
+        });
+        assert_eq!(detect_direction(&payload), None);
+
+        let config = SchemaBaseConfig {
+            payload_pointers: PayloadPointers {
+                capabilities: "/meta/capabilities",
+                profile: "/envelope/profile",
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_direction_at(&payload, &config.payload_pointers),
+            Some(DetectedDirection::Response)
+        );
+        let caps = extract_capabilities(&payload, &config).unwrap();
+        assert_eq!(caps[0].name, "dev.ucp.shopping.checkout");
+
+        let request = json!({ "envelope": { "profile": "https://agent.example.com/ucp" } });
+        assert_eq!(
+            detect_direction_at(&request, &config.payload_pointers),
+            Some(DetectedDirection::Request)
+        );
+    }
+
     #[test]
     fn parse_capabilities_single_root() {
         let caps = json!({
@@ -979,6 +1965,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn capability_serde_round_trip() {
+        let discount = Capability {
+            name: "dev.ucp.shopping.discount".to_string(),
+            version: "2026-01-11".to_string(),
+            schema_url: "https://ucp.dev/schemas/shopping/discount.json".to_string(),
+            extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+        };
+        let value = serde_json::to_value(&discount).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "name": "dev.ucp.shopping.discount",
+                "version": "2026-01-11",
+                "schema": "https://ucp.dev/schemas/shopping/discount.json",
+                "extends": ["dev.ucp.shopping.checkout"]
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Capability>(value).unwrap(),
+            discount
+        );
+
+        // Single-string extends, and roots without extends
+        let parsed: Capability = serde_json::from_value(json!({
+            "name": "dev.ucp.shopping.discount",
+            "version": "2026-01-11",
+            "schema": "https://ucp.dev/schemas/shopping/discount.json",
+            "extends": "dev.ucp.shopping.checkout"
+        }))
+        .unwrap();
+        assert_eq!(parsed, discount);
+
+        let root = Capability {
+            extends: None,
+            ..discount
+        };
+        let value = serde_json::to_value(&root).unwrap();
+        assert!(value.get("extends").is_none());
+        assert_eq!(serde_json::from_value::<Capability>(value).unwrap(), root);
+    }
+
     #[test]
     fn parse_capabilities_multi_parent() {
         // Tests diamond pattern: combo extends both discount and fulfillment
@@ -1019,6 +2047,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_capabilities_version_map_picks_newest() {
+        let caps = json!({
+            "dev.ucp.shopping.checkout": {
+                "2026-01-11": { "schema": "https://ucp.dev/schemas/shopping/checkout-old.json" },
+                "2026-06-01": { "schema": "https://ucp.dev/schemas/shopping/checkout.json" }
+            },
+            "dev.ucp.shopping.discount": {
+                "2026-01-11": {
+                    "schema": "https://ucp.dev/schemas/shopping/discount.json",
+                    "extends": "dev.ucp.shopping.checkout"
+                }
+            }
+        });
+        let result = parse_capabilities_object(&caps).unwrap();
+        assert_eq!(result[0].version, "2026-06-01");
+        assert_eq!(
+            result[0].schema_url,
+            "https://ucp.dev/schemas/shopping/checkout.json"
+        );
+        assert_eq!(result[1].version, "2026-01-11");
+        assert_eq!(
+            result[1].extends,
+            Some(vec!["dev.ucp.shopping.checkout".to_string()])
+        );
+
+        let empty = json!({ "dev.ucp.shopping.checkout": {} });
+        assert!(matches!(
+            parse_capabilities_object(&empty),
+            Err(ComposeError::InvalidCapability { .. })
+        ));
+    }
+
     #[test]
     fn parse_capabilities_empty() {
         let caps = json!({});
@@ -1044,6 +2105,42 @@ mod tests {
         assert_eq!(path, "./schemas/checkout.json");
     }
 
+    #[test]
+    fn capability_schema_source_follows_config() {
+        let cap = Capability {
+            name: "dev.ucp.shopping.checkout".to_string(),
+            version: "2026-01-11".to_string(),
+            schema_url: "https://ucp.dev/draft/schemas/shopping/checkout.json".to_string(),
+            extends: None,
+        };
+
+        let remote = capability_schema_source(&cap, &SchemaBaseConfig::default()).unwrap();
+        assert_eq!(remote, SchemaSource::Remote(cap.schema_url.clone()));
+
+        let config = SchemaBaseConfig {
+            local_base: Some(Path::new("site")),
+            remote_base: Some("https://ucp.dev/draft"),
+            ..Default::default()
+        };
+        assert_eq!(
+            capability_schema_source(&cap, &config).unwrap(),
+            SchemaSource::Mapped {
+                path: PathBuf::from("site/schemas/shopping/checkout.json"),
+                stripped: Some("https://ucp.dev/draft".to_string()),
+            }
+        );
+
+        let overrides = HashMap::from([(cap.name.clone(), PathBuf::from("local.json"))]);
+        let config = SchemaBaseConfig {
+            overrides: Some(&overrides),
+            ..config
+        };
+        assert_eq!(
+            capability_schema_source(&cap, &config).unwrap(),
+            SchemaSource::Override(PathBuf::from("local.json"))
+        );
+    }
+
     #[test]
     fn compose_no_extensions() {
         // Setup: single root capability
@@ -1058,11 +2155,51 @@ mod tests {
         let config = SchemaBaseConfig {
             local_base: Some(Path::new("/nonexistent")),
             remote_base: None,
+            overrides: None,
+            strip_extension_ids: false,
+            relax_closed_extensions: false,
+            reject_closed_extensions: false,
+            top_level_extensions: false,
+            check_satisfiable: false,
+            capability_id_pointer: None,
+            envelope: None,
+            payload_pointers: PayloadPointers::default(),
+            load_options: Default::default(),
         };
         let result = compose_schema(&[checkout], &config);
         assert!(matches!(result, Err(ComposeError::SchemaFetch { .. })));
     }
 
+    #[cfg(feature = "remote")]
+    #[test]
+    fn resolve_schema_url_honors_load_options() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/checkout.json")
+            .with_status(301)
+            .with_header("location", "/v2/checkout.json")
+            .create();
+        server
+            .mock("GET", "/v2/checkout.json")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"type": "object"}"#)
+            .create();
+        let url = format!("{}/checkout.json", server.url());
+
+        let config = SchemaBaseConfig::default();
+        assert_eq!(resolve_schema_url(&url, &config).unwrap()["type"], "object");
+
+        let config = SchemaBaseConfig {
+            load_options: LoadOptions {
+                max_redirects: 0,
+                ..LoadOptions::default()
+            },
+            ..Default::default()
+        };
+        let err = resolve_schema_url(&url, &config).unwrap_err();
+        assert!(err.to_string().contains("too many redirects"), "{}", err);
+    }
+
     #[test]
     fn compose_rejects_unbound_schema_url() {
         // dev.ucp.* served from a non-ucp.dev host: rejected before any fetch.
@@ -1111,11 +2248,471 @@ mod tests {
         let config = SchemaBaseConfig {
             local_base: Some(Path::new("/nonexistent")),
             remote_base: Some("https://ucp.dev/draft"),
+            overrides: None,
+            strip_extension_ids: false,
+            relax_closed_extensions: false,
+            reject_closed_extensions: false,
+            top_level_extensions: false,
+            check_satisfiable: false,
+            capability_id_pointer: None,
+            envelope: None,
+            payload_pointers: PayloadPointers::default(),
+            load_options: Default::default(),
         };
         let err = compose_schema(&[cap], &config).unwrap_err();
         assert!(matches!(err, ComposeError::SchemaFetch { .. }));
     }
 
+    #[test]
+    fn compose_uses_schema_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let draft = dir.path().join("checkout_draft.json");
+        std::fs::write(
+            &draft,
+            r#"{"type": "object", "properties": {"draft_field": {"type": "string"}}}"#,
+        )
+        .unwrap();
+
+        let cap = Capability {
+            name: "dev.ucp.shopping.checkout".to_string(),
+            version: "2026-06-01".to_string(),
+            schema_url: "https://ucp.dev/draft/schemas/shopping/checkout.json".to_string(),
+            extends: None,
+        };
+        let overrides = HashMap::from([("dev.ucp.shopping.checkout".to_string(), draft)]);
+        let config = SchemaBaseConfig {
+            local_base: Some(Path::new("/nonexistent")),
+            remote_base: Some("https://ucp.dev/draft"),
+            overrides: Some(&overrides),
+            strip_extension_ids: false,
+            relax_closed_extensions: false,
+            reject_closed_extensions: false,
+            top_level_extensions: false,
+            check_satisfiable: false,
+            capability_id_pointer: None,
+            envelope: None,
+            payload_pointers: PayloadPointers::default(),
+            load_options: Default::default(),
+        };
+        let schema = compose_schema(&[cap], &config).unwrap();
+        assert!(schema["properties"].get("draft_field").is_some());
+    }
+
+    #[test]
+    fn compose_strips_extension_ids_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("checkout.json");
+        let ext = dir.path().join("discount.json");
+        std::fs::write(
+            &base,
+            r#"{"$id": "https://ucp.dev/schemas/checkout.json", "type": "object"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &ext,
+            r##"{
+              "$id": "https://ucp.dev/schemas/discount.json",
+              "$defs": {
+                "dev.ucp.shopping.checkout": {
+                  "$id": "https://ucp.dev/schemas/discount.json#checkout",
+                  "$schema": "https://json-schema.org/draft/2020-12/schema",
+                  "$anchor": "checkout",
+                  "properties": {
+                    "$id": { "type": "string" },
+                    "discount": { "$ref": "#/$defs/discount" },
+                    "source": {
+                      "type": "object",
+                      "const": { "$id": "promo" },
+                      "examples": [{ "$id": "promo" }]
+                    }
+                  }
+                },
+                "discount": { "$id": "https://ucp.dev/schemas/d.json", "type": "object" }
+              }
+            }"##,
+        )
+        .unwrap();
+
+        let caps = [
+            Capability {
+                name: "dev.ucp.shopping.checkout".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: base.display().to_string(),
+                extends: None,
+            },
+            Capability {
+                name: "dev.ucp.shopping.discount".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: ext.display().to_string(),
+                extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+            },
+        ];
+
+        let kept = compose_schema(&caps, &SchemaBaseConfig::default()).unwrap();
+        assert!(kept["allOf"][0].get("$id").is_some());
+
+        let config = SchemaBaseConfig {
+            strip_extension_ids: true,
+            ..Default::default()
+        };
+        let stripped = compose_schema(&caps, &config).unwrap();
+        let branch = &stripped["allOf"][0];
+        assert!(branch.get("$id").is_none());
+        assert!(branch.get("$anchor").is_none());
+        assert!(branch["properties"]["discount"].get("$id").is_none());
+        // A property named `$id` is data, not an identifier.
+        assert!(branch["properties"].get("$id").is_some());
+        // So is an `$id` inside instance data, and the dialect stays declared
+        let source = &branch["properties"]["source"];
+        assert_eq!(source["const"], json!({ "$id": "promo" }));
+        assert_eq!(source["examples"], json!([{ "$id": "promo" }]));
+        assert_eq!(
+            branch["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+    }
+
+    #[test]
+    fn not_required_forbids_only_a_single_name() {
+        let (mut required, mut forbidden) = (Vec::new(), Vec::new());
+        let together = json!({ "required": ["a"], "not": { "required": ["a", "b"] } });
+        collect_required_forbidden(&together, &mut required, &mut forbidden);
+        assert_eq!(required, ["a"]);
+        assert!(forbidden.is_empty());
+
+        let alone = json!({ "not": { "required": ["b"] } });
+        collect_required_forbidden(&alone, &mut required, &mut forbidden);
+        assert_eq!(forbidden, ["b"]);
+    }
+
+    #[test]
+    fn compose_check_satisfiable_flags_required_forbidden_property() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path.display().to_string()
+        };
+        let base = write(
+            "checkout.json",
+            r#"{
+              "type": "object",
+              "required": ["id", "buyer"],
+              "properties": {
+                "id": { "type": "string" },
+                "buyer": { "type": "object", "ucp_request": { "create": "omit" } }
+              }
+            }"#,
+        );
+        let discount = write(
+            "discount.json",
+            r#"{ "$defs": { "dev.ucp.shopping.checkout": {
+              "allOf": [
+                { "$ref": "checkout.json" },
+                { "required": ["discount_code"] }
+              ]
+            } } }"#,
+        );
+        let gift = write(
+            "gift.json",
+            r#"{ "$defs": { "dev.ucp.shopping.checkout": {
+              "properties": { "buyer": false },
+              "not": { "required": ["discount_code"] }
+            } } }"#,
+        );
+        let cap = |name: &str, schema_url: &str, root: bool| Capability {
+            name: format!("dev.ucp.shopping.{}", name),
+            version: "2026-01-11".to_string(),
+            schema_url: schema_url.to_string(),
+            extends: (!root).then(|| vec!["dev.ucp.shopping.checkout".to_string()]),
+        };
+        let caps = [
+            cap("checkout", &base, true),
+            cap("discount", &discount, false),
+            cap("gift", &gift, false),
+        ];
+
+        // Off by default
+        assert!(compose_schema(&caps, &SchemaBaseConfig::default()).is_ok());
+
+        let config = SchemaBaseConfig {
+            check_satisfiable: true,
+            ..Default::default()
+        };
+        match compose_schema(&caps, &config) {
+            Err(ComposeError::UnsatisfiableComposition {
+                property,
+                capabilities,
+            }) => {
+                assert_eq!(property, "discount_code");
+                assert_eq!(
+                    capabilities,
+                    ["dev.ucp.shopping.discount", "dev.ucp.shopping.gift"]
+                );
+            }
+            other => panic!("expected UnsatisfiableComposition, got {:?}", other),
+        }
+
+        // `buyer` is forbidden by gift but only required where its annotation
+        // keeps it, so it alone is not a clear contradiction
+        let caps = [caps[0].clone(), caps[2].clone()];
+        assert!(compose_schema(&caps, &config).is_ok());
+    }
+
+    #[test]
+    fn compose_keeps_root_meta_schema_declarations() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("checkout.json");
+        let ext = dir.path().join("discount.json");
+        std::fs::write(
+            &base,
+            r#"{
+              "$schema": "https://json-schema.org/draft/2020-12/schema",
+              "$vocabulary": {
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                "https://ucp.dev/vocab/annotations": false
+              },
+              "type": "object"
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &ext,
+            r#"{
+              "$schema": "https://json-schema.org/draft/2020-12/schema",
+              "$defs": { "dev.ucp.shopping.checkout": { "type": "object" } }
+            }"#,
+        )
+        .unwrap();
+
+        let caps = [
+            Capability {
+                name: "dev.ucp.shopping.checkout".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: base.display().to_string(),
+                extends: None,
+            },
+            Capability {
+                name: "dev.ucp.shopping.discount".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: ext.display().to_string(),
+                extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+            },
+        ];
+
+        let config = SchemaBaseConfig {
+            strip_extension_ids: true,
+            ..Default::default()
+        };
+        let composed = compose_schema(&caps, &config).unwrap();
+        assert_eq!(
+            composed["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(
+            composed["$vocabulary"]["https://ucp.dev/vocab/annotations"],
+            false
+        );
+        assert!(composed["allOf"].is_array());
+    }
+
+    #[test]
+    fn compose_adds_envelope_branch() {
+        let envelope = json!({
+            "$defs": { "meta": { "type": "object" } },
+            "properties": { "ucp": { "$ref": "#/$defs/meta" }, "id": { "type": "string" } }
+        });
+        let inlined_envelope = json!({
+            "$defs": { "meta": { "type": "object" } },
+            "properties": { "ucp": { "type": "object" }, "id": { "type": "string" } }
+        });
+        let config = SchemaBaseConfig {
+            envelope: Some(&envelope),
+            ..Default::default()
+        };
+        let cap = |name: &str, extends: Option<&str>| Capability {
+            name: name.to_string(),
+            version: "2026-01-11".to_string(),
+            schema_url: name.to_string(),
+            extends: extends.map(|parent| vec![parent.to_string()]),
+        };
+
+        // Single object with an extension: the envelope leads the allOf
+        let caps = [
+            cap("dev.ucp.shopping.checkout", None),
+            cap(
+                "dev.ucp.shopping.discount",
+                Some("dev.ucp.shopping.checkout"),
+            ),
+        ];
+        let (composed, _) = compose_schema_with(&caps, &config, &|c| {
+            Ok(match c.name.as_str() {
+                "dev.ucp.shopping.checkout" => json!({ "type": "object" }),
+                _ => json!({ "$defs": { "dev.ucp.shopping.checkout": { "type": "object" } } }),
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            composed["allOf"],
+            json!([inlined_envelope, { "type": "object" }])
+        );
+
+        // A root alone keeps its keywords (and `$defs`) at the top
+        let (composed, _) = compose_schema_with(&caps[..1], &config, &|_| {
+            Ok(json!({ "type": "object", "properties": { "total": {} } }))
+        })
+        .unwrap();
+        assert_eq!(composed["properties"], json!({ "total": {} }));
+        assert_eq!(composed["allOf"], json!([inlined_envelope]));
+
+        // A container gets the envelope on each operation shape only
+        let caps = [cap("dev.ucp.shopping.catalog.search", None)];
+        let (composed, _) = compose_schema_with(&caps, &config, &|_| {
+            Ok(json!({ "$defs": {
+                "search_response": { "type": "object" },
+                "product": { "type": "object" }
+            } }))
+        })
+        .unwrap();
+        assert_eq!(
+            composed["$defs"]["search_response"]["allOf"],
+            json!([inlined_envelope])
+        );
+        assert!(composed["$defs"]["product"].get("allOf").is_none());
+    }
+
+    #[test]
+    fn compose_accepts_draft7_definitions_in_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("checkout.json");
+        let ext = dir.path().join("loyalty.json");
+        std::fs::write(&base, r#"{"type": "object"}"#).unwrap();
+        std::fs::write(
+            &ext,
+            r##"{
+              "definitions": {
+                "dev.ucp.shopping.checkout": {
+                  "properties": { "points": { "$ref": "#/definitions/points" } }
+                },
+                "points": { "type": "integer", "minimum": 0 }
+              }
+            }"##,
+        )
+        .unwrap();
+
+        let caps = [
+            Capability {
+                name: "dev.ucp.shopping.checkout".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: base.display().to_string(),
+                extends: None,
+            },
+            Capability {
+                name: "dev.ucp.shopping.loyalty".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: ext.display().to_string(),
+                extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+            },
+        ];
+
+        let composed = compose_schema(&caps, &SchemaBaseConfig::default()).unwrap();
+        assert_eq!(
+            composed["allOf"][0]["properties"]["points"],
+            json!({ "type": "integer", "minimum": 0 })
+        );
+    }
+
+    #[test]
+    fn compose_opens_closed_extension_branches_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("checkout.json");
+        let ext = dir.path().join("discount.json");
+        std::fs::write(&base, r#"{"type": "object"}"#).unwrap();
+        std::fs::write(
+            &ext,
+            r##"{
+              "$defs": {
+                "dev.ucp.shopping.checkout": {
+                  "allOf": [
+                    { "$ref": "#/$defs/base" },
+                    {
+                      "properties": { "discount": { "type": "string" } },
+                      "additionalProperties": false
+                    }
+                  ]
+                },
+                "base": { "type": "object" }
+              }
+            }"##,
+        )
+        .unwrap();
+
+        let caps = [
+            Capability {
+                name: "dev.ucp.shopping.checkout".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: base.display().to_string(),
+                extends: None,
+            },
+            Capability {
+                name: "dev.ucp.shopping.discount".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: ext.display().to_string(),
+                extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+            },
+        ];
+
+        let kept = compose_schema(&caps, &SchemaBaseConfig::default()).unwrap();
+        assert_eq!(
+            kept["allOf"][0]["allOf"][1]["additionalProperties"],
+            json!(false)
+        );
+
+        let config = SchemaBaseConfig {
+            relax_closed_extensions: true,
+            ..Default::default()
+        };
+        let opened = compose_schema(&caps, &config).unwrap();
+        let member = &opened["allOf"][0]["allOf"][1];
+        assert!(member.get("additionalProperties").is_none());
+        assert!(member["properties"].get("discount").is_some());
+
+        let config = SchemaBaseConfig {
+            reject_closed_extensions: true,
+            ..Default::default()
+        };
+        let err = compose_schema(&caps, &config).unwrap_err();
+        assert!(matches!(
+            err,
+            ComposeError::ClosedExtensionBranch { ref extension, ref pointer }
+                if extension == "dev.ucp.shopping.discount"
+                    && pointer == "$defs[dev.ucp.shopping.checkout]/allOf/1"
+        ));
+    }
+
+    #[test]
+    fn open_closed_branches_reports_container_shapes() {
+        let mut branch = json!({
+            "$defs": {
+                "create_request": { "additionalProperties": false },
+                "update_request": { "unevaluatedProperties": false },
+                "read_response": { "additionalProperties": { "type": "string" } }
+            }
+        });
+        let mut found = Vec::new();
+        open_closed_branches(&mut branch, "", false, &mut found);
+        found.sort();
+        assert_eq!(
+            found,
+            vec!["/$defs/create_request", "/$defs/update_request"]
+        );
+        // Detection alone leaves the schema untouched.
+        assert_eq!(
+            branch["$defs"]["create_request"]["additionalProperties"],
+            json!(false)
+        );
+    }
+
     #[test]
     fn compose_no_root_error() {
         let discount = Capability {
@@ -1154,6 +2751,106 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn compose_multi_composes_each_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).unwrap();
+            path.display().to_string()
+        };
+        let checkout = write(
+            "checkout.json",
+            r#"{"type": "object", "properties": {"id": {"type": "string"}}}"#,
+        );
+        let loyalty = write(
+            "loyalty.json",
+            r#"{"type": "object", "properties": {"points": {"type": "integer"}}}"#,
+        );
+        let promo = write(
+            "promo.json",
+            r#"{
+              "$defs": {
+                "dev.ucp.shopping.checkout": { "properties": { "promo": { "type": "string" } } },
+                "com.example.loyalty": { "properties": { "bonus": { "type": "integer" } } }
+              }
+            }"#,
+        );
+        let cap = |name: &str, url: &str, extends: Option<Vec<&str>>| Capability {
+            name: name.to_string(),
+            version: "2026-01-11".to_string(),
+            schema_url: url.to_string(),
+            extends: extends.map(|e| e.into_iter().map(String::from).collect()),
+        };
+        let caps = [
+            cap("dev.ucp.shopping.checkout", &checkout, None),
+            cap("com.example.loyalty", &loyalty, None),
+            cap(
+                "com.example.promo",
+                &promo,
+                Some(vec!["dev.ucp.shopping.checkout", "com.example.loyalty"]),
+            ),
+        ];
+        let config = SchemaBaseConfig::default();
+
+        assert!(matches!(
+            compose_schema(&caps, &config),
+            Err(ComposeError::MultipleRootCapabilities { .. })
+        ));
+
+        let schema = compose_multi(&caps, &config).unwrap();
+        let trees = schema["allOf"].as_array().unwrap();
+        assert_eq!(trees.len(), 2);
+        assert!(trees[0]["allOf"][0]["properties"].get("promo").is_some());
+        assert!(trees[1]["allOf"][0]["properties"].get("bonus").is_some());
+
+        // A single tree composes exactly as compose_schema would.
+        assert_eq!(
+            compose_multi(&caps[..1], &config).unwrap(),
+            compose_schema(&caps[..1], &config).unwrap()
+        );
+
+        // A container root can't share an allOf with another tree.
+        let search = write(
+            "search.json",
+            r#"{"$defs": {"search_request": {"type": "object"}, "search_response": {"type": "object"}}}"#,
+        );
+        let mixed = [
+            cap("dev.ucp.shopping.checkout", &checkout, None),
+            cap("dev.ucp.shopping.catalog.search", &search, None),
+        ];
+        assert!(matches!(
+            compose_multi(&mixed, &config),
+            Err(ComposeError::ContainerInMultiRoot { capability })
+                if capability == "dev.ucp.shopping.catalog.search"
+        ));
+    }
+
+    #[test]
+    fn compose_multi_checks_authority_before_loading() {
+        // The checkout URL would bind, but the loyalty one is off-authority;
+        // nothing is fetched before the whole list is checked.
+        let caps = [
+            Capability {
+                name: "dev.ucp.shopping.checkout".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: "https://ucp.dev/schemas/shopping/checkout.json".to_string(),
+                extends: None,
+            },
+            Capability {
+                name: "com.example.loyalty".to_string(),
+                version: "2026-01-11".to_string(),
+                schema_url: "https://evil.test/loyalty.json".to_string(),
+                extends: None,
+            },
+        ];
+        assert!(matches!(
+            compose_multi(&caps, &SchemaBaseConfig::default()),
+            Err(ComposeError::NamespaceBindingViolation { capability, .. })
+                if capability == "com.example.loyalty"
+        ));
+    }
+
     #[test]
     fn compose_unknown_parent_error() {
         let checkout = Capability {
@@ -1554,6 +3251,94 @@ mod tests {
         assert!(result.is_ok(), "expected Ok, got {:?}", result);
     }
 
+    #[test]
+    fn compose_output_independent_of_capability_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+
+        let checkout = Capability {
+            name: "dev.ucp.shopping.checkout".to_string(),
+            version: "2026-06-01".to_string(),
+            schema_url: write(
+                "checkout.json",
+                r#"{"type": "object", "properties": {"id": {"type": "string"}}}"#,
+            ),
+            extends: None,
+        };
+        let ext = |name: &str, field: &str| Capability {
+            name: format!("com.acme.{}", name),
+            version: "2026-06-01".to_string(),
+            schema_url: write(
+                &format!("{}.json", name),
+                &format!(
+                    r#"{{"$defs": {{"dev.ucp.shopping.checkout": {{
+                        "type": "object",
+                        "properties": {{"{}": {{"type": "string"}}}}
+                    }}}}}}"#,
+                    field
+                ),
+            ),
+            extends: Some(vec!["dev.ucp.shopping.checkout".to_string()]),
+        };
+        let gift = ext("gift", "gift_note");
+        let loyalty = ext("loyalty", "points");
+        let wallet = ext("wallet", "wallet_id");
+
+        let config = SchemaBaseConfig::default();
+        let expected = compose_schema(
+            &[
+                checkout.clone(),
+                gift.clone(),
+                loyalty.clone(),
+                wallet.clone(),
+            ],
+            &config,
+        )
+        .unwrap();
+        for order in [
+            vec![
+                wallet.clone(),
+                checkout.clone(),
+                loyalty.clone(),
+                gift.clone(),
+            ],
+            vec![
+                loyalty.clone(),
+                wallet.clone(),
+                gift.clone(),
+                checkout.clone(),
+            ],
+            vec![
+                gift.clone(),
+                checkout.clone(),
+                wallet.clone(),
+                loyalty.clone(),
+                gift.clone(),
+            ],
+        ] {
+            assert_eq!(compose_schema(&order, &config).unwrap(), expected);
+        }
+        assert_eq!(expected["allOf"].as_array().unwrap().len(), 3);
+        assert!(expected["allOf"][0]["properties"]
+            .get("gift_note")
+            .is_some());
+
+        // The same name with another version is a conflict, not a duplicate
+        let gift_v2 = Capability {
+            version: "2026-09-01".to_string(),
+            ..gift.clone()
+        };
+        let err = compose_schema(&[checkout, gift, gift_v2, loyalty], &config).unwrap_err();
+        assert!(matches!(
+            err,
+            ComposeError::ConflictingCapability { ref name } if name == "com.acme.gift"
+        ));
+    }
+
     // -- Version constraint checking (standalone function) tests --
 
     fn make_capabilities() -> Vec<Capability> {
@@ -1,10 +1,13 @@
 //! Error types for UCP schema resolution and validation.
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Errors during schema composition from UCP capability metadata.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ComposeError {
     #[error("payload is not self-describing: missing ucp.capabilities (response) or meta.profile (request)")]
     NotSelfDescribing,
@@ -58,6 +61,11 @@ pub enum ComposeError {
     #[error("invalid URL '{url}': {message}")]
     InvalidUrl { url: String, message: String },
 
+    /// The same capability is listed more than once with different versions,
+    /// schemas, or parents, so it is ambiguous which entry to compose.
+    #[error("capability '{name}' is listed more than once with different definitions")]
+    ConflictingCapability { name: String },
+
     #[error("extension '{extension}' requires {target} {range} but found {actual}")]
     VersionConstraintViolation {
         extension: String,
@@ -68,6 +76,52 @@ pub enum ComposeError {
 
     #[error("capability '{capability}' fails namespace authority binding: {message}")]
     NamespaceBindingViolation { capability: String, message: String },
+
+    /// One composed branch requires a property that another (or the same)
+    /// branch forbids, so no payload satisfies the `allOf`. Only raised with
+    /// `SchemaBaseConfig::check_satisfiable`.
+    #[error(
+        "unsatisfiable composition: \"{property}\" is both required and forbidden ({})",
+        capabilities.join(", ")
+    )]
+    UnsatisfiableComposition {
+        property: String,
+        capabilities: Vec<String>,
+    },
+
+    /// An extension branch closes itself to unknown properties, so under the
+    /// composed `allOf` it rejects every field another branch adds. Only
+    /// raised with `SchemaBaseConfig::reject_closed_extensions`.
+    #[error(
+        "extension '{extension}' closes {pointer} to unknown properties, rejecting \
+         fields added by other branches"
+    )]
+    ClosedExtensionBranch { extension: String, pointer: String },
+
+    /// A capability's schema declares a different capability id (or none) at
+    /// `SchemaBaseConfig::capability_id_pointer`.
+    #[error(
+        "capability '{name}' schema declares {}",
+        declared.as_ref().map_or("no capability id".to_string(), |d| format!("capability id '{}'", d))
+    )]
+    CapabilityIdMismatch {
+        name: String,
+        declared: Option<String>,
+    },
+
+    /// The `SchemaBaseConfig::envelope` schema has an internal `$ref` that
+    /// points at nothing.
+    #[error("invalid envelope schema: {message}")]
+    InvalidEnvelopeSchema { message: String },
+
+    /// `compose_multi` was given several trees and one of them is rooted at a
+    /// container capability, whose operation shape can't be combined under a
+    /// plain `allOf`.
+    #[error(
+        "capability '{capability}' is a container; compose it on its own rather than \
+         alongside other roots"
+    )]
+    ContainerInMultiRoot { capability: String },
 }
 
 impl ComposeError {
@@ -82,11 +136,17 @@ impl ComposeError {
 
 /// Errors during schema resolution.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ResolveError {
     // IO errors (exit code 3)
     #[error("file not found: {path}")]
     FileNotFound { path: PathBuf },
 
+    /// The path exists but is a directory, typically a relative `$ref` that
+    /// resolved to a folder.
+    #[error("{path} is a directory, expected a schema file")]
+    NotAFile { path: PathBuf },
+
     #[error("cannot read {path}: {source}")]
     ReadError {
         path: PathBuf,
@@ -95,7 +155,7 @@ pub enum ResolveError {
     },
 
     #[cfg(feature = "remote")]
-    #[error("failed to fetch {url}: {source}")]
+    #[error("failed to fetch {url}: {}", network_cause(source))]
     NetworkError {
         url: String,
         #[source]
@@ -109,16 +169,48 @@ pub enum ResolveError {
         source: serde_json::Error,
     },
 
+    #[cfg(feature = "yaml")]
+    #[error("invalid YAML: {source}")]
+    InvalidYaml {
+        #[source]
+        source: serde_yaml::Error,
+    },
+
     // Schema errors (exit code 2)
     #[error("invalid annotation at {path}: expected string or object, got {actual}")]
     InvalidAnnotationType { path: String, actual: String },
 
-    #[error("unknown visibility \"{value}\" at {path}: expected omit, required, or optional")]
+    #[error(
+        "unknown visibility \"{value}\" at {path}: expected omit, required, optional, deprecate, or inherit"
+    )]
     UnknownVisibility { path: String, value: String },
 
+    /// A `ucp_strict` annotation that is neither a boolean nor an object
+    /// mapping operations to booleans.
+    #[error("invalid ucp_strict at {path}: expected boolean or object of booleans, got {actual}")]
+    InvalidStrictAnnotation { path: String, actual: String },
+
+    /// A per-operation `min_items` constraint that is not a non-negative integer.
+    #[error("invalid min_items at {path}: expected non-negative integer, got {actual}")]
+    InvalidMinItems { path: String, actual: String },
+
+    /// A per-operation annotation object sets a keyword outside
+    /// `UCP_OVERRIDABLE_KEYWORDS`, or gives one a value of the wrong type.
+    #[error("keyword \"{keyword}\" at {path} {reason}")]
+    InvalidOverrideKeyword {
+        path: String,
+        keyword: String,
+        reason: String,
+    },
+
     #[error("invalid schema transition at {path}: {message}")]
     InvalidSchemaTransition { path: String, message: String },
 
+    /// A per-operation annotation names an operation outside the set allowed
+    /// by `ResolveOptions::validate_operations`.
+    #[error("unknown operation \"{op}\" at {path}: not in the allowed operation list")]
+    UnknownOperation { path: String, op: String },
+
     /// allOf extension tries to weaken a field that base declares as required.
     /// Monotonicity rule: extensions can narrow (optional→omit) or strengthen
     /// (optional→required) but never weaken required fields.
@@ -143,8 +235,30 @@ pub enum ResolveError {
         ext_type: String,
     },
 
-    #[error("invalid schema: {message}")]
-    InvalidSchema { message: String },
+    /// A conditional or dependent `required` entry names a property that was
+    /// omitted at the same object level, so the resolved schema contradicts
+    /// itself: any payload that triggers the constraint cannot satisfy it.
+    /// Only raised with `ResolveOptions::reject_dangling_required`.
+    #[error(
+        "dangling required at {path}: \"{property}\" is omitted for this operation \
+         but still required here"
+    )]
+    DanglingRequired { path: String, property: String },
+
+    /// Every branch of an `anyOf`/`oneOf` carries an annotation omitting it
+    /// for this operation, which would leave an empty (invalid) composition.
+    #[error("every branch of {path} is omitted for this operation")]
+    EmptyComposition { path: String },
+
+    /// The JSON Schema validator rejected a (resolved) schema. `path` is a
+    /// JSON Pointer into that schema when the failing location is known — the
+    /// offending keyword for meta-schema violations, or the `$ref` that could
+    /// not be resolved.
+    #[error("invalid schema{}: {message}", at_path(path))]
+    InvalidSchema {
+        message: String,
+        path: Option<String>,
+    },
 
     /// A container-shaped capability schema has no message body for the
     /// requested `(op, direction)`. The body lives at `$defs/{op}_{direction}`;
@@ -163,24 +277,73 @@ pub enum ResolveError {
     #[error("schema has no $defs entry '{def}'; available: [{available}]")]
     DefNotFound { def: String, available: String },
 
+    /// A `--schema-id` / `select_schema_by_id` selector matches no `$id` in a
+    /// multi-schema bundle.
+    #[error("bundle has no schema with $id '{id}'; available: [{available}]")]
+    SchemaIdNotFound { id: String, available: String },
+
+    /// A `--schema-id` / `select_schema_by_id` selector matches more than one
+    /// schema in the bundle.
+    #[error("$id '{id}' is ambiguous; declared at: [{paths}]")]
+    AmbiguousSchemaId { id: String, paths: String },
+
+    /// [`crate::ResolveOptionsBuilder::build`] was called without a required
+    /// option (`direction` or `operation`).
+    #[error("resolve options are missing required `{option}`")]
+    IncompleteOptions { option: &'static str },
+
     #[error("failed to bundle schema: {message}")]
     BundleError { message: String },
+
+    /// [`crate::assert_bundled`] found an external `$ref` in a schema expected
+    /// to be self-contained.
+    #[error("$ref \"{reference}\" at {path} was not bundled")]
+    UnbundledRef { path: String, reference: String },
+
+    /// Loading the target of an external `$ref` failed while bundling.
+    /// `referrer` is the file (or URL) holding the `$ref` and `pointer` the
+    /// JSON Pointer to it there; the exit code is that of `source`.
+    #[error("{source} (loading $ref \"{reference}\" at {pointer} in {referrer})")]
+    RefTargetError {
+        reference: String,
+        referrer: String,
+        pointer: String,
+        #[source]
+        source: Box<ResolveError>,
+    },
+
+    /// A JSON Patch document is malformed or one of its operations failed
+    /// (unknown op, path not found, failed `test`).
+    #[error("invalid patch: {message}")]
+    InvalidPatch { message: String },
 }
 
 /// Errors during validation.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ValidateError {
     #[error(transparent)]
     Resolve(#[from] ResolveError),
 
+    /// Composing the schema from the payload's capability metadata failed.
+    /// Only produced by entry points that compose, such as `validate_many`.
+    #[error(transparent)]
+    Compose(#[from] ComposeError),
+
     #[error("validation failed with {} error(s)", errors.len())]
     Invalid { errors: Vec<SchemaError> },
+
+    /// The schema for a `validate_many` group could not be built; every
+    /// payload in the group holds the same error.
+    #[error(transparent)]
+    Group(Arc<ValidateError>),
 }
 
 /// Single validation error with path context.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SchemaError {
-    /// JSON Pointer (RFC 6901) to the invalid field.
+    /// JSON Pointer (RFC 6901) to the invalid field, or a JSONPath when
+    /// requested with `ValidateOptions::path_format`.
     pub path: String,
     /// Human-readable error message.
     pub message: String,
@@ -192,13 +355,120 @@ impl std::fmt::Display for SchemaError {
     }
 }
 
+/// Cloneable snapshot of any error from this crate.
+///
+/// The error enums hold non-`Clone` sources (`std::io::Error`,
+/// `reqwest::Error`), so they cannot be stored or fanned out to several
+/// sinks directly. A report keeps what consumers usually need: the exit code,
+/// the rendered message, and the location the error refers to.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ErrorReport {
+    /// Exit code the CLI would use for this error (see `exit_code`).
+    pub code: i32,
+    /// Human-readable error message (the error's `Display` output).
+    pub message: String,
+    /// JSON Pointer, file path, or URL the error refers to, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl std::fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<&ResolveError> for ErrorReport {
+    fn from(err: &ResolveError) -> Self {
+        let path = match err {
+            ResolveError::FileNotFound { path }
+            | ResolveError::NotAFile { path }
+            | ResolveError::ReadError { path, .. } => Some(path.display().to_string()),
+            #[cfg(feature = "remote")]
+            ResolveError::NetworkError { url, .. } => Some(url.clone()),
+            ResolveError::InvalidAnnotationType { path, .. }
+            | ResolveError::UnknownVisibility { path, .. }
+            | ResolveError::InvalidStrictAnnotation { path, .. }
+            | ResolveError::InvalidMinItems { path, .. }
+            | ResolveError::InvalidOverrideKeyword { path, .. }
+            | ResolveError::UnbundledRef { path, .. }
+            | ResolveError::InvalidSchemaTransition { path, .. }
+            | ResolveError::UnknownOperation { path, .. }
+            | ResolveError::MonotonicityViolation { path, .. }
+            | ResolveError::TypeConflict { path, .. }
+            | ResolveError::DanglingRequired { path, .. }
+            | ResolveError::EmptyComposition { path } => Some(path.clone()),
+            ResolveError::InvalidSchema { path, .. } => path.clone(),
+            ResolveError::RefTargetError { source, .. } => ErrorReport::from(&**source).path,
+            _ => None,
+        };
+        ErrorReport {
+            code: err.exit_code(),
+            message: err.to_string(),
+            path,
+        }
+    }
+}
+
+impl From<&ComposeError> for ErrorReport {
+    fn from(err: &ComposeError) -> Self {
+        let path = match err {
+            ComposeError::SchemaFetch { url, .. }
+            | ComposeError::ProfileFetch { url, .. }
+            | ComposeError::InvalidUrl { url, .. } => Some(url.clone()),
+            _ => None,
+        };
+        ErrorReport {
+            code: err.exit_code(),
+            message: err.to_string(),
+            path,
+        }
+    }
+}
+
+impl From<&ValidateError> for ErrorReport {
+    fn from(err: &ValidateError) -> Self {
+        match err {
+            ValidateError::Resolve(e) => e.into(),
+            ValidateError::Compose(e) => e.into(),
+            ValidateError::Group(e) => e.as_ref().into(),
+            ValidateError::Invalid { .. } => ErrorReport {
+                code: err.exit_code(),
+                message: err.to_string(),
+                path: None,
+            },
+        }
+    }
+}
+
+/// Format an optional location as ` at {path}` for error messages.
+fn at_path(path: &Option<String>) -> String {
+    path.as_ref()
+        .map(|p| format!(" at {}", p))
+        .unwrap_or_default()
+}
+
+/// A reqwest error for messages; redirect failures add the policy's reason
+/// (e.g. the redirect limit), which reqwest's own message leaves out.
+#[cfg(feature = "remote")]
+fn network_cause(source: &reqwest::Error) -> String {
+    use std::error::Error as _;
+    match source.source() {
+        Some(reason) if source.is_redirect() => format!("{}: {}", source, reason),
+        _ => source.to_string(),
+    }
+}
+
 impl ResolveError {
     /// Returns the exit code for this error type.
     pub fn exit_code(&self) -> i32 {
         match self {
-            ResolveError::FileNotFound { .. } | ResolveError::ReadError { .. } => 3,
+            ResolveError::FileNotFound { .. }
+            | ResolveError::NotAFile { .. }
+            | ResolveError::ReadError { .. } => 3,
             #[cfg(feature = "remote")]
             ResolveError::NetworkError { .. } => 3,
+            ResolveError::RefTargetError { source, .. } => source.exit_code(),
             _ => 2,
         }
     }
@@ -209,9 +479,72 @@ impl ValidateError {
     pub fn exit_code(&self) -> i32 {
         match self {
             ValidateError::Resolve(e) => e.exit_code(),
+            ValidateError::Compose(e) => e.exit_code(),
+            ValidateError::Group(e) => e.exit_code(),
             ValidateError::Invalid { .. } => 1,
         }
     }
+
+    /// Validation messages grouped by path (see [`group_errors`]), or `None`
+    /// if the payload was never validated.
+    pub fn errors_by_path(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        match self {
+            ValidateError::Invalid { errors } => Some(group_errors(errors)),
+            _ => None,
+        }
+    }
+
+    /// Number of validation errors; 0 if the payload was never validated.
+    pub fn error_count(&self) -> usize {
+        match self {
+            ValidateError::Invalid { errors } => errors.len(),
+            _ => 0,
+        }
+    }
+
+    /// One-line summary of the failing paths, e.g.
+    /// `3 validation errors: /a, /b, /c (+0 more)`; empty if the payload was
+    /// never validated. Lists at most three paths, with the root as `/`.
+    pub fn summary(&self) -> String {
+        const SHOWN: usize = 3;
+        let ValidateError::Invalid { errors } = self else {
+            return String::new();
+        };
+        let paths: Vec<&str> = errors
+            .iter()
+            .take(SHOWN)
+            .map(|e| {
+                if e.path.is_empty() {
+                    "/"
+                } else {
+                    e.path.as_str()
+                }
+            })
+            .collect();
+        format!(
+            "{} validation error{}: {} (+{} more)",
+            errors.len(),
+            if errors.len() == 1 { "" } else { "s" },
+            paths.join(", "),
+            errors.len().saturating_sub(SHOWN)
+        )
+    }
+}
+
+/// Group validation messages by the JSON Pointer they apply to.
+///
+/// A derived view for per-field error display; the flat error list stays the
+/// source of truth. Paths sort lexically, and each path keeps its messages in
+/// the order they were reported. Errors on the payload root use `""`.
+pub fn group_errors(errors: &[SchemaError]) -> BTreeMap<String, Vec<String>> {
+    let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for error in errors {
+        grouped
+            .entry(error.path.clone())
+            .or_default()
+            .push(error.message.clone());
+    }
+    grouped
 }
 
 #[cfg(test)]
@@ -249,6 +582,64 @@ mod tests {
         assert_eq!(err.exit_code(), 1);
     }
 
+    #[test]
+    fn validate_error_count_and_summary() {
+        let error = |path: &str| SchemaError {
+            path: path.into(),
+            message: "invalid".into(),
+        };
+        let err = ValidateError::Invalid {
+            errors: vec![error("/a"), error("/b"), error("/c")],
+        };
+        assert_eq!(err.error_count(), 3);
+        assert_eq!(err.summary(), "3 validation errors: /a, /b, /c (+0 more)");
+
+        let err = ValidateError::Invalid {
+            errors: vec![
+                error(""),
+                error("/a"),
+                error("/b"),
+                error("/c"),
+                error("/d"),
+            ],
+        };
+        assert_eq!(err.error_count(), 5);
+        assert_eq!(err.summary(), "5 validation errors: /, /a, /b (+2 more)");
+
+        let err = ValidateError::Invalid {
+            errors: vec![error("/id")],
+        };
+        assert_eq!(err.summary(), "1 validation error: /id (+0 more)");
+
+        let err = ValidateError::Compose(ComposeError::EmptyCapabilities);
+        assert_eq!(err.error_count(), 0);
+        assert_eq!(err.summary(), "");
+    }
+
+    #[test]
+    fn error_report_snapshots_errors() {
+        let err = ResolveError::ReadError {
+            path: PathBuf::from("schema.json"),
+            source: std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"),
+        };
+        let report = ErrorReport::from(&err);
+        assert_eq!(report.code, 3);
+        assert_eq!(report.message, "cannot read schema.json: denied");
+        assert_eq!(report.path.as_deref(), Some("schema.json"));
+
+        let err = ValidateError::Resolve(ResolveError::UnknownVisibility {
+            path: "/properties/id".into(),
+            value: "readonly".into(),
+        });
+        let report = ErrorReport::from(&err);
+        assert_eq!(report.code, 2);
+        assert_eq!(report.path.as_deref(), Some("/properties/id"));
+
+        let report = ErrorReport::from(&ComposeError::EmptyCapabilities);
+        assert_eq!(report.clone(), report);
+        assert_eq!(report.path, None);
+    }
+
     #[test]
     fn schema_error_display() {
         let err = SchemaError {
@@ -257,4 +648,29 @@ mod tests {
         };
         assert_eq!(err.to_string(), "/buyer/email: expected string, got number");
     }
+
+    #[test]
+    fn group_errors_buckets_by_path() {
+        let error = |path: &str, message: &str| SchemaError {
+            path: path.into(),
+            message: message.into(),
+        };
+        let err = ValidateError::Invalid {
+            errors: vec![
+                error("/buyer/email", "expected string"),
+                error("", "missing id"),
+                error("/buyer/email", "does not match format"),
+            ],
+        };
+
+        let grouped = err.errors_by_path().unwrap();
+        assert_eq!(grouped.keys().collect::<Vec<_>>(), ["", "/buyer/email"]);
+        assert_eq!(
+            grouped["/buyer/email"],
+            ["expected string", "does not match format"]
+        );
+        assert!(ValidateError::Compose(ComposeError::EmptyCapabilities)
+            .errors_by_path()
+            .is_none());
+    }
 }
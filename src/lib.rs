@@ -40,8 +40,14 @@
 //! | `"omit"` | Remove field | Remove from required |
 //! | `"required"` | Keep field | Add to required |
 //! | `"optional"` | Keep field | Remove from required |
+//! | `"deprecate"` | Keep field, set `deprecated: true` | Remove from required |
+//! | `"inherit"` | As the enclosing property resolved | As the enclosing property resolved |
 //! | (none) | Keep field | Preserve original |
 //!
+//! In strict mode ([`ResolveOptions::strict`]), every field a visibility keeps
+//! is recursed into and closed with `additionalProperties: false`, whatever
+//! that visibility is.
+//!
 //! # Annotation Format
 //!
 //! Annotations can be shorthand (applies to all operations):
@@ -53,32 +59,97 @@
 //! ```json
 //! { "ucp_request": { "create": "omit", "update": "required" } }
 //! ```
+//!
+//! # Logging
+//!
+//! With the `tracing` feature enabled, the library emits `tracing` events:
+//! `debug` for schema loads, fetches, and bundling steps, `trace` for each
+//! annotation decision, and `warn` for fallbacks (e.g. a schema URL that does
+//! not match the configured remote base). Nothing is printed unless the
+//! consumer installs a subscriber.
 
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "full")]
+mod codegen;
+mod compare;
+#[cfg(feature = "full")]
 mod compose;
 mod error;
+#[cfg(feature = "full")]
 mod linter;
+#[cfg(feature = "full")]
 mod loader;
+#[cfg(feature = "full")]
 mod namespace;
+#[cfg(feature = "full")]
+mod openapi;
 mod resolver;
 mod types;
+#[cfg(feature = "full")]
 mod validator;
 
+#[cfg(feature = "full")]
+pub use codegen::{flatten_for_codegen, CodegenFlattening, CODEGEN_UNSUPPORTED_KEYWORDS};
+#[cfg(feature = "full")]
+pub use compare::schema_fingerprint;
+pub use compare::schemas_equal;
+pub use error::{
+    group_errors, ComposeError, ErrorReport, ResolveError, SchemaError, ValidateError,
+};
+pub use resolver::{
+    combine_visibility, get_visibility, get_visibility_with_options, resolve, resolve_detailed,
+    resolve_explained, resolve_with_stats, strip_annotations, visibility_of,
+};
+pub use types::{
+    json_type_name, Direction, Omission, OmitMatch, RequiredPolicy, Requires, ResolveOptions,
+    ResolveOptionsBuilder, ResolveStats, SchemaTransitionInfo, VersionConstraint, Visibility,
+    UCP_ANNOTATIONS, UCP_DEFAULTS, UCP_OVERRIDABLE_KEYWORDS, UCP_STRICT, VALID_OPERATIONS,
+};
+
+#[cfg(feature = "full")]
 pub use compose::{
-    capability_short_name, check_version_constraints, compose_from_payload, compose_schema,
-    detect_direction, extract_capabilities, extract_capabilities_from_profile,
-    extract_jsonrpc_payload, is_container_schema, Capability, DetectedDirection, SchemaBaseConfig,
-    VersionViolation,
+    capability_schema_source, capability_short_name, check_version_constraints,
+    compose_from_payload, compose_from_payload_detailed, compose_multi, compose_schema,
+    detect_direction, detect_direction_at, extract_capabilities, extract_capabilities_from_profile,
+    extract_jsonrpc_payload, is_container_schema, payload_metadata, payload_metadata_at,
+    Capability, DetectedDirection, PayloadMetadata, PayloadPointers, SchemaBaseConfig,
+    SchemaSource, VersionViolation,
+};
+#[cfg(feature = "full")]
+pub use linter::{
+    lint, lint_file, lint_file_with_options, lint_value, lint_value_with_options,
+    lint_with_options, AnnotationCoverage, Diagnostic, FileResult, FileStatus, LintOptions,
+    LintResult, PathStyle, Severity, LINT_CONFIG_FILES,
 };
-pub use error::{ComposeError, ResolveError, SchemaError, ValidateError};
-pub use linter::{lint, lint_file, Diagnostic, FileResult, FileStatus, LintResult, Severity};
+#[cfg(feature = "full")]
 pub use loader::{
-    bundle_refs, bundle_refs_with_url_mapping, is_url, load_schema, load_schema_auto,
-    load_schema_str, navigate_fragment,
+    assert_bundled, bundle_refs, bundle_refs_traced, bundle_refs_with_url_mapping, collect_refs,
+    inline_internal_refs, is_internal_ref, is_url, load_schema, load_schema_as, load_schema_auto,
+    load_schema_auto_as, load_schema_auto_with, load_schema_str, load_schema_str_as,
+    navigate_fragment, FetchedSchema, InputFormat, LoadOptions, DEFAULT_MAX_REDIRECTS,
 };
+#[cfg(feature = "full")]
 pub use namespace::{reverse_labels, validate_binding, BindingError};
-pub use resolver::{resolve, strip_annotations};
-pub use types::{Direction, Requires, ResolveOptions, VersionConstraint, Visibility};
-pub use validator::{select_operation_schema, validate, validate_against_schema};
+#[cfg(feature = "full")]
+pub use openapi::{to_openapi30, OpenApiConversion, UNSUPPORTED_KEYWORDS};
+#[cfg(feature = "full")]
+pub use resolver::resolve_property;
+#[cfg(feature = "full")]
+pub use validator::{
+    resolve_layered, select_operation_schema, select_schema_by_id, unknown_keywords, validate,
+    validate_against_schema, validate_against_schema_with_options,
+    validate_against_schema_with_refs, validate_and_normalize, validate_layered, validate_many,
+    validate_with_options, PathFormat, ValidateOptions,
+};
 
+#[cfg(feature = "patch")]
+pub use loader::apply_patch;
 #[cfg(feature = "remote")]
-pub use loader::{bundle_refs_remote, load_schema_url};
+pub use loader::{bundle_refs_remote, load_schema_url, load_schema_url_with};
+
+#[cfg(feature = "async")]
+pub use compose::compose_from_payload_async;
+#[cfg(feature = "async")]
+pub use loader::{bundle_refs_remote_async, load_schema_url_async, load_schema_url_with_async};
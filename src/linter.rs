@@ -2,28 +2,48 @@
 //!
 //! Validates schema files for:
 //! - JSON syntax errors
-//! - Broken $ref references (file not found, anchor not found)
+//! - Broken $ref references (file not found, anchor not found, non-schema target)
 //! - Invalid ucp_* annotation values
+//! - Composition keywords (`allOf`/`anyOf`/`oneOf`) that are not arrays
+//! - Defaults on fields that an operation omits
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
-use serde::Serialize;
-use serde_json::Value;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
 
-use crate::loader::{load_schema, navigate_fragment};
+use crate::loader::{
+    id_base, is_schema_value, load_schema, load_schema_as, navigate_fragment, rebase_ref,
+    split_ref, InputFormat,
+};
+use crate::resolver::property_visibility;
 use crate::types::{
-    is_valid_schema_transition, is_valid_version, json_type_name, VersionConstraint, Visibility,
-    UCP_ANNOTATIONS, VALID_OPERATIONS,
+    is_constraint_object, is_valid_schema_transition, is_valid_version, json_type_name,
+    override_value_error, Direction, VersionConstraint, Visibility, UCP_ANNOTATIONS, UCP_DEFAULTS,
+    UCP_OVERRIDABLE_KEYWORDS, VALID_OPERATIONS,
 };
 
 /// Severity level for diagnostics.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum Severity {
     Error,
     Warning,
 }
 
+impl Severity {
+    /// Lowercase name, as serialized: `"error"` or `"warning"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
 /// A single diagnostic message from linting.
 #[derive(Debug, Clone, Serialize)]
 pub struct Diagnostic {
@@ -42,6 +62,78 @@ pub struct FileResult {
     pub status: FileStatus,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub diagnostics: Vec<Diagnostic>,
+    /// Set when [`LintOptions::coverage`] is on and the file parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<AnnotationCoverage>,
+}
+
+/// How thoroughly properties carry `ucp_request` / `ucp_response`.
+///
+/// Counts entries of every `properties` keyword in a schema position, at any
+/// depth; `properties` inside instance data such as `examples` is not a
+/// schema and is skipped. An annotation
+/// is shorthand when it is a single value for all operations (a string, or a
+/// boolean under [`LintOptions::boolean_shorthand`]) and per-operation when
+/// it is an object keyed by operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AnnotationCoverage {
+    pub properties: usize,
+    /// Properties with `ucp_request`, `ucp_response`, or both, their own or
+    /// from the enclosing object's `ucp_defaults`.
+    pub annotated: usize,
+    pub request: usize,
+    pub response: usize,
+    pub shorthand: usize,
+    pub per_operation: usize,
+}
+
+impl AnnotationCoverage {
+    /// Percentage of properties annotated; 100 when there are none.
+    pub fn percent(&self) -> f64 {
+        if self.properties == 0 {
+            100.0
+        } else {
+            self.annotated as f64 * 100.0 / self.properties as f64
+        }
+    }
+
+    fn add(&mut self, other: &AnnotationCoverage) {
+        self.properties += other.properties;
+        self.annotated += other.annotated;
+        self.request += other.request;
+        self.response += other.response;
+        self.shorthand += other.shorthand;
+        self.per_operation += other.per_operation;
+    }
+
+    /// Count one property; `defaults` is the enclosing object's
+    /// `ucp_defaults`, which annotates any direction the property leaves out.
+    fn record_property(&mut self, property: &Value, defaults: Option<&Value>) {
+        self.properties += 1;
+        let mut annotated = false;
+        for &key in UCP_ANNOTATIONS {
+            let Some(annotation) = property
+                .get(key)
+                .or_else(|| defaults.and_then(|d| d.get(key)))
+            else {
+                continue;
+            };
+            annotated = true;
+            if key == "ucp_request" {
+                self.request += 1;
+            } else {
+                self.response += 1;
+            }
+            if annotation.is_object() {
+                self.per_operation += 1;
+            } else {
+                self.shorthand += 1;
+            }
+        }
+        if annotated {
+            self.annotated += 1;
+        }
+    }
 }
 
 /// Status of a linted file.
@@ -63,6 +155,124 @@ pub struct LintResult {
     pub errors: usize,
     pub warnings: usize,
     pub results: Vec<FileResult>,
+    /// Totals over all files; set when [`LintOptions::coverage`] is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<AnnotationCoverage>,
+}
+
+/// How file paths are rendered in lint results and CLI messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// File results relative to the linted path; other paths as given.
+    #[default]
+    Relative,
+    /// Relative to the current working directory (absolute when outside it).
+    CwdRelative,
+    /// Absolute, joined onto the current working directory.
+    Absolute,
+}
+
+impl PathStyle {
+    /// Render `path` in this style. `base` is the root that `Relative`
+    /// strips; pass an empty path to keep `path` as given.
+    pub fn render(self, path: &Path, base: &Path) -> PathBuf {
+        match self {
+            PathStyle::Relative => path.strip_prefix(base).unwrap_or(path).to_path_buf(),
+            PathStyle::Absolute => absolute_path(path),
+            PathStyle::CwdRelative => {
+                let absolute = absolute_path(path);
+                match std::env::current_dir() {
+                    Ok(cwd) => match absolute.strip_prefix(&cwd) {
+                        Ok(rel) if rel.as_os_str().is_empty() => PathBuf::from("."),
+                        Ok(rel) => rel.to_path_buf(),
+                        Err(_) => absolute,
+                    },
+                    Err(_) => absolute,
+                }
+            }
+        }
+    }
+}
+
+/// Accepts the same names as the CLI: `relative`, `cwd-relative`, `absolute`.
+impl<'de> Deserialize<'de> for PathStyle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(PathStyle::Relative),
+            "cwd-relative" => Ok(PathStyle::CwdRelative),
+            "absolute" => Ok(PathStyle::Absolute),
+            other => Err(format!(
+                "unknown path style '{}': expected relative, cwd-relative, or absolute",
+                other
+            )),
+        }
+    }
+}
+
+/// Join a relative path onto the current directory, dropping `.` components.
+/// Lexical only: symlinks are not resolved and the path need not exist.
+fn absolute_path(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+    joined
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Options controlling which constructs the linter accepts.
+///
+/// Deserializes from a lint config file keyed by field name (e.g.
+/// `{"strict": true, "exclude": ["fixtures"]}`); missing keys keep their
+/// defaults and unknown keys are rejected. In code, start from
+/// `LintOptions::default()` and set fields; new fields may be added in minor
+/// releases.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LintOptions {
+    /// Treat warnings as errors when counting failed files.
+    pub strict: bool,
+    /// Accept `true`/`false` as `ucp_*` annotation shorthand instead of
+    /// reporting E005 (see [`crate::ResolveOptions::boolean_shorthand`]).
+    pub boolean_shorthand: bool,
+    /// Parse files as this format instead of inferring it from the extension.
+    /// Directories are then searched for this format's extensions, and an
+    /// explicitly named file is linted whatever its extension.
+    pub input_format: Option<InputFormat>,
+    /// How to render `FileResult::file` and `Diagnostic::file`. The default
+    /// strips the linted path from file results and leaves diagnostics as
+    /// walked.
+    pub path_style: PathStyle,
+    /// Skip W002 for files without `$id`, for projects that omit it by design.
+    pub allow_missing_id: bool,
+    /// Glob patterns for files and directories to skip when walking a
+    /// directory. A pattern containing `/` matches the path relative to the
+    /// linted directory; one without matches any file or directory name
+    /// (`*.generated.json`, `fixtures`). Invalid patterns are ignored.
+    pub exclude: Vec<String>,
+    /// Report [`AnnotationCoverage`] per file and in total.
+    pub coverage: bool,
+    /// Warn (W014) on a schema with `properties` but no `ucp_request`,
+    /// `ucp_response`, or `ucp_defaults` anywhere, which resolves the same
+    /// for every operation.
+    pub warn_unannotated: bool,
 }
 
 impl LintResult {
@@ -78,13 +288,26 @@ impl LintResult {
 /// If `strict` is true, warnings are treated as errors.
 /// Returns aggregated results for all files.
 pub fn lint(path: &Path, strict: bool) -> LintResult {
-    let files = collect_schema_files(path);
+    lint_with_options(
+        path,
+        &LintOptions {
+            strict,
+            ..LintOptions::default()
+        },
+    )
+}
+
+/// Lint a file or directory with explicit [`LintOptions`].
+pub fn lint_with_options(path: &Path, options: &LintOptions) -> LintResult {
+    let strict = options.strict;
+    let files = collect_schema_files(path, options);
     let mut results = Vec::new();
     let mut total_errors = 0;
     let mut total_warnings = 0;
+    let mut coverage = options.coverage.then(AnnotationCoverage::default);
 
     for file in &files {
-        let file_result = lint_file(file, path);
+        let file_result = lint_file_with_options(file, path, options);
         let file_errors = file_result
             .diagnostics
             .iter()
@@ -98,6 +321,9 @@ pub fn lint(path: &Path, strict: bool) -> LintResult {
 
         total_errors += file_errors;
         total_warnings += file_warnings;
+        if let (Some(total), Some(file_coverage)) = (&mut coverage, &file_result.coverage) {
+            total.add(file_coverage);
+        }
         results.push(file_result);
     }
 
@@ -120,15 +346,24 @@ pub fn lint(path: &Path, strict: bool) -> LintResult {
         errors: total_errors,
         warnings: total_warnings,
         results,
+        coverage,
     }
 }
 
 /// Lint a single schema file.
 pub fn lint_file(file: &Path, base_path: &Path) -> FileResult {
+    lint_file_with_options(file, base_path, &LintOptions::default())
+}
+
+/// Lint a single schema file with explicit [`LintOptions`].
+pub fn lint_file_with_options(file: &Path, base_path: &Path, options: &LintOptions) -> FileResult {
     let mut diagnostics = Vec::new();
 
     // Try to load the file (checks syntax)
-    let schema = match load_schema(file) {
+    let format = options
+        .input_format
+        .unwrap_or_else(|| InputFormat::from_path(file));
+    let schema = match load_schema_as(file, format) {
         Ok(s) => s,
         Err(e) => {
             diagnostics.push(Diagnostic {
@@ -138,29 +373,102 @@ pub fn lint_file(file: &Path, base_path: &Path) -> FileResult {
                 path: "/".to_string(),
                 message: format!("syntax error: {}", e),
             });
-            return FileResult {
-                file: file.strip_prefix(base_path).unwrap_or(file).to_path_buf(),
-                status: FileStatus::Error,
+            return finish_file_result(
+                file,
+                base_path,
+                options,
+                FileStatus::Error,
                 diagnostics,
-            };
+                None,
+            );
         }
     };
 
-    // Check $refs
     let file_dir = file.parent().unwrap_or(Path::new("."));
-    check_refs(&schema, file, file_dir, "", &schema, &mut diagnostics);
+    let (status, coverage) = lint_schema(&schema, file, Some(file_dir), options, &mut diagnostics);
+    finish_file_result(file, base_path, options, status, diagnostics, coverage)
+}
+
+/// Lint an in-memory schema, using `label` as the diagnostics' `file`.
+///
+/// Runs the same checks as [`lint_file`], except that `$ref`s to other files
+/// are skipped rather than checked for existence; internal `#/...` refs are
+/// still resolved.
+pub fn lint_value(schema: &Value, label: &str) -> FileResult {
+    lint_value_with_options(schema, label, &LintOptions::default())
+}
+
+/// Lint an in-memory schema with explicit [`LintOptions`].
+///
+/// `label` is reported verbatim; `options.path_style` does not apply.
+pub fn lint_value_with_options(schema: &Value, label: &str, options: &LintOptions) -> FileResult {
+    let file = Path::new(label);
+    let mut diagnostics = Vec::new();
+    let (status, coverage) = lint_schema(schema, file, None, options, &mut diagnostics);
+    FileResult {
+        file: file.to_path_buf(),
+        status,
+        diagnostics,
+        coverage,
+    }
+}
+
+/// Run every check on a loaded schema and derive the file status.
+///
+/// Without `file_dir`, `$ref`s to other files are not checked.
+fn lint_schema(
+    schema: &Value,
+    file: &Path,
+    file_dir: Option<&Path>,
+    options: &LintOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (FileStatus, Option<AnnotationCoverage>) {
+    // Check $refs
+    let base = id_base(schema);
+    check_refs(
+        schema,
+        file,
+        file_dir,
+        base.as_ref(),
+        "",
+        schema,
+        diagnostics,
+    );
+
+    // Check ucp_* annotations, counting coverage on the way
+    let mut coverage = AnnotationCoverage::default();
+    check_annotations(schema, file, "", options, diagnostics, &mut coverage);
 
-    // Check ucp_* annotations
-    check_annotations(&schema, file, "", &mut diagnostics);
+    // Check for defaults on fields that some operation omits (warning)
+    check_omitted_defaults(schema, file, "", options, diagnostics);
 
     // Check `requires` field (version constraints on extension schemas)
-    check_requires(&schema, file, &mut diagnostics);
+    check_requires(schema, file, diagnostics);
 
     // Check that `examples` entries validate against their own (sub)schema
-    check_examples(&schema, file, "", &mut diagnostics);
+    check_examples(schema, file, "", diagnostics);
+
+    // Check that allOf/anyOf/oneOf hold arrays
+    check_compositions(schema, file, "", diagnostics);
+
+    // Check for schemas that resolve the same for every operation (opt-in);
+    // coverage counts properties annotated through `ucp_defaults` too
+    if options.warn_unannotated && coverage.properties > 0 && coverage.annotated == 0 {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            code: "W014".to_string(),
+            file: file.to_path_buf(),
+            path: "/".to_string(),
+            message: format!(
+                "schema has {} properties but no ucp_request/ucp_response annotations; \
+                 it resolves the same for every operation",
+                coverage.properties
+            ),
+        });
+    }
 
     // Check for missing $id (warning)
-    if schema.get("$id").is_none() {
+    if !options.allow_missing_id && schema.get("$id").is_none() {
         diagnostics.push(Diagnostic {
             severity: Severity::Warning,
             code: "W002".to_string(),
@@ -170,21 +478,44 @@ pub fn lint_file(file: &Path, base_path: &Path) -> FileResult {
         });
     }
 
-    let has_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
-    let has_warnings = diagnostics.iter().any(|d| d.severity == Severity::Warning);
+    (
+        file_status(diagnostics),
+        options.coverage.then_some(coverage),
+    )
+}
 
-    let status = if has_errors {
+/// A file's status from the most severe of its diagnostics.
+fn file_status(diagnostics: &[Diagnostic]) -> FileStatus {
+    let has = |severity| diagnostics.iter().any(|d| d.severity == severity);
+    if has(Severity::Error) {
         FileStatus::Error
-    } else if has_warnings {
+    } else if has(Severity::Warning) {
         FileStatus::Warning
     } else {
         FileStatus::Ok
-    };
+    }
+}
 
+/// Build a [`FileResult`], rendering file paths per `options.path_style`.
+fn finish_file_result(
+    file: &Path,
+    base_path: &Path,
+    options: &LintOptions,
+    status: FileStatus,
+    mut diagnostics: Vec<Diagnostic>,
+    coverage: Option<AnnotationCoverage>,
+) -> FileResult {
+    let style = options.path_style;
+    if style != PathStyle::Relative {
+        for diag in &mut diagnostics {
+            diag.file = style.render(&diag.file, base_path);
+        }
+    }
     FileResult {
-        file: file.strip_prefix(base_path).unwrap_or(file).to_path_buf(),
+        file: style.render(file, base_path),
         status,
         diagnostics,
+        coverage,
     }
 }
 
@@ -235,11 +566,75 @@ fn check_examples(value: &Value, file: &Path, path: &str, diagnostics: &mut Vec<
     }
 }
 
+/// Keywords whose value maps names to schemas, rather than being a schema.
+const NAMED_SCHEMA_KEYWORDS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+];
+
+/// Keywords holding instance data, never schemas.
+const DATA_KEYWORDS: &[&str] = &["enum", "const", "default", "examples"];
+
+/// Report `allOf`/`anyOf`/`oneOf` written as something other than an array
+/// (E010), typically a single object. Validators reject it, and the resolver
+/// can only treat the object as one branch.
+fn check_compositions(value: &Value, file: &Path, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match value {
+        Value::Object(map) => {
+            for key in ["allOf", "anyOf", "oneOf"] {
+                match map.get(key) {
+                    Some(Value::Array(_)) | None => {}
+                    Some(other) => diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        code: "E010".to_string(),
+                        file: file.to_path_buf(),
+                        path: format!("{}/{}", path, key),
+                        message: format!(
+                            "{} must be an array of schemas, found {}",
+                            key,
+                            json_type_name(other)
+                        ),
+                    }),
+                }
+            }
+            for (key, child) in map {
+                let child_path = format!("{}/{}", path, key);
+                if DATA_KEYWORDS.contains(&key.as_str()) {
+                    continue;
+                }
+                match child {
+                    Value::Object(named) if NAMED_SCHEMA_KEYWORDS.contains(&key.as_str()) => {
+                        for (name, schema) in named {
+                            let schema_path = format!("{}/{}", child_path, name);
+                            check_compositions(schema, file, &schema_path, diagnostics);
+                        }
+                    }
+                    _ => check_compositions(child, file, &child_path, diagnostics),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let child_path = format!("{}/{}", path, i);
+                check_compositions(item, file, &child_path, diagnostics);
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Recursively check $ref values in a schema.
+///
+/// `base` is the root schema's `$id`, when it is an absolute URI; relative
+/// refs resolve against it, as the bundler resolves them.
 fn check_refs(
     value: &Value,
     file: &Path,
-    file_dir: &Path,
+    file_dir: Option<&Path>,
+    base: Option<&Url>,
     path: &str,
     root: &Value,
     diagnostics: &mut Vec<Diagnostic>,
@@ -247,18 +642,18 @@ fn check_refs(
     match value {
         Value::Object(map) => {
             if let Some(Value::String(ref_val)) = map.get("$ref") {
-                check_single_ref(ref_val, file, file_dir, path, root, diagnostics);
+                check_single_ref(ref_val, file, file_dir, base, path, root, diagnostics);
             }
 
             for (key, val) in map {
                 let child_path = format!("{}/{}", path, key);
-                check_refs(val, file, file_dir, &child_path, root, diagnostics);
+                check_refs(val, file, file_dir, base, &child_path, root, diagnostics);
             }
         }
         Value::Array(arr) => {
             for (i, item) in arr.iter().enumerate() {
                 let child_path = format!("{}/{}", path, i);
-                check_refs(item, file, file_dir, &child_path, root, diagnostics);
+                check_refs(item, file, file_dir, base, &child_path, root, diagnostics);
             }
         }
         _ => {}
@@ -269,35 +664,43 @@ fn check_refs(
 fn check_single_ref(
     ref_val: &str,
     file: &Path,
-    file_dir: &Path,
+    file_dir: Option<&Path>,
+    base: Option<&Url>,
     path: &str,
     root: &Value,
     diagnostics: &mut Vec<Diagnostic>,
 ) {
+    let rebased = base.and_then(|base| rebase_ref(ref_val, base));
+    let ref_val = rebased.as_deref().unwrap_or(ref_val);
+
     // External URLs can't be validated locally - skip silently
     if ref_val.starts_with("http://") || ref_val.starts_with("https://") {
         return;
     }
 
     if ref_val.starts_with('#') {
-        // Internal reference - check anchor resolves
-        if ref_val != "#" && navigate_fragment(root, ref_val).is_err() {
-            diagnostics.push(Diagnostic {
-                severity: Severity::Error,
-                code: "E003".to_string(),
-                file: file.to_path_buf(),
-                path: path.to_string(),
-                message: format!("anchor not found: {}", ref_val),
-            });
+        // Internal reference - check anchor resolves to a schema
+        if ref_val != "#" {
+            match navigate_fragment(root, ref_val) {
+                Ok(target) => check_ref_target(&target, ref_val, file, path, diagnostics),
+                Err(_) => diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "E003".to_string(),
+                    file: file.to_path_buf(),
+                    path: path.to_string(),
+                    message: format!("anchor not found: {}", ref_val),
+                }),
+            }
         }
         return;
     }
 
-    // File reference (possibly with anchor)
-    let (file_part, fragment) = match ref_val.find('#') {
-        Some(idx) => (&ref_val[..idx], Some(&ref_val[idx..])),
-        None => (ref_val, None),
+    // File reference (possibly with anchor); an in-memory schema has no
+    // directory to check it against
+    let Some(file_dir) = file_dir else {
+        return;
     };
+    let (file_part, fragment) = split_ref(ref_val);
 
     let ref_path = file_dir.join(file_part);
     if !ref_path.exists() {
@@ -310,22 +713,31 @@ fn check_single_ref(
         });
         return;
     }
+    if ref_path.is_dir() {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "E002".to_string(),
+            file: file.to_path_buf(),
+            path: path.to_string(),
+            message: format!("ref target is a directory, expected a file: {}", file_part),
+        });
+        return;
+    }
 
     // If there's a fragment, check it resolves in the referenced file
     if let Some(frag) = fragment {
         if frag != "#" {
             match load_schema(&ref_path) {
-                Ok(ref_schema) => {
-                    if navigate_fragment(&ref_schema, frag).is_err() {
-                        diagnostics.push(Diagnostic {
-                            severity: Severity::Error,
-                            code: "E003".to_string(),
-                            file: file.to_path_buf(),
-                            path: path.to_string(),
-                            message: format!("anchor not found in {}: {}", file_part, frag),
-                        });
-                    }
-                }
+                Ok(ref_schema) => match navigate_fragment(&ref_schema, frag) {
+                    Ok(target) => check_ref_target(&target, ref_val, file, path, diagnostics),
+                    Err(_) => diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        code: "E003".to_string(),
+                        file: file.to_path_buf(),
+                        path: path.to_string(),
+                        message: format!("anchor not found in {}: {}", file_part, frag),
+                    }),
+                },
                 Err(_) => {
                     // If we can't load the ref'd file, that's already an error
                     // from a different check, so don't duplicate
@@ -335,40 +747,169 @@ fn check_single_ref(
     }
 }
 
-/// Recursively check ucp_* annotation values.
-fn check_annotations(value: &Value, file: &Path, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+/// Report a `$ref` whose fragment resolves to a non-schema value (E009).
+fn check_ref_target(
+    target: &Value,
+    ref_val: &str,
+    file: &Path,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if !is_schema_value(target) {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "E009".to_string(),
+            file: file.to_path_buf(),
+            path: path.to_string(),
+            message: format!(
+                "ref target is a {}, expected a schema: {}",
+                json_type_name(target),
+                ref_val
+            ),
+        });
+    }
+}
+
+/// Recursively check ucp_* annotation values, counting `properties` entries
+/// into `coverage`.
+fn check_annotations(
+    value: &Value,
+    file: &Path,
+    path: &str,
+    options: &LintOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+    coverage: &mut AnnotationCoverage,
+) {
     if let Value::Object(map) = value {
         // Check all UCP annotations
         for &annotation_key in UCP_ANNOTATIONS {
             if let Some(annotation) = map.get(annotation_key) {
-                check_annotation_value(annotation, annotation_key, file, path, diagnostics);
+                check_annotation_value(
+                    annotation,
+                    annotation_key,
+                    file,
+                    path,
+                    options,
+                    diagnostics,
+                );
+            }
+        }
+
+        check_annotation_symmetry(map, file, path, diagnostics);
+
+        if let Some(Value::Object(properties)) = map.get("properties") {
+            for property in properties.values() {
+                coverage.record_property(property, map.get(UCP_DEFAULTS));
             }
         }
 
-        // Recurse
+        // Recurse into schema positions only: instance data and the
+        // name-to-schema maps themselves hold no annotations
         for (key, val) in map {
             let child_path = format!("{}/{}", path, key);
-            check_annotations(val, file, &child_path, diagnostics);
+            if DATA_KEYWORDS.contains(&key.as_str()) {
+                continue;
+            }
+            match val {
+                Value::Object(named) if NAMED_SCHEMA_KEYWORDS.contains(&key.as_str()) => {
+                    for (name, schema) in named {
+                        let schema_path = format!("{}/{}", child_path, name);
+                        check_annotations(
+                            schema,
+                            file,
+                            &schema_path,
+                            options,
+                            diagnostics,
+                            coverage,
+                        );
+                    }
+                }
+                _ => check_annotations(val, file, &child_path, options, diagnostics, coverage),
+            }
         }
     } else if let Value::Array(arr) = value {
         for (i, item) in arr.iter().enumerate() {
             let child_path = format!("{}/{}", path, i);
-            check_annotations(item, file, &child_path, diagnostics);
+            check_annotations(item, file, &child_path, options, diagnostics, coverage);
         }
     }
 }
 
+/// Warn (W013) when a field's `ucp_request` and `ucp_response` are both in
+/// per-operation form but list different operations.
+///
+/// A heuristic for copy-paste errors: an operation spelled out for one
+/// direction and silently missing from the other usually means one object was
+/// edited and the other forgotten. `read` is left out of the comparison since
+/// read requests carry no body, and `transition` is not an operation.
+fn check_annotation_symmetry(
+    map: &Map<String, Value>,
+    file: &Path,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let (Some(Value::Object(request)), Some(Value::Object(response))) =
+        (map.get("ucp_request"), map.get("ucp_response"))
+    else {
+        return;
+    };
+    let request_only = operations_missing_from(request, response);
+    let response_only = operations_missing_from(response, request);
+    let mut differences = Vec::new();
+    if !request_only.is_empty() {
+        differences.push(format!("only in ucp_request: {}", request_only.join(", ")));
+    }
+    if !response_only.is_empty() {
+        differences.push(format!(
+            "only in ucp_response: {}",
+            response_only.join(", ")
+        ));
+    }
+    if differences.is_empty() {
+        return;
+    }
+    diagnostics.push(Diagnostic {
+        severity: Severity::Warning,
+        code: "W013".to_string(),
+        file: file.to_path_buf(),
+        path: if path.is_empty() {
+            "/".to_string()
+        } else {
+            path.to_string()
+        },
+        message: format!(
+            "ucp_request and ucp_response list different operations ({}); \
+             check for a copy-paste error",
+            differences.join("; ")
+        ),
+    });
+}
+
+/// Operation keys of `ours` absent from `theirs`, ignoring `read` and
+/// `transition` (see [`check_annotation_symmetry`]).
+fn operations_missing_from<'a>(
+    ours: &'a Map<String, Value>,
+    theirs: &Map<String, Value>,
+) -> Vec<&'a str> {
+    ours.keys()
+        .map(String::as_str)
+        .filter(|op| !matches!(*op, "read" | "transition") && !theirs.contains_key(*op))
+        .collect()
+}
+
 /// Check a single ucp_* annotation value is valid.
 fn check_annotation_value(
     annotation: &Value,
     key: &str,
     file: &Path,
     path: &str,
+    options: &LintOptions,
     diagnostics: &mut Vec<Diagnostic>,
 ) {
     let annotation_path = format!("{}/{}", path, key);
 
     match annotation {
+        Value::Bool(_) if options.boolean_shorthand => {}
         Value::String(s) => {
             if Visibility::parse(s).is_none() {
                 diagnostics.push(Diagnostic {
@@ -377,7 +918,7 @@ fn check_annotation_value(
                     file: file.to_path_buf(),
                     path: annotation_path,
                     message: format!(
-                        "invalid {} value \"{}\": expected omit, required, or optional",
+                        "invalid {} value \"{}\": expected omit, required, optional, deprecate, or inherit",
                         key, s
                     ),
                 });
@@ -411,6 +952,7 @@ fn check_annotation_value(
 
                 // Check value is valid
                 match val {
+                    Value::Bool(_) if options.boolean_shorthand => {}
                     Value::String(s) => {
                         if Visibility::parse(s).is_none() {
                             diagnostics.push(Diagnostic {
@@ -419,7 +961,7 @@ fn check_annotation_value(
                                 file: file.to_path_buf(),
                                 path: op_path,
                                 message: format!(
-                                    "invalid {} value \"{}\": expected omit, required, or optional",
+                                    "invalid {} value \"{}\": expected omit, required, optional, deprecate, or inherit",
                                     key, s
                                 ),
                             });
@@ -429,6 +971,9 @@ fn check_annotation_value(
                         // Per-operation transition: { "update": { "transition": { ... } } }
                         if let Some(t) = obj.get("transition") {
                             check_transition_object(t, key, file, &op_path, diagnostics);
+                        } else if is_constraint_object(obj) {
+                            // Constraint form: { "create": { "min_items": 1 } }
+                            check_constraint_object(obj, key, file, &op_path, diagnostics);
                         } else {
                             diagnostics.push(Diagnostic {
                                 severity: Severity::Error,
@@ -475,6 +1020,143 @@ fn check_annotation_value(
     }
 }
 
+/// Warn (W012) on properties that declare a `default` but are omitted for
+/// some operation.
+///
+/// An omitted field is removed from the resolved schema, so its `default`
+/// never applies to that operation — usually a sign the author expected it to.
+/// Visibility is computed per direction across `VALID_OPERATIONS`, with the
+/// object's `ucp_defaults` applied as `resolve` applies them; malformed
+/// annotations are skipped here since `check_annotations` reports them.
+fn check_omitted_defaults(
+    value: &Value,
+    file: &Path,
+    path: &str,
+    options: &LintOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match value {
+        Value::Object(map) => {
+            if let Some(props) = map.get("properties").and_then(|p| p.as_object()) {
+                for (name, prop) in props {
+                    if prop.get("default").is_none() {
+                        continue;
+                    }
+                    let prop_path = format!("{}/properties/{}", path, name);
+                    let omitted: Vec<String> = [Direction::Request, Direction::Response]
+                        .into_iter()
+                        .flat_map(|dir| VALID_OPERATIONS.iter().map(move |op| (dir, *op)))
+                        .filter(|(dir, op)| {
+                            matches!(
+                                property_visibility(
+                                    map,
+                                    prop,
+                                    *dir,
+                                    op,
+                                    path,
+                                    &prop_path,
+                                    options.boolean_shorthand
+                                ),
+                                Ok(Visibility::Omit)
+                            )
+                        })
+                        .map(|(dir, op)| format!("{} {}", dir.dir_str(), op))
+                        .collect();
+                    if !omitted.is_empty() {
+                        diagnostics.push(Diagnostic {
+                            severity: Severity::Warning,
+                            code: "W012".to_string(),
+                            file: file.to_path_buf(),
+                            path: prop_path,
+                            message: format!(
+                                "field \"{}\" declares a default but is omitted for {}; \
+                                 the default never applies there",
+                                name,
+                                omitted.join(", ")
+                            ),
+                        });
+                    }
+                }
+            }
+            for (key, val) in map {
+                let child_path = format!("{}/{}", path, key);
+                check_omitted_defaults(val, file, &child_path, options, diagnostics);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                let child_path = format!("{}/{}", path, i);
+                check_omitted_defaults(item, file, &child_path, options, diagnostics);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validate a constraint object { "visibility"?, "min_items"?, <overridable keyword>... }.
+fn check_constraint_object(
+    obj: &Map<String, Value>,
+    key: &str,
+    file: &Path,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for keyword in obj.keys() {
+        if keyword != "visibility"
+            && keyword != "min_items"
+            && !UCP_OVERRIDABLE_KEYWORDS.contains(&keyword.as_str())
+        {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "E005".to_string(),
+                file: file.to_path_buf(),
+                path: format!("{}/{}", path, keyword),
+                message: format!(
+                    "invalid {} keyword \"{}\": cannot be overridden per operation",
+                    key, keyword
+                ),
+            });
+        } else if let Some(expected) = override_value_error(keyword, &obj[keyword]) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "E005".to_string(),
+                file: file.to_path_buf(),
+                path: format!("{}/{}", path, keyword),
+                message: format!(
+                    "invalid {} keyword \"{}\": expected {}, got {}",
+                    key, keyword, expected, obj[keyword]
+                ),
+            });
+        }
+    }
+    if let Some(min) = obj.get("min_items").filter(|min| min.as_u64().is_none()) {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "E005".to_string(),
+            file: file.to_path_buf(),
+            path: format!("{}/min_items", path),
+            message: format!(
+                "invalid {} min_items: expected non-negative integer, got {}",
+                key, min
+            ),
+        });
+    }
+    match obj.get("visibility") {
+        None => {}
+        Some(Value::String(s)) if Visibility::parse(s).is_some() => {}
+        Some(other) => diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code: "E004".to_string(),
+            file: file.to_path_buf(),
+            path: format!("{}/visibility", path),
+            message: format!(
+                "invalid {} value {}: expected omit, required, optional, deprecate, or inherit",
+                key, other
+            ),
+        }),
+    }
+}
+
 /// Validate a schema transition object { "from", "to", "description" }.
 fn check_transition_object(
     value: &Value,
@@ -525,7 +1207,7 @@ fn check_transition_object(
             file: file.to_path_buf(),
             path: path.to_string(),
             message: format!(
-                "invalid {} schema transition: \"from\" ({}) and \"to\" ({}) must be distinct visibility values (omit, required, optional)",
+                "invalid {} schema transition: \"from\" ({}) and \"to\" ({}) must be distinct visibility values (omit, required, optional, deprecate)",
                 key, from, to
             ),
         });
@@ -756,30 +1438,79 @@ fn check_requires(schema: &Value, file: &Path, diagnostics: &mut Vec<Diagnostic>
 }
 
 /// Collect all .json files in a path (file or directory).
-fn collect_schema_files(path: &Path) -> Vec<PathBuf> {
+fn collect_schema_files(path: &Path, options: &LintOptions) -> Vec<PathBuf> {
+    let format = options.input_format;
+    let extensions = format.unwrap_or(InputFormat::Json).extensions();
     if path.is_file() {
-        if path.extension().map(|e| e == "json").unwrap_or(false) {
+        // A forced format means the caller named this file deliberately.
+        if format.is_some() || has_extension(path, extensions) {
             return vec![path.to_path_buf()];
         }
         return vec![];
     }
 
+    let exclude: Vec<glob::Pattern> = options
+        .exclude
+        .iter()
+        .filter_map(|p| glob::Pattern::new(p).ok())
+        .collect();
     let mut files = Vec::new();
-    collect_files_recursive(path, &mut files);
+    collect_files_recursive(path, path, extensions, &exclude, &mut files);
     files.sort();
     files
 }
 
-fn collect_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) {
-    let Ok(entries) = std::fs::read_dir(dir) else {
-        return;
+/// Whether `path` (under `root`) matches any exclude pattern.
+fn is_excluded(path: &Path, root: &Path, exclude: &[glob::Pattern]) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..glob::MatchOptions::new()
+    };
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let name = path.file_name().map(Path::new).unwrap_or(relative);
+    exclude.iter().any(|pattern| {
+        if pattern.as_str().contains('/') {
+            pattern.matches_path_with(relative, options)
+        } else {
+            pattern.matches_path_with(name, options)
+        }
+    })
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e))
+}
+
+/// File names of lint config files, which directory walks never lint.
+pub const LINT_CONFIG_FILES: &[&str] = &[".ucp-lint.json", ".ucp-lint.toml"];
+
+fn is_lint_config(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| LINT_CONFIG_FILES.contains(&n))
+}
+
+fn collect_files_recursive(
+    dir: &Path,
+    root: &Path,
+    extensions: &[&str],
+    exclude: &[glob::Pattern],
+    files: &mut Vec<PathBuf>,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
     };
 
     for entry in entries.flatten() {
         let path = entry.path();
+        if is_excluded(&path, root, exclude) {
+            continue;
+        }
         if path.is_dir() {
-            collect_files_recursive(&path, files);
-        } else if path.extension().map(|e| e == "json").unwrap_or(false) {
+            collect_files_recursive(&path, root, extensions, exclude, files);
+        } else if has_extension(&path, extensions) && !is_lint_config(&path) {
             files.push(path);
         }
     }
@@ -896,6 +1627,33 @@ mod tests {
         assert!(result.diagnostics.iter().any(|d| d.code == "E003"));
     }
 
+    #[test]
+    fn lint_ref_to_non_schema_value() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r##"{{
+            "$id": "https://example.com/test.json",
+            "$defs": {{ "name": "string", "open": true }},
+            "properties": {{
+                "name": {{ "$ref": "#/$defs/name" }},
+                "extra": {{ "$ref": "#/$defs/open" }}
+            }}
+        }}"##
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        let e009: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "E009")
+            .collect();
+        assert_eq!(e009.len(), 1, "{:?}", result.diagnostics);
+        assert_eq!(e009[0].path, "/properties/name");
+        assert!(e009[0].message.contains("string"));
+    }
+
     #[test]
     fn lint_broken_file_ref() {
         let mut file = NamedTempFile::new().unwrap();
@@ -915,6 +1673,27 @@ mod tests {
         assert!(result.diagnostics.iter().any(|d| d.code == "E002"));
     }
 
+    #[test]
+    fn lint_ref_to_directory() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("types")).unwrap();
+        let schema = dir.path().join("schema.json");
+        std::fs::write(
+            &schema,
+            r#"{
+            "$id": "https://example.com/test.json",
+            "properties": { "data": { "$ref": "types" } }
+        }"#,
+        )
+        .unwrap();
+
+        let result = lint_file(&schema, dir.path());
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.code == "E002" && d.message.contains("directory")));
+    }
+
     #[test]
     fn lint_invalid_ucp_request_value() {
         let mut file = NamedTempFile::new().unwrap();
@@ -949,7 +1728,8 @@ mod tests {
                     "type": "string",
                     "ucp_request": {{
                         "create": "omit",
-                        "update": "required"
+                        "update": "required",
+                        "complete": "deprecate"
                     }},
                     "ucp_response": "omit"
                 }}
@@ -993,6 +1773,82 @@ mod tests {
         assert!(result.diagnostics.is_empty());
     }
 
+    #[test]
+    fn lint_min_items_object() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "properties": {{
+                "line_items": {{
+                    "type": "array",
+                    "ucp_request": {{
+                        "create": {{ "min_items": 1, "visibility": "required" }},
+                        "update": {{ "min_items": "one" }}
+                    }}
+                }}
+            }}
+        }}"#
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "E005");
+        assert!(result.diagnostics[0].path.ends_with("/update/min_items"));
+    }
+
+    #[test]
+    fn lint_value_checks_in_memory_schema() {
+        let schema = serde_json::json!({
+            "$id": "https://example.com/test.json",
+            "properties": {
+                "id": { "type": "string", "ucp_request": "bogus" },
+                "buyer": { "$ref": "types/buyer.json" },
+                "total": { "$ref": "#/$defs/missing" }
+            }
+        });
+
+        let result = lint_value(&schema, "generated:checkout");
+        assert_eq!(result.file, PathBuf::from("generated:checkout"));
+        assert_eq!(result.status, FileStatus::Error);
+        // The file ref is not checked; the internal ref and annotation are
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code.as_str()).collect();
+        assert_eq!(codes.len(), 2);
+        assert!(codes.contains(&"E003") && codes.contains(&"E004"));
+        assert!(result
+            .diagnostics
+            .iter()
+            .all(|d| d.file == Path::new("generated:checkout")));
+    }
+
+    #[test]
+    fn lint_constraint_object_keywords() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "properties": {{
+                "coupon_code": {{
+                    "type": "string",
+                    "ucp_request": {{
+                        "create": {{ "visibility": "required", "pattern": "^[A-Z0-9]{{8}}$" }},
+                        "update": {{ "maxLength": 16, "type": "integer" }}
+                    }}
+                }}
+            }}
+        }}"#
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "E005");
+        assert!(result.diagnostics[0].path.ends_with("/update/type"));
+    }
+
     #[test]
     fn lint_invalid_schema_transition() {
         let mut file = NamedTempFile::new().unwrap();
@@ -1073,6 +1929,63 @@ mod tests {
         assert!(result.diagnostics.iter().any(|d| d.code == "E005"));
     }
 
+    #[test]
+    fn lint_boolean_annotation_requires_opt_in() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "properties": {{
+                "id": {{
+                    "type": "string",
+                    "ucp_request": false,
+                    "ucp_response": {{ "create": true }}
+                }}
+            }}
+        }}"#
+        )
+        .unwrap();
+        let base = file.path().parent().unwrap();
+
+        let result = lint_file(file.path(), base);
+        let e005: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "E005")
+            .collect();
+        assert_eq!(e005.len(), 2);
+
+        let options = LintOptions {
+            boolean_shorthand: true,
+            ..LintOptions::default()
+        };
+        let result = lint_file_with_options(file.path(), base, &options);
+        assert_eq!(result.status, FileStatus::Ok, "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn path_style_renders_paths() {
+        let cwd = std::env::current_dir().unwrap();
+        let file = Path::new("schemas/./shopping/checkout.json");
+
+        assert_eq!(
+            PathStyle::Relative.render(file, Path::new("schemas")),
+            Path::new("shopping/checkout.json")
+        );
+        assert_eq!(PathStyle::Relative.render(file, Path::new("")), file);
+        assert_eq!(
+            PathStyle::Absolute.render(file, Path::new("schemas")),
+            cwd.join("schemas/shopping/checkout.json")
+        );
+        assert_eq!(
+            PathStyle::CwdRelative.render(&cwd.join(file), Path::new("")),
+            Path::new("schemas/shopping/checkout.json")
+        );
+        assert_eq!("cwd-relative".parse(), Ok(PathStyle::CwdRelative));
+        assert!("home".parse::<PathStyle>().is_err());
+    }
+
     #[test]
     fn lint_missing_id_warning() {
         let mut file = NamedTempFile::new().unwrap();
@@ -1088,6 +2001,268 @@ mod tests {
         let result = lint_file(file.path(), file.path().parent().unwrap());
         assert_eq!(result.status, FileStatus::Warning);
         assert!(result.diagnostics.iter().any(|d| d.code == "W002"));
+
+        let options = LintOptions {
+            allow_missing_id: true,
+            ..LintOptions::default()
+        };
+        let result = lint_file_with_options(file.path(), file.path().parent().unwrap(), &options);
+        assert_eq!(result.status, FileStatus::Ok, "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn lint_warns_on_asymmetric_per_operation_annotations() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "properties": {{
+                "id": {{
+                    "ucp_request": {{ "create": "omit", "update": "required" }},
+                    "ucp_response": {{ "create": "required", "complete": "required", "read": "required" }}
+                }},
+                "total": {{
+                    "ucp_request": {{ "create": "omit" }},
+                    "ucp_response": {{ "create": "required", "read": "required" }}
+                }},
+                "note": {{
+                    "ucp_request": "optional",
+                    "ucp_response": {{ "complete": "required" }}
+                }}
+            }}
+        }}"#
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        let w013: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "W013")
+            .collect();
+        assert_eq!(w013.len(), 1, "{:?}", result.diagnostics);
+        assert_eq!(w013[0].path, "/properties/id");
+        assert!(w013[0]
+            .message
+            .contains("only in ucp_request: update; only in ucp_response: complete"));
+    }
+
+    #[test]
+    fn lint_composition_must_be_array() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "allOf": {{ "type": "object" }},
+            "properties": {{
+                "oneOf": {{ "type": "string" }},
+                "kind": {{ "anyOf": "string", "enum": [{{ "allOf": 1 }}] }}
+            }}
+        }}"#
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        let found: Vec<_> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("E010", "/allOf"), ("E010", "/properties/kind/anyOf")]
+        );
+        assert!(result.diagnostics[0]
+            .message
+            .contains("allOf must be an array of schemas, found object"));
+    }
+
+    #[test]
+    fn lint_reports_annotation_coverage() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.json"),
+            r#"{
+                "$id": "https://example.com/a.json",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": "omit" },
+                    "note": { "type": "string" },
+                    "buyer": {
+                        "type": "object",
+                        "properties": {
+                            "email": {
+                                "ucp_request": { "create": "required", "update": "optional" },
+                                "ucp_response": "required"
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.json"),
+            r#"{"$id": "https://example.com/b.json", "properties": {"x": {"type": "string"}}}"#,
+        )
+        .unwrap();
+
+        let without = lint_with_options(dir.path(), &LintOptions::default());
+        assert!(without.coverage.is_none());
+        assert!(without.results.iter().all(|r| r.coverage.is_none()));
+
+        let options = LintOptions {
+            coverage: true,
+            ..LintOptions::default()
+        };
+        let result = lint_with_options(dir.path(), &options);
+        let a = result.results[0].coverage.unwrap();
+        assert_eq!(
+            a,
+            AnnotationCoverage {
+                properties: 4,
+                annotated: 2,
+                request: 2,
+                response: 1,
+                shorthand: 2,
+                per_operation: 1,
+            }
+        );
+        let total = result.coverage.unwrap();
+        assert_eq!((total.annotated, total.properties), (2, 5));
+        assert_eq!(total.percent(), 40.0);
+    }
+
+    #[test]
+    fn lint_warns_on_unannotated_schema_when_enabled() {
+        let unannotated = serde_json::json!({
+            "$id": "https://example.com/a.json",
+            "properties": { "id": { "type": "string" }, "note": { "type": "string" } }
+        });
+        let options = LintOptions {
+            warn_unannotated: true,
+            ..LintOptions::default()
+        };
+        let w014 = |schema: &Value, options: &LintOptions| {
+            lint_value_with_options(schema, "a.json", options)
+                .diagnostics
+                .into_iter()
+                .filter(|d| d.code == "W014")
+                .count()
+        };
+
+        assert_eq!(w014(&unannotated, &LintOptions::default()), 0);
+        assert_eq!(w014(&unannotated, &options), 1);
+
+        let mut annotated = unannotated.clone();
+        annotated["properties"]["id"]["ucp_request"] = serde_json::json!({ "create": "omit" });
+        assert_eq!(w014(&annotated, &options), 0);
+
+        let mut defaulted = unannotated.clone();
+        defaulted["ucp_defaults"] = serde_json::json!({ "ucp_response": "required" });
+        assert_eq!(w014(&defaulted, &options), 0);
+
+        // A property named `ucp_defaults`, or one inside instance data, is not
+        // an annotation
+        let mut named = unannotated.clone();
+        named["properties"]["ucp_defaults"] = serde_json::json!({ "type": "object" });
+        assert_eq!(w014(&named, &options), 1);
+        let mut data = unannotated.clone();
+        data["examples"] = serde_json::json!([{
+            "ucp_defaults": { "ucp_request": "omit" },
+            "properties": { "id": { "ucp_request": "omit" } }
+        }]);
+        assert_eq!(w014(&data, &options), 1);
+
+        // Nothing to annotate
+        assert_eq!(w014(&serde_json::json!({ "type": "string" }), &options), 0);
+    }
+
+    #[test]
+    fn lint_default_on_omitted_field_warns() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"{{
+            "$id": "https://example.com/test.json",
+            "type": "object",
+            "properties": {{
+                "currency": {{
+                    "type": "string",
+                    "default": "USD",
+                    "ucp_request": {{ "create": "omit", "update": "optional" }}
+                }},
+                "locale": {{ "type": "string", "default": "en" }}
+            }}
+        }}"#
+        )
+        .unwrap();
+
+        let result = lint_file(file.path(), file.path().parent().unwrap());
+        let w012: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "W012")
+            .collect();
+        assert_eq!(w012.len(), 1);
+        assert_eq!(w012[0].path, "/properties/currency");
+        assert!(w012[0].message.contains("request create"));
+        assert!(!w012[0].message.contains("update"));
+    }
+
+    #[test]
+    fn lint_default_on_field_omitted_by_ucp_defaults_warns() {
+        let schema = serde_json::json!({
+            "$id": "https://example.com/test.json",
+            "ucp_defaults": { "ucp_request": { "create": "omit" } },
+            "properties": {
+                "currency": { "type": "string", "default": "USD" },
+                "locale": {
+                    "type": "string",
+                    "default": "en",
+                    "ucp_request": { "create": "optional" }
+                }
+            }
+        });
+        let result = lint_value(&schema, "test.json");
+        let w012: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "W012")
+            .collect();
+        assert_eq!(w012.len(), 1);
+        assert_eq!(w012[0].path, "/properties/currency");
+        assert!(w012[0].message.contains("request create"));
+    }
+
+    #[test]
+    fn coverage_counts_properties_annotated_by_ucp_defaults() {
+        let schema = serde_json::json!({
+            "ucp_defaults": { "ucp_request": "omit" },
+            "properties": {
+                "id": { "type": "string", "ucp_response": { "read": "required" } },
+                "note": { "type": "string" }
+            }
+        });
+        let options = LintOptions {
+            coverage: true,
+            ..LintOptions::default()
+        };
+        let coverage = lint_value_with_options(&schema, "a.json", &options)
+            .coverage
+            .unwrap();
+        assert_eq!(
+            coverage,
+            AnnotationCoverage {
+                properties: 2,
+                annotated: 2,
+                request: 2,
+                response: 1,
+                shorthand: 2,
+                per_operation: 1,
+            }
+        );
     }
 
     #[test]
@@ -1113,6 +2288,42 @@ mod tests {
         assert!(!result.is_ok());
     }
 
+    #[test]
+    fn lint_directory_skips_excluded_paths() {
+        let dir = tempdir().unwrap();
+        let schema = r#"{"$id": "https://example.com/s.json", "type": "object"}"#;
+        std::fs::create_dir_all(dir.path().join("fixtures/deep")).unwrap();
+        std::fs::create_dir_all(dir.path().join("shopping")).unwrap();
+        std::fs::write(dir.path().join("checkout.json"), schema).unwrap();
+        std::fs::write(dir.path().join("order.generated.json"), schema).unwrap();
+        std::fs::write(dir.path().join("fixtures/deep/bad.json"), "{ not json }").unwrap();
+        std::fs::write(dir.path().join("shopping/cart.json"), schema).unwrap();
+        std::fs::write(dir.path().join("shopping/cart.generated.json"), schema).unwrap();
+
+        let options = LintOptions {
+            exclude: vec!["*.generated.json".to_string(), "fixtures".to_string()],
+            ..LintOptions::default()
+        };
+        let result = lint_with_options(dir.path(), &options);
+        let files: Vec<_> = result.results.iter().map(|r| r.file.clone()).collect();
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("checkout.json"),
+                PathBuf::from("shopping/cart.json")
+            ]
+        );
+
+        // A pattern with a separator matches the path under the linted root
+        let options = LintOptions {
+            exclude: vec!["shopping/*".to_string(), "fixtures/**".to_string()],
+            ..LintOptions::default()
+        };
+        let result = lint_with_options(dir.path(), &options);
+        assert_eq!(result.files_checked, 2);
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn lint_strict_mode() {
         let dir = tempdir().unwrap();
@@ -1157,6 +2368,59 @@ mod tests {
         assert_eq!(result.status, FileStatus::Ok);
     }
 
+    #[test]
+    fn lint_ref_with_query_string() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("types.json"),
+            r#"{"$id": "https://example.com/types.json", "$defs": {"thing": {"type": "string"}}}"#,
+        )
+        .unwrap();
+
+        let main_path = dir.path().join("main.json");
+        std::fs::write(
+            &main_path,
+            r#"{"$id": "https://example.com/main.json", "properties": {"x": {"$ref": "types.json?version=2#/$defs/thing"}}}"#,
+        )
+        .unwrap();
+
+        let result = lint_file(&main_path, dir.path());
+        assert_eq!(result.status, FileStatus::Ok);
+    }
+
+    #[test]
+    fn lint_resolves_relative_refs_against_id() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("shopping/types")).unwrap();
+        std::fs::write(
+            dir.path().join("shopping/types/buyer.json"),
+            r#"{"$id": "https://ucp.dev/schemas/shopping/types/buyer.json", "type": "object"}"#,
+        )
+        .unwrap();
+
+        // Saved under a draft name; its refs still resolve against the $id
+        let main_path = dir.path().join("shopping/checkout.draft.json");
+        std::fs::write(
+            &main_path,
+            r#"{
+                "$id": "https://ucp.dev/schemas/shopping/checkout.json",
+                "$defs": { "total": { "type": "integer" } },
+                "properties": {
+                    "self": { "$ref": "checkout.json#/$defs/total" },
+                    "missing": { "$ref": "checkout.json#/$defs/nope" },
+                    "buyer": { "$ref": "/schemas/shopping/types/buyer.json" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let result = lint_file(&main_path, dir.path());
+        assert_eq!(result.diagnostics.len(), 1, "{:?}", result.diagnostics);
+        let diag = &result.diagnostics[0];
+        assert_eq!(diag.code, "E003");
+        assert_eq!(diag.path, "/properties/missing");
+    }
+
     #[test]
     fn lint_valid_requires() {
         let mut file = NamedTempFile::new().unwrap();
@@ -3,11 +3,18 @@
 //! Handles loading schemas from files, strings, and HTTP URLs.
 
 use std::path::Path;
+use std::str::FromStr;
 
 use serde_json::Value;
+use url::Url;
 
 use crate::error::ResolveError;
+use crate::types::json_type_name;
 
+#[cfg(feature = "remote")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "remote")]
+use std::sync::Arc;
 #[cfg(feature = "remote")]
 use std::time::Duration;
 
@@ -15,25 +22,139 @@ use std::time::Duration;
 #[cfg(feature = "remote")]
 const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Default redirect limit for HTTP requests (reqwest's own default).
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// How [`load_schema_auto_with`] loads a file or URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Parse file sources as this format instead of inferring it from the
+    /// extension. URLs are always fetched as JSON.
+    pub format: Option<InputFormat>,
+    /// Redirects followed before a URL fetch fails with `NetworkError`;
+    /// `0` rejects any redirect.
+    pub max_redirects: usize,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+        }
+    }
+}
+
+/// A schema fetched over HTTP and where it was finally served from.
+#[derive(Debug, Clone)]
+pub struct FetchedSchema {
+    pub schema: Value,
+    /// URL of the response after following redirects.
+    pub final_url: String,
+    /// Number of redirects followed to reach `final_url`; `0` when the
+    /// requested URL served the schema, even if `final_url` is spelled
+    /// differently (normalized).
+    pub redirects: usize,
+}
+
+/// Serialization format of a schema or payload document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InputFormat {
+    Json,
+    /// Requires the `yaml` feature (enabled by default).
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl InputFormat {
+    /// Infer the format from a file extension: `.yaml` and `.yml` are YAML
+    /// (with the `yaml` feature), anything else is JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => InputFormat::Yaml,
+            _ => InputFormat::Json,
+        }
+    }
+
+    /// File extensions that select this format.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            InputFormat::Json => &["json"],
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for InputFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(InputFormat::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Ok(InputFormat::Yaml),
+            #[cfg(feature = "yaml")]
+            other => Err(format!(
+                "unknown input format '{}': expected json or yaml",
+                other
+            )),
+            #[cfg(not(feature = "yaml"))]
+            other => Err(format!("unknown input format '{}': expected json", other)),
+        }
+    }
+}
+
 /// Load a schema from a file path.
 ///
+/// The format is inferred from the extension (see [`InputFormat::from_path`]).
+///
 /// # Errors
 ///
 /// Returns `ResolveError::FileNotFound` if the file doesn't exist,
+/// `ResolveError::NotAFile` if the path is a directory,
 /// or `ResolveError::InvalidJson` if the file isn't valid JSON.
 pub fn load_schema(path: &Path) -> Result<Value, ResolveError> {
+    load_schema_as(path, InputFormat::from_path(path))
+}
+
+/// Load a schema from a file path, parsing it as `format` whatever the
+/// file's extension.
+///
+/// # Errors
+///
+/// Same as [`load_schema`], with `ResolveError::InvalidYaml` for YAML input.
+pub fn load_schema_as(path: &Path, format: InputFormat) -> Result<Value, ResolveError> {
+    debug!("loading schema from {}", path.display());
     if !path.exists() {
         return Err(ResolveError::FileNotFound {
             path: path.to_path_buf(),
         });
     }
+    // Only directories are rejected: FIFOs, `/dev/stdin` and process
+    // substitution are readable streams
+    if path.is_dir() {
+        return Err(ResolveError::NotAFile {
+            path: path.to_path_buf(),
+        });
+    }
 
     let content = std::fs::read_to_string(path).map_err(|source| ResolveError::ReadError {
         path: path.to_path_buf(),
         source,
     })?;
 
-    serde_json::from_str(&content).map_err(|source| ResolveError::InvalidJson { source })
+    load_schema_str_as(&content, format)
 }
 
 /// Load a schema from a JSON string.
@@ -45,6 +166,22 @@ pub fn load_schema_str(content: &str) -> Result<Value, ResolveError> {
     serde_json::from_str(content).map_err(|source| ResolveError::InvalidJson { source })
 }
 
+/// Load a schema from a string in an explicit format.
+///
+/// # Errors
+///
+/// Returns `ResolveError::InvalidJson` or `ResolveError::InvalidYaml` if the
+/// string doesn't parse as `format`.
+pub fn load_schema_str_as(content: &str, format: InputFormat) -> Result<Value, ResolveError> {
+    match format {
+        InputFormat::Json => load_schema_str(content),
+        #[cfg(feature = "yaml")]
+        InputFormat::Yaml => {
+            serde_yaml::from_str(content).map_err(|source| ResolveError::InvalidYaml { source })
+        }
+    }
+}
+
 /// Load a schema from an HTTP/HTTPS URL.
 ///
 /// Requires the `remote` feature (enabled by default).
@@ -55,8 +192,30 @@ pub fn load_schema_str(content: &str) -> Result<Value, ResolveError> {
 /// or `ResolveError::InvalidJson` if the response isn't valid JSON.
 #[cfg(feature = "remote")]
 pub fn load_schema_url(url: &str) -> Result<Value, ResolveError> {
+    load_schema_url_with(url, &LoadOptions::default()).map(|fetched| fetched.schema)
+}
+
+/// [`load_schema_url`] with an explicit redirect limit, reporting the URL the
+/// schema was finally served from.
+///
+/// # Errors
+///
+/// Same as [`load_schema_url`]; exceeding `options.max_redirects` (or a
+/// redirect loop, which exceeds any limit) is a `NetworkError` for the
+/// requested URL.
+#[cfg(feature = "remote")]
+pub fn load_schema_url_with(
+    url: &str,
+    options: &LoadOptions,
+) -> Result<FetchedSchema, ResolveError> {
+    debug!("fetching schema from {}", url);
+    let followed = Arc::new(AtomicUsize::new(0));
     let client = reqwest::blocking::Client::builder()
         .timeout(HTTP_TIMEOUT)
+        .redirect(redirect_policy(
+            options.max_redirects,
+            Arc::clone(&followed),
+        ))
         .build()
         .map_err(|source| ResolveError::NetworkError {
             url: url.to_string(),
@@ -79,12 +238,96 @@ pub fn load_schema_url(url: &str) -> Result<Value, ResolveError> {
             source,
         })?;
 
-    response
+    let final_url = response.url().to_string();
+    let redirects = followed.load(Ordering::Relaxed);
+    if redirects > 0 {
+        debug!("{} redirected to {}", url, final_url);
+    }
+    let schema = response
         .json()
         .map_err(|source| ResolveError::NetworkError {
             url: url.to_string(),
             source,
-        })
+        })?;
+    Ok(FetchedSchema {
+        redirects,
+        schema,
+        final_url,
+    })
+}
+
+/// Redirect policy failing past `max_redirects`, storing the number of
+/// redirects followed in `followed`.
+#[cfg(feature = "remote")]
+fn redirect_policy(max_redirects: usize, followed: Arc<AtomicUsize>) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        // `previous` holds every URL requested so far, the original included
+        let redirects = attempt.previous().len();
+        if redirects > max_redirects {
+            attempt.error(format!("too many redirects (limit {})", max_redirects))
+        } else {
+            followed.store(redirects, Ordering::Relaxed);
+            attempt.follow()
+        }
+    })
+}
+
+/// Load a schema from an HTTP/HTTPS URL without blocking.
+///
+/// Async counterpart of [`load_schema_url`], using `reqwest`'s async client so
+/// it can run directly on a tokio runtime. Requires the `async` feature.
+///
+/// # Errors
+///
+/// Same as [`load_schema_url`].
+#[cfg(feature = "async")]
+pub async fn load_schema_url_async(url: &str) -> Result<Value, ResolveError> {
+    load_schema_url_with_async(url, &LoadOptions::default())
+        .await
+        .map(|fetched| fetched.schema)
+}
+
+/// Async counterpart of [`load_schema_url_with`]. Requires the `async` feature.
+///
+/// # Errors
+///
+/// Same as [`load_schema_url_with`].
+#[cfg(feature = "async")]
+pub async fn load_schema_url_with_async(
+    url: &str,
+    options: &LoadOptions,
+) -> Result<FetchedSchema, ResolveError> {
+    debug!("fetching schema from {}", url);
+    let network_err = |source| ResolveError::NetworkError {
+        url: url.to_string(),
+        source,
+    };
+    let followed = Arc::new(AtomicUsize::new(0));
+    let client = reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .redirect(redirect_policy(
+            options.max_redirects,
+            Arc::clone(&followed),
+        ))
+        .build()
+        .map_err(network_err)?;
+
+    let response = client.get(url).send().await.map_err(network_err)?;
+
+    // Check for HTTP errors before parsing
+    let response = response.error_for_status().map_err(network_err)?;
+
+    let final_url = response.url().to_string();
+    let redirects = followed.load(Ordering::Relaxed);
+    if redirects > 0 {
+        debug!("{} redirected to {}", url, final_url);
+    }
+    let schema = response.json().await.map_err(network_err)?;
+    Ok(FetchedSchema {
+        redirects,
+        schema,
+        final_url,
+    })
 }
 
 /// Check if a string looks like a URL (starts with http:// or https://).
@@ -92,6 +335,12 @@ pub fn is_url(s: &str) -> bool {
     s.starts_with("http://") || s.starts_with("https://")
 }
 
+/// Escape a key for use as a JSON Pointer token (RFC 6901): `~` → `~0`,
+/// `/` → `~1`.
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
 /// Navigate a JSON Pointer fragment (e.g., "#/$defs/foo" or "#/properties/bar").
 ///
 /// Returns the value at the given JSON Pointer path within the schema.
@@ -107,13 +356,253 @@ pub fn navigate_fragment(schema: &Value, fragment: &str) -> Result<Value, Resolv
     for part in path.split('/') {
         // Unescape JSON Pointer encoding (~1 = /, ~0 = ~)
         let key = part.replace("~1", "/").replace("~0", "~");
-        current = current.get(&key).ok_or_else(|| ResolveError::BundleError {
+        let next = match current {
+            // Array elements are addressed by index (`/oneOf/2`)
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => current.get(&key),
+        };
+        current = next.ok_or_else(|| ResolveError::BundleError {
             message: format!("fragment not found: {}", fragment),
         })?;
     }
     Ok(current.clone())
 }
 
+/// Navigate to a `$ref` target, requiring it to be a schema.
+///
+/// A schema is an object or a boolean. A pointer that lands on a string,
+/// number, array, or null is an authoring mistake (`#/$defs/foo` where `foo`
+/// is `"string"`) that would otherwise inline as nothing.
+pub(crate) fn navigate_ref_target(schema: &Value, fragment: &str) -> Result<Value, ResolveError> {
+    let target = navigate_fragment(schema, fragment)?;
+    if is_schema_value(&target) {
+        Ok(target)
+    } else {
+        Err(ResolveError::BundleError {
+            message: format!(
+                "$ref target {} is a {}, expected a schema (object or boolean)",
+                fragment,
+                json_type_name(&target)
+            ),
+        })
+    }
+}
+
+/// Whether `value` can stand as a JSON Schema (object or boolean schema).
+pub(crate) fn is_schema_value(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Bool(_))
+}
+
+/// Apply an RFC 6902 JSON Patch document to a schema in place.
+///
+/// The patch is all-or-nothing: if any operation fails the schema is left
+/// unchanged.
+///
+/// # Errors
+///
+/// Returns `InvalidPatch` if `patch` is not a JSON Patch array or an
+/// operation cannot be applied.
+#[cfg(feature = "patch")]
+pub fn apply_patch(schema: &mut Value, patch: &Value) -> Result<(), ResolveError> {
+    let patch: json_patch::Patch =
+        serde_json::from_value(patch.clone()).map_err(|e| ResolveError::InvalidPatch {
+            message: e.to_string(),
+        })?;
+    json_patch::patch(schema, &patch).map_err(|e| ResolveError::InvalidPatch {
+        message: e.to_string(),
+    })
+}
+
+/// Split an external `$ref` into its file part and optional `#...` fragment.
+///
+/// A `?...` query on the file part (`common.json?version=2#/$defs/Address`,
+/// emitted by some toolchains) is ignored: it does not name a different file.
+pub(crate) fn split_ref(ref_val: &str) -> (&str, Option<&str>) {
+    let (target, fragment) = match ref_val.find('#') {
+        Some(idx) => (&ref_val[..idx], Some(&ref_val[idx..])),
+        None => (ref_val, None),
+    };
+    let file_part = target.split_once('?').map_or(target, |(file, _)| file);
+    (file_part, fragment)
+}
+
+/// The schema's `$id` as a base URI, when it is absolute.
+pub(crate) fn id_base(schema: &Value) -> Option<Url> {
+    schema
+        .get("$id")
+        .and_then(Value::as_str)
+        .and_then(|id| Url::parse(id).ok())
+}
+
+/// Re-express a relative `$ref` against the schema's `$id` base, as
+/// validators resolve it.
+///
+/// A ref that resolves back to the schema's own `$id` (e.g. `checkout.json#/$defs/x`
+/// inside `.../checkout.json`) becomes an internal `#...` ref, whatever the file
+/// is called on disk. Any other relative ref becomes a path from the `$id`'s
+/// directory, which is taken to be the file's directory, so root-relative refs
+/// (`/schemas/types/buyer.json`) map onto the local tree. Absolute URLs and refs
+/// the base cannot resolve are left as written (`None`).
+pub(crate) fn rebase_ref(ref_val: &str, base: &Url) -> Option<String> {
+    if ref_val.starts_with('#') || is_url(ref_val) {
+        return None;
+    }
+    let (_, fragment) = split_ref(ref_val);
+    let mut target = base.join(ref_val).ok()?;
+    target.set_fragment(None);
+    let mut own = base.clone();
+    own.set_fragment(None);
+
+    if target == own {
+        return Some(fragment.unwrap_or("#").to_string());
+    }
+    let relative = own.make_relative(&target)?;
+    Some(format!("{}{}", relative, fragment.unwrap_or("")))
+}
+
+/// Inline every internal `$ref` (`#/...`) so the schema no longer needs pointer
+/// resolution.
+///
+/// Each local ref is replaced by the value it points at; keywords written next
+/// to the `$ref` win over the inlined ones. Self-root refs (`$ref: "#"`) and refs
+/// back into a definition that is already being expanded are true recursion and
+/// are left as `$ref`, so `$defs` is kept in the output for them (and for
+/// container shapes, which live there). External refs are untouched; run
+/// [`bundle_refs`] first to pull those in.
+///
+/// # Errors
+///
+/// Returns `BundleError` if a local ref points at nothing.
+pub fn inline_internal_refs(schema: &Value) -> Result<Value, ResolveError> {
+    let mut inlined = schema.clone();
+    inline_local_refs(&mut inlined, schema)?;
+    Ok(inlined)
+}
+
+/// Check that no external `$ref` survives in a bundled schema.
+///
+/// Internal refs (`#`, `#/...`) are expected to remain for recursion and
+/// `$defs`; any other `$ref` means a reference was missed or failed to
+/// bundle, and output assumed to be self-contained is not.
+///
+/// # Errors
+///
+/// Returns `UnbundledRef` for the first external `$ref` found, with its JSON
+/// Pointer.
+pub fn assert_bundled(schema: &Value) -> Result<(), ResolveError> {
+    match collect_refs(schema)
+        .into_iter()
+        .find(|(_, reference)| !is_internal_ref(reference))
+    {
+        Some((path, reference)) => Err(ResolveError::UnbundledRef { path, reference }),
+        None => Ok(()),
+    }
+}
+
+/// Every `$ref` in a schema, as `(pointer, reference)` pairs in document order.
+///
+/// The pointer is the JSON Pointer of the `$ref` member itself
+/// (`/properties/buyer/$ref`). Nothing is loaded or resolved; use
+/// [`is_internal_ref`] to tell `#...` refs into the same document from
+/// external ones.
+pub fn collect_refs(schema: &Value) -> Vec<(String, String)> {
+    let mut refs = Vec::new();
+    collect_refs_inner(schema, "", &mut refs);
+    refs
+}
+
+/// Whether a `$ref` points into the document it appears in (`#`, `#/...`,
+/// `#anchor`) rather than at another resource.
+pub fn is_internal_ref(reference: &str) -> bool {
+    reference.starts_with('#')
+}
+
+fn collect_refs_inner(value: &Value, path: &str, refs: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+                refs.push((format!("{}/$ref", path), reference.to_string()));
+            }
+            for (key, child) in obj {
+                let escaped = escape_pointer_token(key);
+                collect_refs_inner(child, &format!("{}/{}", path, escaped), refs);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                collect_refs_inner(item, &format!("{}/{}", path, i), refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Inline internal refs in `value`, resolving pointers against `root`.
+pub(crate) fn inline_local_refs(value: &mut Value, root: &Value) -> Result<(), ResolveError> {
+    inline_local_refs_inner(value, root, &mut Vec::new())
+}
+
+fn inline_local_refs_inner(
+    value: &mut Value,
+    root: &Value,
+    expanding: &mut Vec<String>,
+) -> Result<(), ResolveError> {
+    match value {
+        Value::Object(obj) => {
+            let local_ref = obj
+                .get("$ref")
+                .and_then(|v| v.as_str())
+                .filter(|r| r.starts_with("#/"))
+                .map(str::to_string);
+            if let Some(pointer) = local_ref {
+                // Guard against circular refs: leave the recursion point as-is
+                if expanding.contains(&pointer) {
+                    return Ok(());
+                }
+
+                let mut target = navigate_ref_target(root, &pointer)?;
+                expanding.push(pointer);
+                let result = inline_local_refs_inner(&mut target, root, expanding);
+                expanding.pop();
+                result?;
+
+                // Siblings are expanded before merging; the target already is,
+                // and walking it again would re-enter its recursion points
+                obj.remove("$ref");
+                for v in obj.values_mut() {
+                    inline_local_refs_inner(v, root, expanding)?;
+                }
+                match target {
+                    Value::Object(target_obj) => {
+                        for (k, v) in target_obj {
+                            obj.entry(k).or_insert(v);
+                        }
+                    }
+                    // Boolean schemas: `true` adds nothing next to siblings
+                    other => {
+                        if obj.is_empty() || other == Value::Bool(false) {
+                            *value = other;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+
+            for v in obj.values_mut() {
+                inline_local_refs_inner(v, root, expanding)?;
+            }
+            Ok(())
+        }
+        Value::Array(arr) => {
+            for item in arr {
+                inline_local_refs_inner(item, root, expanding)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Recursively resolve and inline external $ref pointers.
 ///
 /// Walks the schema tree, finds `$ref` values pointing to external files,
@@ -122,6 +611,19 @@ pub fn navigate_fragment(schema: &Value, fragment: &str) -> Result<Value, Resolv
 /// Internal refs in loaded external files are resolved against that file.
 /// Self-root refs (`$ref: "#"`) are left as-is (recursive type definitions).
 ///
+/// When a file declares an absolute `$id`, its relative refs resolve against
+/// that URI, with the `$id`'s directory standing for the file's directory: a
+/// ref back to the file's own `$id` is internal whatever the file is named,
+/// and root-relative refs (`/schemas/...`) map onto the local tree.
+///
+/// Keywords beside a `$ref` are kept and win over the same keywords in the
+/// referenced schema, as in 2019-09 and later. Draft 7, where siblings of
+/// `$ref` are ignored, is not modelled: such siblings are kept as well.
+///
+/// A target that fails to load is reported as
+/// [`ResolveError::RefTargetError`], naming the file and JSON Pointer of the
+/// `$ref` alongside the underlying failure.
+///
 /// # Arguments
 /// * `schema` - The schema to process (modified in place)
 /// * `base_dir` - Base directory for resolving relative file paths
@@ -134,7 +636,7 @@ pub fn bundle_refs(schema: &mut Value, base_dir: &Path) -> Result<(), ResolveErr
         Some(&root_snapshot),
         None,
         None,
-        &mut std::collections::HashSet::new(),
+        &mut BundleState::default(),
     )
 }
 
@@ -163,38 +665,153 @@ pub fn bundle_refs_with_url_mapping(
         Some(&root_snapshot),
         Some(local_base),
         Some(remote_base),
-        &mut std::collections::HashSet::new(),
+        &mut BundleState::default(),
+    )
+}
+
+/// [`bundle_refs`] / [`bundle_refs_with_url_mapping`] that reports each
+/// external `$ref` as it is inlined.
+///
+/// `on_ref` receives the ref as written and where it resolved: an absolute
+/// file path, or the URL when the file is fetched over HTTP, followed by the
+/// fragment. It is called before the target is loaded, so the last call
+/// before an error names the ref that failed. Internal `#...` refs resolve
+/// within the document being bundled and are not reported.
+pub fn bundle_refs_traced(
+    schema: &mut Value,
+    base_dir: &Path,
+    url_mapping: Option<(&Path, &str)>,
+    on_ref: &mut dyn FnMut(&str, &str),
+) -> Result<(), ResolveError> {
+    let root_snapshot = schema.clone();
+    bundle_refs_inner(
+        schema,
+        base_dir,
+        Some(&root_snapshot),
+        url_mapping.map(|(local, _)| local),
+        url_mapping.map(|(_, remote)| remote),
+        &mut BundleState {
+            on_ref: Some(on_ref),
+            ..BundleState::default()
+        },
     )
 }
 
+/// Callback receiving `(ref as written, resolved file or URL)`.
+type RefObserver<'a> = &'a mut dyn FnMut(&str, &str);
+
+/// Mutable state threaded through one bundling pass.
+#[derive(Default)]
+struct BundleState<'a> {
+    /// Refs currently being expanded, for cycle detection.
+    visited: std::collections::HashSet<String>,
+    on_ref: Option<RefObserver<'a>>,
+    /// File (or URL) being walked; `None` for the schema passed in.
+    file: Option<String>,
+    /// JSON Pointer (without the leading `#`) of the value being walked
+    /// within `file`.
+    pointer: String,
+}
+
+impl BundleState<'_> {
+    /// Wrap a failure to load `reference` with where the `$ref` was written.
+    fn ref_error(&self, reference: &str, source: ResolveError) -> ResolveError {
+        ResolveError::RefTargetError {
+            reference: reference.to_string(),
+            referrer: self
+                .file
+                .clone()
+                .unwrap_or_else(|| "the root schema".to_string()),
+            pointer: format!("#{}", self.pointer),
+            source: Box::new(source),
+        }
+    }
+
+    /// Walk `value` with `segment` appended to the current pointer.
+    fn descend(
+        &mut self,
+        segment: &str,
+        walk: impl FnOnce(&mut Self) -> Result<(), ResolveError>,
+    ) -> Result<(), ResolveError> {
+        let len = self.pointer.len();
+        self.pointer.push('/');
+        self.pointer.push_str(&escape_pointer_token(segment));
+        let result = walk(self);
+        self.pointer.truncate(len);
+        result
+    }
+
+    /// Walk a ref target, which sits at `fragment` in `file` (`None` keeps
+    /// the current file).
+    fn enter(
+        &mut self,
+        file: Option<String>,
+        fragment: Option<&str>,
+        walk: impl FnOnce(&mut Self) -> Result<(), ResolveError>,
+    ) -> Result<(), ResolveError> {
+        let pointer = fragment.map_or("", |f| f.strip_prefix('#').unwrap_or(f));
+        let saved_pointer = std::mem::replace(&mut self.pointer, pointer.to_string());
+        let saved_file = file.map(|file| self.file.replace(file));
+        let result = walk(self);
+        self.pointer = saved_pointer;
+        if let Some(file) = saved_file {
+            self.file = file;
+        }
+        result
+    }
+}
+
+/// Where an external ref resolved, as reported by [`bundle_refs_traced`].
+fn traced_target(ref_path: &Path, file_part: &str, fragment: Option<&str>) -> String {
+    let target = if !ref_path.exists() && is_url(file_part) {
+        file_part.to_string()
+    } else {
+        let absolute = match ref_path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => std::env::current_dir()
+                .map(|cwd| cwd.join(ref_path))
+                .unwrap_or_else(|_| ref_path.to_path_buf()),
+        };
+        absolute.display().to_string()
+    };
+    format!("{}{}", target, fragment.unwrap_or(""))
+}
+
 fn bundle_refs_inner(
     schema: &mut Value,
     base_dir: &Path,
     file_root: Option<&Value>, // Root of external file for resolving internal refs
     url_local_base: Option<&Path>,
     url_remote_base: Option<&str>,
-    visited: &mut std::collections::HashSet<String>,
+    state: &mut BundleState,
 ) -> Result<(), ResolveError> {
     match schema {
         Value::Object(obj) => {
             // Check if this object has a $ref
-            if let Some(ref_val) = obj.get("$ref").and_then(|v| v.as_str()) {
+            if let Some(raw_ref) = obj.get("$ref").and_then(|v| v.as_str()) {
+                // Relative refs resolve against the file's `$id`, if it has one
+                let rebased = file_root
+                    .and_then(id_base)
+                    .and_then(|base| rebase_ref(raw_ref, &base));
+                let ref_val = rebased.as_deref().unwrap_or(raw_ref);
                 if ref_val.starts_with('#') {
                     // Internal ref - only resolve if we have a file_root context
                     // Skip self-root refs ($ref: "#") - these are recursive type defs
                     if ref_val == "#" {
                         // Leave as-is - can't inline recursive self-reference
                     } else if let Some(root) = file_root {
-                        let mut target = navigate_fragment(root, ref_val)?;
+                        let mut target = navigate_ref_target(root, ref_val)?;
                         // Recursively process (may have nested refs)
-                        bundle_refs_inner(
-                            &mut target,
-                            base_dir,
-                            file_root,
-                            url_local_base,
-                            url_remote_base,
-                            visited,
-                        )?;
+                        state.enter(None, Some(ref_val), |state| {
+                            bundle_refs_inner(
+                                &mut target,
+                                base_dir,
+                                file_root,
+                                url_local_base,
+                                url_remote_base,
+                                state,
+                            )
+                        })?;
                         // Inline the resolved definition
                         obj.remove("$ref");
                         if let Value::Object(ref_obj) = target {
@@ -207,61 +824,72 @@ fn bundle_refs_inner(
                     // No file_root context — leave as-is
                 } else {
                     // External ref - may be relative path or absolute URL
-                    let (file_part, fragment) = match ref_val.find('#') {
-                        Some(idx) => (&ref_val[..idx], Some(&ref_val[idx..])),
-                        None => (ref_val, None),
-                    };
+                    let (file_part, fragment) = split_ref(ref_val);
 
                     // Resolve ref to local path, handling URL mapping if configured
                     let ref_path =
                         resolve_ref_to_path(file_part, base_dir, url_local_base, url_remote_base);
+                    debug!("bundling $ref {} -> {}", ref_val, ref_path.display());
+                    if let Some(on_ref) = state.on_ref.as_mut() {
+                        on_ref(raw_ref, &traced_target(&ref_path, file_part, fragment));
+                    }
 
                     // If local resolution fails and the ref is a URL, try HTTP fetch
                     #[cfg(feature = "remote")]
-                    let (loaded, ref_dir_owned) = if !ref_path.exists() && is_url(file_part) {
-                        let fetched = load_schema_url(file_part)?;
-                        // Remote schemas have no local directory; use base_dir for
-                        // any relative refs within the fetched schema
-                        (fetched, base_dir.to_path_buf())
-                    } else {
-                        let schema = load_schema(&ref_path)?;
-                        let dir = ref_path.parent().unwrap_or(base_dir).to_path_buf();
-                        (schema, dir)
-                    };
+                    let (loaded, ref_dir_owned, ref_file) =
+                        if !ref_path.exists() && is_url(file_part) {
+                            warn!(
+                                "no local file for $ref {}, fetching over HTTP instead",
+                                file_part
+                            );
+                            let fetched = load_schema_url(file_part)
+                                .map_err(|e| state.ref_error(raw_ref, e))?;
+                            // Remote schemas have no local directory; use base_dir for
+                            // any relative refs within the fetched schema
+                            (fetched, base_dir.to_path_buf(), file_part.to_string())
+                        } else {
+                            let schema =
+                                load_schema(&ref_path).map_err(|e| state.ref_error(raw_ref, e))?;
+                            let dir = ref_path.parent().unwrap_or(base_dir).to_path_buf();
+                            (schema, dir, ref_path.display().to_string())
+                        };
 
                     #[cfg(not(feature = "remote"))]
-                    let (loaded, ref_dir_owned) = {
-                        let schema = load_schema(&ref_path)?;
+                    let (loaded, ref_dir_owned, ref_file) = {
+                        let schema =
+                            load_schema(&ref_path).map_err(|e| state.ref_error(raw_ref, e))?;
                         let dir = ref_path.parent().unwrap_or(base_dir).to_path_buf();
-                        (schema, dir)
+                        (schema, dir, ref_path.display().to_string())
                     };
 
                     let canonical = ref_path.canonicalize().unwrap_or(ref_path.clone());
                     let visit_key = format!("{}|{}", canonical.display(), fragment.unwrap_or(""));
 
-                    if visited.contains(&visit_key) {
+                    if state.visited.contains(&visit_key) {
                         return Err(ResolveError::BundleError {
                             message: format!("circular reference detected: {}", ref_val),
                         });
                     }
 
                     let mut target = if let Some(frag) = fragment {
-                        navigate_fragment(&loaded, frag)?
+                        navigate_ref_target(&loaded, frag)?
                     } else {
                         loaded.clone()
                     };
 
-                    visited.insert(visit_key.clone());
+                    state.visited.insert(visit_key.clone());
                     // Pass loaded file as file_root so internal refs resolve against it
-                    bundle_refs_inner(
-                        &mut target,
-                        &ref_dir_owned,
-                        Some(&loaded),
-                        url_local_base,
-                        url_remote_base,
-                        visited,
-                    )?;
-                    visited.remove(&visit_key);
+                    state.enter(Some(ref_file), fragment, |state| {
+                        bundle_refs_inner(
+                            &mut target,
+                            &ref_dir_owned,
+                            Some(&loaded),
+                            url_local_base,
+                            url_remote_base,
+                            state,
+                        )
+                    })?;
+                    state.visited.remove(&visit_key);
 
                     obj.remove("$ref");
                     if let Value::Object(ref_obj) = target {
@@ -274,27 +902,31 @@ fn bundle_refs_inner(
             }
 
             // Recurse into all values
-            for value in obj.values_mut() {
-                bundle_refs_inner(
-                    value,
-                    base_dir,
-                    file_root,
-                    url_local_base,
-                    url_remote_base,
-                    visited,
-                )?;
+            for (key, value) in obj.iter_mut() {
+                state.descend(key, |state| {
+                    bundle_refs_inner(
+                        value,
+                        base_dir,
+                        file_root,
+                        url_local_base,
+                        url_remote_base,
+                        state,
+                    )
+                })?;
             }
         }
         Value::Array(arr) => {
-            for item in arr {
-                bundle_refs_inner(
-                    item,
-                    base_dir,
-                    file_root,
-                    url_local_base,
-                    url_remote_base,
-                    visited,
-                )?;
+            for (i, item) in arr.iter_mut().enumerate() {
+                state.descend(&i.to_string(), |state| {
+                    bundle_refs_inner(
+                        item,
+                        base_dir,
+                        file_root,
+                        url_local_base,
+                        url_remote_base,
+                        state,
+                    )
+                })?;
             }
         }
         _ => {}
@@ -319,6 +951,14 @@ fn resolve_ref_to_path(
             // URL matches remote base - map to local path
             return local_base.join(remainder.trim_start_matches('/'));
         }
+        if is_url(ref_val) {
+            warn!(
+                "$ref {} does not match remote base {}; not mapped to {}",
+                ref_val,
+                remote_base,
+                local_base.display()
+            );
+        }
     }
 
     // Default: treat as relative path from base_dir
@@ -343,15 +983,80 @@ pub fn bundle_refs_remote(schema: &mut Value, base_url: &str) -> Result<(), Reso
         base_url,
         Some(&root_snapshot),
         &mut std::collections::HashSet::new(),
+        &load_schema_url,
+    )
+}
+
+/// Async counterpart of [`bundle_refs_remote`]. Requires the `async` feature.
+///
+/// Every document reachable through external `$ref`s is fetched up front with
+/// [`load_schema_url_async`]; the refs are then inlined from that set exactly
+/// as the blocking variant would inline them.
+#[cfg(feature = "async")]
+pub async fn bundle_refs_remote_async(
+    schema: &mut Value,
+    base_url: &str,
+) -> Result<(), ResolveError> {
+    let mut fetched: std::collections::HashMap<String, Value> = std::collections::HashMap::new();
+    let mut pending = Vec::new();
+    collect_remote_refs(schema, base_url, &mut pending);
+    while let Some(url) = pending.pop() {
+        if fetched.contains_key(&url) {
+            continue;
+        }
+        let loaded = load_schema_url_async(&url).await?;
+        collect_remote_refs(&loaded, &url, &mut pending);
+        fetched.insert(url, loaded);
+    }
+
+    let root_snapshot = schema.clone();
+    bundle_refs_remote_inner(
+        schema,
+        base_url,
+        Some(&root_snapshot),
+        &mut std::collections::HashSet::new(),
+        &|url: &str| {
+            fetched
+                .get(url)
+                .cloned()
+                .ok_or_else(|| ResolveError::BundleError {
+                    message: format!("{} was not prefetched", url),
+                })
+        },
     )
 }
 
+/// Collect the absolute URLs of all external `$ref`s in a document.
+#[cfg(feature = "async")]
+fn collect_remote_refs(schema: &Value, base_url: &str, out: &mut Vec<String>) {
+    match schema {
+        Value::Object(obj) => {
+            if let Some(ref_val) = obj.get("$ref").and_then(|v| v.as_str()) {
+                if !ref_val.starts_with('#') {
+                    let file_part = ref_val.split('#').next().unwrap_or(ref_val);
+                    out.push(resolve_url(file_part, base_url));
+                }
+            }
+            for value in obj.values() {
+                collect_remote_refs(value, base_url, out);
+            }
+        }
+        Value::Array(arr) => {
+            for item in arr {
+                collect_remote_refs(item, base_url, out);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(feature = "remote")]
 fn bundle_refs_remote_inner(
     schema: &mut Value,
     base_url: &str,
     file_root: Option<&Value>,
     visited: &mut std::collections::HashSet<String>,
+    fetch: &dyn Fn(&str) -> Result<Value, ResolveError>,
 ) -> Result<(), ResolveError> {
     match schema {
         Value::Object(obj) => {
@@ -361,8 +1066,8 @@ fn bundle_refs_remote_inner(
                     if ref_val == "#" {
                         // Self-reference, leave as-is
                     } else if let Some(root) = file_root {
-                        let mut target = navigate_fragment(root, ref_val)?;
-                        bundle_refs_remote_inner(&mut target, base_url, file_root, visited)?;
+                        let mut target = navigate_ref_target(root, ref_val)?;
+                        bundle_refs_remote_inner(&mut target, base_url, file_root, visited, fetch)?;
                         obj.remove("$ref");
                         if let Value::Object(ref_obj) = target {
                             for (k, v) in ref_obj {
@@ -381,6 +1086,7 @@ fn bundle_refs_remote_inner(
 
                     // Resolve to absolute URL
                     let resolved_url = resolve_url(file_part, base_url);
+                    debug!("bundling $ref {} -> {}", ref_val, resolved_url);
                     let visit_key = format!("{}|{}", resolved_url, fragment.unwrap_or(""));
 
                     if visited.contains(&visit_key) {
@@ -390,16 +1096,22 @@ fn bundle_refs_remote_inner(
                     }
 
                     // Fetch the referenced schema
-                    let loaded = load_schema_url(&resolved_url)?;
+                    let loaded = fetch(&resolved_url)?;
                     let mut target = if let Some(frag) = fragment {
-                        navigate_fragment(&loaded, frag)?
+                        navigate_ref_target(&loaded, frag)?
                     } else {
                         loaded.clone()
                     };
 
                     visited.insert(visit_key.clone());
                     // Recursively bundle with new base URL
-                    bundle_refs_remote_inner(&mut target, &resolved_url, Some(&loaded), visited)?;
+                    bundle_refs_remote_inner(
+                        &mut target,
+                        &resolved_url,
+                        Some(&loaded),
+                        visited,
+                        fetch,
+                    )?;
                     visited.remove(&visit_key);
 
                     obj.remove("$ref");
@@ -414,12 +1126,12 @@ fn bundle_refs_remote_inner(
 
             // Recurse into all values
             for value in obj.values_mut() {
-                bundle_refs_remote_inner(value, base_url, file_root, visited)?;
+                bundle_refs_remote_inner(value, base_url, file_root, visited, fetch)?;
             }
         }
         Value::Array(arr) => {
             for item in arr {
-                bundle_refs_remote_inner(item, base_url, file_root, visited)?;
+                bundle_refs_remote_inner(item, base_url, file_root, visited, fetch)?;
             }
         }
         _ => {}
@@ -453,10 +1165,32 @@ fn resolve_url(url: &str, base: &str) -> String {
 ///
 /// Returns appropriate errors based on the source type.
 pub fn load_schema_auto(source: &str) -> Result<Value, ResolveError> {
+    load_schema_auto_as(source, None)
+}
+
+/// [`load_schema_auto`] with an optional forced format for file sources.
+///
+/// `None` infers the format from the file extension. URLs are always fetched
+/// as JSON.
+pub fn load_schema_auto_as(
+    source: &str,
+    format: Option<InputFormat>,
+) -> Result<Value, ResolveError> {
+    load_schema_auto_with(
+        source,
+        &LoadOptions {
+            format,
+            ..LoadOptions::default()
+        },
+    )
+}
+
+/// [`load_schema_auto`] with explicit [`LoadOptions`].
+pub fn load_schema_auto_with(source: &str, options: &LoadOptions) -> Result<Value, ResolveError> {
     if is_url(source) {
         #[cfg(feature = "remote")]
         {
-            load_schema_url(source)
+            load_schema_url_with(source, options).map(|fetched| fetched.schema)
         }
         #[cfg(not(feature = "remote"))]
         {
@@ -465,7 +1199,11 @@ pub fn load_schema_auto(source: &str) -> Result<Value, ResolveError> {
             })
         }
     } else {
-        load_schema(Path::new(source))
+        let path = Path::new(source);
+        let format = options
+            .format
+            .unwrap_or_else(|| InputFormat::from_path(path));
+        load_schema_as(path, format)
     }
 }
 
@@ -484,12 +1222,299 @@ mod tests {
         assert_eq!(schema["type"], "object");
     }
 
+    #[test]
+    fn inline_internal_refs_expands_local_pointers() {
+        let schema = serde_json::json!({
+            "properties": {
+                "total": { "$ref": "#/$defs/amount", "description": "Order total" },
+                "tree": { "$ref": "#/$defs/tree" },
+                "self": { "$ref": "#" }
+            },
+            "$defs": {
+                "amount": { "type": "integer", "description": "Minor units" },
+                "tree": { "items": { "$ref": "#/$defs/tree" } }
+            }
+        });
+
+        let inlined = inline_internal_refs(&schema).unwrap();
+        let props = &inlined["properties"];
+        assert_eq!(props["total"]["type"], "integer");
+        assert_eq!(props["total"]["description"], "Order total");
+        assert!(props["total"].get("$ref").is_none());
+        assert_eq!(props["tree"]["items"]["$ref"], "#/$defs/tree");
+        assert_eq!(props["self"]["$ref"], "#");
+        assert!(inlined.get("$defs").is_some());
+    }
+
+    #[test]
+    fn inline_internal_refs_rejects_dangling_pointer() {
+        let schema = serde_json::json!({
+            "properties": { "x": { "$ref": "#/$defs/missing" } }
+        });
+        let result = inline_internal_refs(&schema);
+        assert!(matches!(result, Err(ResolveError::BundleError { .. })));
+    }
+
+    #[test]
+    fn assert_bundled_allows_only_internal_refs() {
+        let schema = serde_json::json!({
+            "properties": {
+                "self": { "$ref": "#" },
+                "item": { "$ref": "#/$defs/item" }
+            },
+            "$defs": { "item": { "type": "string" } }
+        });
+        assert!(assert_bundled(&schema).is_ok());
+
+        let schema = serde_json::json!({
+            "allOf": [{ "$ref": "#/$defs/a" }, { "$ref": "types/a~b.json" }]
+        });
+        match assert_bundled(&schema) {
+            Err(ResolveError::UnbundledRef { path, reference }) => {
+                assert_eq!(path, "/allOf/1/$ref");
+                assert_eq!(reference, "types/a~b.json");
+            }
+            other => panic!("expected UnbundledRef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn collect_refs_lists_every_ref_with_its_pointer() {
+        let schema = serde_json::json!({
+            "properties": {
+                "buyer": { "$ref": "types/buyer.json" },
+                "a/b": { "items": { "$ref": "#/$defs/item" } }
+            },
+            "allOf": [{ "$ref": "https://ucp.dev/schemas/base.json#/$defs/x" }],
+            "$defs": { "item": { "type": "string" } }
+        });
+
+        let refs = collect_refs(&schema);
+        assert_eq!(
+            refs,
+            [
+                ("/properties/buyer/$ref", "types/buyer.json"),
+                ("/properties/a~1b/items/$ref", "#/$defs/item"),
+                (
+                    "/allOf/0/$ref",
+                    "https://ucp.dev/schemas/base.json#/$defs/x"
+                ),
+            ]
+            .map(|(p, r)| (p.to_string(), r.to_string()))
+        );
+        let internal: Vec<bool> = refs.iter().map(|(_, r)| is_internal_ref(r)).collect();
+        assert_eq!(internal, [false, true, false]);
+    }
+
+    #[test]
+    fn bundle_rejects_ref_to_non_schema_value() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("common.json"),
+            r#"{"$defs": {"Name": "string", "Flag": true}}"#,
+        )
+        .unwrap();
+
+        let mut schema = serde_json::json!({
+            "properties": { "name": { "$ref": "common.json#/$defs/Name" } }
+        });
+        let err = bundle_refs(&mut schema, dir.path()).unwrap_err();
+        assert!(matches!(err, ResolveError::BundleError { .. }));
+        assert!(err.to_string().contains("is a string"), "{}", err);
+
+        // Boolean schemas are schemas
+        let mut schema = serde_json::json!({
+            "properties": { "flag": { "$ref": "common.json#/$defs/Flag" } }
+        });
+        bundle_refs(&mut schema, dir.path()).unwrap();
+
+        let schema = serde_json::json!({
+            "$defs": { "n": 3 },
+            "properties": { "x": { "$ref": "#/$defs/n" } }
+        });
+        let result = inline_internal_refs(&schema);
+        assert!(matches!(result, Err(ResolveError::BundleError { .. })));
+    }
+
+    #[cfg(feature = "patch")]
+    #[test]
+    fn apply_patch_edits_schema() {
+        let mut schema = serde_json::json!({
+            "properties": { "sku": { "type": "string", "pattern": "^[A-Z]+$" } }
+        });
+        let patch = serde_json::json!([
+            { "op": "remove", "path": "/properties/sku/pattern" },
+            { "op": "add", "path": "/properties/sku/maxLength", "value": 32 }
+        ]);
+
+        apply_patch(&mut schema, &patch).unwrap();
+        assert!(schema["properties"]["sku"].get("pattern").is_none());
+        assert_eq!(schema["properties"]["sku"]["maxLength"], 32);
+    }
+
+    #[cfg(feature = "patch")]
+    #[test]
+    fn apply_patch_rejects_bad_operations() {
+        let mut schema = serde_json::json!({ "type": "object" });
+        let original = schema.clone();
+
+        let missing = serde_json::json!([{ "op": "remove", "path": "/properties/x" }]);
+        let result = apply_patch(&mut schema, &missing);
+        assert!(matches!(result, Err(ResolveError::InvalidPatch { .. })));
+        assert_eq!(schema, original);
+
+        let unknown_op = serde_json::json!([{ "op": "rename", "path": "/type" }]);
+        let result = apply_patch(&mut schema, &unknown_op);
+        assert!(matches!(result, Err(ResolveError::InvalidPatch { .. })));
+    }
+
+    #[test]
+    fn split_ref_ignores_query() {
+        assert_eq!(
+            split_ref("common.json?version=2#/$defs/Address"),
+            ("common.json", Some("#/$defs/Address"))
+        );
+        assert_eq!(split_ref("common.json?version=2"), ("common.json", None));
+        assert_eq!(split_ref("common.json"), ("common.json", None));
+    }
+
+    #[test]
+    fn bundle_ref_siblings_override_referenced_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.json"),
+            r#"{
+                "type": "object",
+                "description": "from base",
+                "properties": { "id": { "type": "string" } },
+                "$defs": { "money": { "type": "integer", "description": "cents" } }
+            }"#,
+        )
+        .unwrap();
+        let mut schema = serde_json::json!({
+            "properties": {
+                "whole": { "$ref": "base.json", "description": "override" },
+                "part": { "$ref": "base.json#/$defs/money", "minimum": 0, "description": "total" }
+            }
+        });
+
+        bundle_refs(&mut schema, dir.path()).unwrap();
+        let whole = &schema["properties"]["whole"];
+        assert!(whole.get("$ref").is_none());
+        assert_eq!(whole["description"], "override");
+        assert_eq!(whole["type"], "object");
+        assert_eq!(whole["properties"]["id"]["type"], "string");
+
+        let part = &schema["properties"]["part"];
+        assert_eq!(part["description"], "total");
+        assert_eq!(part["minimum"], 0);
+        assert_eq!(part["type"], "integer");
+    }
+
+    #[test]
+    fn bundle_resolves_relative_refs_against_id() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("types")).unwrap();
+        std::fs::write(
+            dir.path().join("types/buyer.json"),
+            r#"{"type": "object", "required": ["email"]}"#,
+        )
+        .unwrap();
+        let mut schema = serde_json::json!({
+            "$id": "https://ucp.dev/schemas/checkout.json",
+            "$defs": { "total": { "type": "integer" } },
+            "properties": {
+                "total": { "$ref": "checkout.json#/$defs/total" },
+                "buyer": { "$ref": "/schemas/types/buyer.json" }
+            }
+        });
+
+        bundle_refs(&mut schema, dir.path()).unwrap();
+        assert_eq!(schema["properties"]["total"]["type"], "integer");
+        assert_eq!(schema["properties"]["buyer"]["required"][0], "email");
+    }
+
+    #[test]
+    fn bundle_ref_with_query_loads_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("common.json"),
+            r#"{"$defs": {"Address": {"type": "object"}}}"#,
+        )
+        .unwrap();
+        let mut schema = serde_json::json!({
+            "properties": { "addr": { "$ref": "common.json?version=2#/$defs/Address" } }
+        });
+
+        bundle_refs(&mut schema, dir.path()).unwrap();
+        assert_eq!(schema["properties"]["addr"]["type"], "object");
+    }
+
     #[test]
     fn load_schema_file_not_found() {
         let result = load_schema(Path::new("/nonexistent/path.json"));
         assert!(matches!(result, Err(ResolveError::FileNotFound { .. })));
     }
 
+    #[test]
+    fn bundle_ref_to_directory_is_not_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("types")).unwrap();
+        let mut schema = serde_json::json!({
+            "properties": { "item": { "$ref": "types" } }
+        });
+
+        let result = bundle_refs(&mut schema, dir.path());
+        assert!(matches!(
+            result,
+            Err(ResolveError::RefTargetError { ref source, .. })
+                if matches!(**source, ResolveError::NotAFile { .. })
+        ));
+        assert!(result.unwrap_err().to_string().contains("directory"));
+    }
+
+    #[test]
+    fn bundle_load_failure_names_referencing_file_and_pointer() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("checkout.json"),
+            r#"{"$defs": {"order": {"properties": {"buyer": {"$ref": "types/buyer.json"}}}}}"#,
+        )
+        .unwrap();
+        let mut schema = serde_json::json!({
+            "properties": { "order": { "$ref": "checkout.json#/$defs/order" } }
+        });
+
+        let err = bundle_refs(&mut schema, dir.path()).unwrap_err();
+        let ResolveError::RefTargetError {
+            reference,
+            referrer,
+            pointer,
+            source,
+        } = &err
+        else {
+            panic!("expected RefTargetError, got {:?}", err);
+        };
+        assert_eq!(reference, "types/buyer.json");
+        assert!(referrer.ends_with("checkout.json"), "{}", referrer);
+        assert_eq!(pointer, "#/$defs/order/properties/buyer");
+        assert!(matches!(**source, ResolveError::FileNotFound { .. }));
+        assert_eq!(err.exit_code(), 3);
+
+        // A ref written in the schema being bundled names the root schema
+        let mut schema = serde_json::json!({
+            "properties": { "a/b": { "$ref": "missing.json" } }
+        });
+        let err = bundle_refs(&mut schema, dir.path()).unwrap_err();
+        assert!(
+            err.to_string().ends_with(
+                "(loading $ref \"missing.json\" at #/properties/a~1b in the root schema)"
+            ),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn load_schema_invalid_json() {
         let mut file = NamedTempFile::new().unwrap();
@@ -511,6 +1536,32 @@ mod tests {
         assert!(matches!(result, Err(ResolveError::InvalidJson { .. })));
     }
 
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn load_schema_yaml_by_extension() {
+        let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        writeln!(file, "type: object\nrequired: [id]").unwrap();
+
+        let schema = load_schema(file.path()).unwrap();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"][0], "id");
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn load_schema_as_overrides_extension() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        writeln!(file, "type: object").unwrap();
+
+        assert!(matches!(
+            load_schema(file.path()),
+            Err(ResolveError::InvalidJson { .. })
+        ));
+        let schema = load_schema_as(file.path(), InputFormat::Yaml).unwrap();
+        assert_eq!(schema["type"], "object");
+        assert_eq!("yml".parse::<InputFormat>(), Ok(InputFormat::Yaml));
+    }
+
     #[test]
     fn is_url_https() {
         assert!(is_url("https://example.com/schema.json"));
@@ -656,5 +1707,168 @@ mod tests {
             assert_eq!(result.unwrap()["type"], "string");
             mock.assert();
         }
+
+        #[test]
+        fn load_schema_url_with_reports_redirects() {
+            let mut server = mockito::Server::new();
+            server
+                .mock("GET", "/canonical.json")
+                .with_status(301)
+                .with_header("location", "/v2/schema.json")
+                .create();
+            server
+                .mock("GET", "/v2/schema.json")
+                .with_header("content-type", "application/json")
+                .with_body(r#"{"type": "object"}"#)
+                .create();
+            let url = format!("{}/canonical.json", server.url());
+
+            let fetched = load_schema_url_with(&url, &LoadOptions::default()).unwrap();
+            assert_eq!(fetched.schema["type"], "object");
+            assert_eq!(fetched.redirects, 1);
+            assert_eq!(
+                fetched.final_url,
+                format!("{}/v2/schema.json", server.url())
+            );
+
+            let options = LoadOptions {
+                max_redirects: 0,
+                ..LoadOptions::default()
+            };
+            let err = load_schema_url_with(&url, &options).unwrap_err();
+            assert!(matches!(err, ResolveError::NetworkError { .. }));
+            assert!(err.to_string().contains("too many redirects"), "{}", err);
+        }
+
+        #[test]
+        fn load_schema_url_with_stops_redirect_loop() {
+            let mut server = mockito::Server::new();
+            server
+                .mock("GET", "/loop.json")
+                .with_status(302)
+                .with_header("location", "/loop.json")
+                .create();
+
+            let options = LoadOptions {
+                max_redirects: 3,
+                ..LoadOptions::default()
+            };
+            let result = load_schema_url_with(&format!("{}/loop.json", server.url()), &options);
+            assert!(matches!(result, Err(ResolveError::NetworkError { .. })));
+        }
+    }
+
+    #[cfg(feature = "async")]
+    mod remote_async {
+        use super::*;
+
+        fn block_on<F: std::future::Future>(future: F) -> F::Output {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(future)
+        }
+
+        #[test]
+        fn load_schema_url_async_valid() {
+            let mut server = mockito::Server::new();
+            let mock = server
+                .mock("GET", "/schema.json")
+                .with_header("content-type", "application/json")
+                .with_body(r#"{"type": "object"}"#)
+                .create();
+
+            let result = block_on(load_schema_url_async(&format!(
+                "{}/schema.json",
+                server.url()
+            )));
+            assert_eq!(result.unwrap()["type"], "object");
+            mock.assert();
+        }
+
+        #[test]
+        fn load_schema_url_async_404() {
+            let mut server = mockito::Server::new();
+            server
+                .mock("GET", "/missing.json")
+                .with_status(404)
+                .create();
+
+            let result = block_on(load_schema_url_async(&format!(
+                "{}/missing.json",
+                server.url()
+            )));
+            assert!(matches!(result, Err(ResolveError::NetworkError { .. })));
+        }
+
+        #[test]
+        fn load_schema_url_with_async_honors_redirect_limit() {
+            let mut server = mockito::Server::new();
+            server
+                .mock("GET", "/canonical.json")
+                .with_status(301)
+                .with_header("location", "/v2/schema.json")
+                .create();
+            server
+                .mock("GET", "/v2/schema.json")
+                .with_header("content-type", "application/json")
+                .with_body(r#"{"type": "object"}"#)
+                .create();
+            let url = format!("{}/canonical.json", server.url());
+
+            let fetched =
+                block_on(load_schema_url_with_async(&url, &LoadOptions::default())).unwrap();
+            assert_eq!(fetched.redirects, 1);
+            assert_eq!(
+                fetched.final_url,
+                format!("{}/v2/schema.json", server.url())
+            );
+
+            let options = LoadOptions {
+                max_redirects: 0,
+                ..LoadOptions::default()
+            };
+            let err = block_on(load_schema_url_with_async(&url, &options)).unwrap_err();
+            assert!(err.to_string().contains("too many redirects"), "{}", err);
+        }
+
+        #[test]
+        fn bundle_refs_remote_async_matches_blocking() {
+            // Transitive refs (root -> buyer -> address) are prefetched, and
+            // each document is fetched once per bundle call.
+            let mut server = mockito::Server::new();
+            let buyer = server
+                .mock("GET", "/types/buyer.json")
+                .with_body(
+                    r#"{"type": "object", "properties": {"address": {"$ref": "address.json"}}}"#,
+                )
+                .expect(2)
+                .create();
+            let address = server
+                .mock("GET", "/types/address.json")
+                .with_body(r#"{"type": "object", "properties": {"city": {"type": "string"}}}"#)
+                .expect(2)
+                .create();
+
+            let base = format!("{}/checkout.json", server.url());
+            let schema = serde_json::json!({
+                "properties": { "buyer": { "$ref": "types/buyer.json" } }
+            });
+
+            let mut blocking = schema.clone();
+            bundle_refs_remote(&mut blocking, &base).unwrap();
+            let mut async_bundled = schema;
+            block_on(bundle_refs_remote_async(&mut async_bundled, &base)).unwrap();
+
+            assert_eq!(async_bundled, blocking);
+            assert_eq!(
+                async_bundled["properties"]["buyer"]["properties"]["address"]["properties"]["city"]
+                    ["type"],
+                "string"
+            );
+            buyer.assert();
+            address.assert();
+        }
     }
 }
@@ -0,0 +1,373 @@
+//! OpenAPI 3.0 output target.
+//!
+//! Resolved schemas are JSON Schema 2020-12. OpenAPI 3.0 schema objects are an
+//! older, restricted dialect: no `$defs`, `nullable` instead of `"null"` in
+//! `type`, a single `type` string, and no 2020-12 applicators. This module
+//! rewrites the common cases and drops what 3.0 cannot express, reporting
+//! each dropped keyword.
+//!
+//! Conversions:
+//! - Internal `#/...` refs are inlined and `$defs`/`definitions` removed;
+//!   recursive refs stay as `$ref` and keep `$defs` in place (with a warning)
+//! - `type: [T, "null"]` becomes `type: T, nullable: true`; several non-null
+//!   types become an `anyOf` of single-type branches, and `type: ["null"]`
+//!   becomes `nullable: true, enum: [null]`
+//! - A `{"type": "null"}` branch of `anyOf`/`oneOf` becomes `nullable: true`
+//! - `const: v` becomes `enum: [v]`
+//! - `examples: [v, ...]` becomes `example: v`
+//! - Numeric `exclusiveMinimum`/`exclusiveMaximum` become
+//!   `minimum`/`maximum` with the boolean form, unless a declared
+//!   `minimum`/`maximum` is already stricter
+//! - `$schema`, `$id`, `$anchor`, and `$comment` are removed silently
+//!
+//! Unsupported keywords that are dropped with a warning: see
+//! [`UNSUPPORTED_KEYWORDS`]. Schemas that rely on them validate more loosely
+//! after conversion.
+
+use serde_json::{Map, Value};
+
+use crate::error::ResolveError;
+use crate::loader::{escape_pointer_token, inline_local_refs};
+
+/// 2020-12 keywords with no OpenAPI 3.0 equivalent, dropped with a warning.
+pub const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "prefixItems",
+    "contains",
+    "minContains",
+    "maxContains",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "patternProperties",
+    "propertyNames",
+    "dependentRequired",
+    "dependentSchemas",
+    "if",
+    "then",
+    "else",
+    "$dynamicRef",
+    "$dynamicAnchor",
+    "contentEncoding",
+    "contentMediaType",
+    "contentSchema",
+];
+
+/// Identifier and comment keywords removed without a warning.
+const SILENT_KEYWORDS: &[&str] = &["$schema", "$id", "$anchor", "$comment"];
+
+/// A schema converted for OpenAPI 3.0 and what the conversion gave up.
+#[derive(Debug, Clone)]
+pub struct OpenApiConversion {
+    pub schema: Value,
+    /// One message per dropped keyword or kept construct, prefixed with the
+    /// JSON pointer of the schema it was found in.
+    pub warnings: Vec<String>,
+}
+
+/// Convert a resolved JSON Schema into an OpenAPI 3.0 schema object.
+///
+/// # Errors
+///
+/// Returns `BundleError` if an internal `$ref` points at a missing or
+/// non-schema value.
+pub fn to_openapi30(schema: &Value) -> Result<OpenApiConversion, ResolveError> {
+    let mut schema = schema.clone();
+    let root = schema.clone();
+    inline_local_refs(&mut schema, &root)?;
+
+    let mut warnings = Vec::new();
+    let recursive = contains_local_ref(&schema);
+    if let Value::Object(obj) = &mut schema {
+        if recursive {
+            warnings.push(
+                "/: recursive $ref kept; $defs left in place (move it to components/schemas)"
+                    .to_string(),
+            );
+        } else {
+            obj.remove("$defs");
+            obj.remove("definitions");
+        }
+    }
+
+    convert_schema(&mut schema, "", &mut warnings);
+    Ok(OpenApiConversion { schema, warnings })
+}
+
+/// Whether any internal `$ref` survived inlining (recursion points).
+fn contains_local_ref(value: &Value) -> bool {
+    match value {
+        Value::Object(obj) => {
+            obj.get("$ref")
+                .and_then(Value::as_str)
+                .is_some_and(|r| r.starts_with('#'))
+                || obj.values().any(contains_local_ref)
+        }
+        Value::Array(items) => items.iter().any(contains_local_ref),
+        _ => false,
+    }
+}
+
+/// Rewrite one schema object, then recurse into its subschema positions.
+fn convert_schema(schema: &mut Value, path: &str, warnings: &mut Vec<String>) {
+    let Value::Object(obj) = schema else {
+        return;
+    };
+    let at = if path.is_empty() { "/" } else { path };
+
+    for key in SILENT_KEYWORDS {
+        obj.remove(*key);
+    }
+    for key in UNSUPPORTED_KEYWORDS {
+        if obj.remove(*key).is_some() {
+            warnings.push(format!("{}: dropped unsupported keyword `{}`", at, key));
+        }
+    }
+
+    convert_type(obj);
+    convert_null_branches(obj);
+    if let Some(value) = obj.remove("const") {
+        obj.entry("enum")
+            .or_insert_with(|| Value::Array(vec![value]));
+    }
+    if let Some(Value::Array(mut examples)) = obj.remove("examples") {
+        if !examples.is_empty() {
+            obj.entry("example")
+                .or_insert_with(|| examples.swap_remove(0));
+        }
+    }
+    convert_exclusive_bound(obj, "exclusiveMinimum", "minimum");
+    convert_exclusive_bound(obj, "exclusiveMaximum", "maximum");
+
+    for key in ["properties", "$defs", "definitions"] {
+        if let Some(Value::Object(map)) = obj.get_mut(key) {
+            for (name, sub) in map.iter_mut() {
+                convert_schema(
+                    sub,
+                    &format!("{}/{}/{}", path, key, escape_pointer_token(name)),
+                    warnings,
+                );
+            }
+        }
+    }
+    for key in ["items", "additionalProperties", "not"] {
+        if let Some(sub) = obj.get_mut(key) {
+            convert_schema(sub, &format!("{}/{}", path, key), warnings);
+        }
+    }
+    for key in ["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = obj.get_mut(key) {
+            for (i, sub) in branches.iter_mut().enumerate() {
+                convert_schema(sub, &format!("{}/{}/{}", path, key, i), warnings);
+            }
+        }
+    }
+}
+
+/// `type: [T, "null"]` → `type: T, nullable: true`; several non-null types
+/// become an `anyOf` of single-type branches.
+fn convert_type(obj: &mut Map<String, Value>) {
+    let Some(Value::Array(types)) = obj.get("type") else {
+        return;
+    };
+    let nullable = types.iter().any(|t| t == "null");
+    let mut rest: Vec<Value> = types.iter().filter(|t| *t != "null").cloned().collect();
+
+    obj.remove("type");
+    match rest.len() {
+        // 3.0 has no null type, and `nullable` needs a `type` to act on
+        0 if nullable => {
+            obj.entry("enum")
+                .or_insert_with(|| Value::Array(vec![Value::Null]));
+        }
+        0 => {}
+        1 => {
+            obj.insert("type".to_string(), rest.remove(0));
+        }
+        _ => {
+            let branches = rest
+                .into_iter()
+                .map(|t| serde_json::json!({ "type": t }))
+                .collect();
+            let any_of = Value::Array(branches);
+            if obj.contains_key("anyOf") {
+                // Keep an existing anyOf; both must hold
+                let all_of = obj
+                    .entry("allOf")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Value::Array(all_of) = all_of {
+                    all_of.push(serde_json::json!({ "anyOf": any_of }));
+                }
+            } else {
+                obj.insert("anyOf".to_string(), any_of);
+            }
+        }
+    }
+    if nullable {
+        obj.insert("nullable".to_string(), Value::Bool(true));
+    }
+}
+
+/// Remove `{"type": "null"}` branches from `anyOf`/`oneOf`, marking the schema
+/// `nullable`. A single remaining branch is merged into the schema.
+fn convert_null_branches(obj: &mut Map<String, Value>) {
+    let is_null_branch = |v: &Value| {
+        v.get("type").and_then(Value::as_str) == Some("null")
+            && v.as_object().is_some_and(|o| o.len() == 1)
+    };
+
+    for key in ["anyOf", "oneOf"] {
+        let Some(Value::Array(branches)) = obj.get_mut(key) else {
+            continue;
+        };
+        let before = branches.len();
+        branches.retain(|b| !is_null_branch(b));
+        if branches.len() == before {
+            continue;
+        }
+        obj.insert("nullable".to_string(), Value::Bool(true));
+
+        let Some(Value::Array(mut branches)) = obj.remove(key) else {
+            continue;
+        };
+        match branches.pop() {
+            Some(Value::Object(only)) if branches.is_empty() => {
+                for (k, v) in only {
+                    obj.entry(k).or_insert(v);
+                }
+            }
+            Some(last) => {
+                branches.push(last);
+                obj.insert(key.to_string(), Value::Array(branches));
+            }
+            None => {}
+        }
+    }
+}
+
+/// 2020-12 numeric `exclusiveMinimum: n` → `minimum: n, exclusiveMinimum: true`.
+///
+/// A schema declaring both keeps whichever bound is stricter: an inclusive
+/// bound beyond the exclusive one stands alone.
+fn convert_exclusive_bound(obj: &mut Map<String, Value>, exclusive: &str, inclusive: &str) {
+    let Some(bound) = obj.get(exclusive).and_then(Value::as_f64) else {
+        return;
+    };
+    let exclusive_value = obj.remove(exclusive).unwrap_or(Value::Null);
+    if let Some(declared) = obj.get(inclusive).and_then(Value::as_f64) {
+        let inclusive_stricter = if exclusive == "exclusiveMinimum" {
+            declared > bound
+        } else {
+            declared < bound
+        };
+        if inclusive_stricter {
+            return;
+        }
+    }
+    obj.insert(inclusive.to_string(), exclusive_value);
+    obj.insert(exclusive.to_string(), Value::Bool(true));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn converts_common_constructs() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "https://example.com/checkout.json",
+            "type": "object",
+            "$defs": { "money": { "type": "integer", "exclusiveMinimum": 0 } },
+            "properties": {
+                "note": { "type": ["string", "null"] },
+                "total": { "$ref": "#/$defs/money" },
+                "kind": { "const": "checkout" },
+                "code": { "type": "string", "examples": ["SAVE10", "FREESHIP"] },
+                "tags": { "type": "array", "prefixItems": [{ "type": "string" }] },
+                "ref": { "anyOf": [{ "type": "string" }, { "type": "null" }] }
+            }
+        });
+
+        let converted = to_openapi30(&schema).unwrap();
+        let out = &converted.schema;
+        assert!(out.get("$defs").is_none());
+        assert!(out.get("$schema").is_none());
+        assert!(out.get("$id").is_none());
+
+        let props = &out["properties"];
+        assert_eq!(props["note"], json!({ "type": "string", "nullable": true }));
+        assert_eq!(
+            props["total"],
+            json!({ "type": "integer", "minimum": 0, "exclusiveMinimum": true })
+        );
+        assert_eq!(props["kind"], json!({ "enum": ["checkout"] }));
+        assert_eq!(props["code"]["example"], "SAVE10");
+        assert!(props["tags"].get("prefixItems").is_none());
+        assert_eq!(props["ref"], json!({ "type": "string", "nullable": true }));
+
+        assert_eq!(
+            converted.warnings,
+            vec!["/properties/tags: dropped unsupported keyword `prefixItems`"]
+        );
+    }
+
+    #[test]
+    fn multiple_types_become_any_of() {
+        let schema = json!({ "type": ["string", "number", "null"] });
+        let out = to_openapi30(&schema).unwrap().schema;
+        assert_eq!(
+            out,
+            json!({
+                "anyOf": [{ "type": "string" }, { "type": "number" }],
+                "nullable": true
+            })
+        );
+    }
+
+    #[test]
+    fn null_only_type_becomes_nullable_enum() {
+        let schema = json!({ "type": ["null"] });
+        let out = to_openapi30(&schema).unwrap().schema;
+        assert_eq!(out, json!({ "nullable": true, "enum": [null] }));
+    }
+
+    #[test]
+    fn exclusive_bound_keeps_stricter_inclusive_bound() {
+        let schema = json!({
+            "properties": {
+                "low": { "minimum": 5, "exclusiveMinimum": 3 },
+                "high": { "minimum": 1, "exclusiveMinimum": 3 },
+                "cap": { "maximum": 2, "exclusiveMaximum": 4 },
+                "ceiling": { "maximum": 9, "exclusiveMaximum": 4 }
+            }
+        });
+        let out = to_openapi30(&schema).unwrap().schema;
+        let props = &out["properties"];
+        assert_eq!(props["low"], json!({ "minimum": 5 }));
+        assert_eq!(
+            props["high"],
+            json!({ "minimum": 3, "exclusiveMinimum": true })
+        );
+        assert_eq!(props["cap"], json!({ "maximum": 2 }));
+        assert_eq!(
+            props["ceiling"],
+            json!({ "maximum": 4, "exclusiveMaximum": true })
+        );
+    }
+
+    #[test]
+    fn recursive_ref_keeps_defs() {
+        let schema = json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": { "next": { "$ref": "#/$defs/node" } }
+                }
+            },
+            "properties": { "head": { "$ref": "#/$defs/node" } }
+        });
+        let converted = to_openapi30(&schema).unwrap();
+        assert!(converted.schema.get("$defs").is_some());
+        assert!(converted.warnings[0].contains("recursive $ref"));
+    }
+}
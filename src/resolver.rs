@@ -4,8 +4,10 @@ use serde_json::{Map, Value};
 
 use crate::error::ResolveError;
 use crate::types::{
-    is_valid_schema_transition, json_type_name, Direction, ResolveOptions, SchemaTransitionInfo,
-    Visibility, UCP_ANNOTATIONS,
+    is_constraint_object, is_valid_schema_transition, json_type_name, override_value_error,
+    Direction, Omission, OmitMatch, RequiredPolicy, ResolveOptions, ResolveStats,
+    SchemaTransitionInfo, Visibility, UCP_ANNOTATIONS, UCP_DEFAULTS, UCP_OVERRIDABLE_KEYWORDS,
+    UCP_STRICT,
 };
 
 /// Resolve a schema for a specific direction and operation.
@@ -13,19 +15,150 @@ use crate::types::{
 /// Returns a standard JSON Schema with UCP annotations removed.
 /// When `options.strict` is true, sets `additionalProperties: false`
 /// on all object schemas to reject unknown fields. Default is false
-/// to respect UCP's extensibility model.
+/// to respect UCP's extensibility model. An object's `ucp_strict`
+/// annotation overrides `options.strict` for the requested operation, for
+/// that object and everything nested in it.
 ///
 /// # Errors
 ///
 /// Returns `ResolveError` if the schema contains invalid annotations.
 pub fn resolve(schema: &Value, options: &ResolveOptions) -> Result<Value, ResolveError> {
-    let mut resolved = resolve_value(schema, options, "")?;
+    resolve_explained(schema, options).map(|(resolved, _)| resolved)
+}
+
+/// [`resolve`], also reporting each property dropped as omitted and why.
+///
+/// Omissions are listed in the order the walk reaches them. Nothing below an
+/// omitted property is visited, so only the outermost omitted property of a
+/// subtree is reported. Planned additions kept by `include_future` are not
+/// omissions.
+///
+/// # Errors
+///
+/// Returns `ResolveError` if the schema contains invalid annotations.
+pub fn resolve_explained(
+    schema: &Value,
+    options: &ResolveOptions,
+) -> Result<(Value, Vec<Omission>), ResolveError> {
+    resolve_traced(schema, options).map(|(resolved, explain)| (resolved, explain.omissions))
+}
+
+/// [`resolve`], also counting the annotation decisions it made.
+///
+/// # Errors
+///
+/// Returns `ResolveError` if the schema contains invalid annotations.
+pub fn resolve_with_stats(
+    schema: &Value,
+    options: &ResolveOptions,
+) -> Result<(Value, ResolveStats), ResolveError> {
+    resolve_traced(schema, options).map(|(resolved, explain)| (resolved, explain.stats))
+}
+
+/// [`resolve`], reporting both the omissions of [`resolve_explained`] and the
+/// counts of [`resolve_with_stats`] from a single walk.
+///
+/// # Errors
+///
+/// Returns `ResolveError` if the schema contains invalid annotations.
+pub fn resolve_detailed(
+    schema: &Value,
+    options: &ResolveOptions,
+) -> Result<(Value, Vec<Omission>, ResolveStats), ResolveError> {
+    resolve_traced(schema, options)
+        .map(|(resolved, explain)| (resolved, explain.omissions, explain.stats))
+}
+
+/// What the walk records besides the resolved schema.
+#[derive(Default)]
+struct Explain {
+    omissions: Vec<Omission>,
+    stats: ResolveStats,
+}
 
-    if options.strict {
-        close_additional_properties(&mut resolved);
+fn resolve_traced(
+    schema: &Value,
+    options: &ResolveOptions,
+) -> Result<(Value, Explain), ResolveError> {
+    if let Some(allowed) = &options.allowed_operations {
+        check_operations(schema, allowed, "")?;
     }
 
-    Ok(resolved)
+    let mut explain = Explain::default();
+    let mut resolved = resolve_value(schema, options, "", Visibility::Include, &mut explain)?;
+
+    // `resolve_object` leaves each `ucp_strict` reduced to this operation's
+    // boolean for the closing pass to read; drop them once it has.
+    close_additional_properties(&mut resolved, options.strict);
+    remove_strict_annotations(&mut resolved);
+
+    Ok((resolved, explain))
+}
+
+/// Resolve a schema and return the resolved subschema at `pointer`.
+///
+/// `pointer` is a JSON Pointer into the schema (`/properties/buyer/properties/email`;
+/// a leading `#` is accepted). Returns `Ok(None)` when the property exists in
+/// the source schema but was omitted for this direction and operation —
+/// including when an ancestor or the enclosing `anyOf`/`oneOf` branch was
+/// omitted. Omitted branches keep their index (as `false`), so the pointer
+/// addresses the same branch in the resolved schema.
+///
+/// # Errors
+///
+/// Returns `ResolveError` if resolution fails, or if `pointer` does not
+/// exist in the source schema.
+#[cfg(feature = "full")]
+pub fn resolve_property(
+    schema: &Value,
+    pointer: &str,
+    options: &ResolveOptions,
+) -> Result<Option<Value>, ResolveError> {
+    use crate::loader::navigate_fragment;
+
+    // Check the pointer against the source first, so a typo is an error
+    // rather than indistinguishable from an omitted property
+    navigate_fragment(schema, pointer)?;
+    let resolved = resolve(schema, options)?;
+    Ok(navigate_fragment(&resolved, pointer).ok())
+}
+
+/// Check that every operation key in a per-operation annotation is allowed.
+///
+/// Covers both directions, not just the one being resolved, so an unexpected
+/// operation fails regardless of which one is requested. The `transition`
+/// key of the shorthand transition form is not an operation.
+fn check_operations(value: &Value, allowed: &[String], path: &str) -> Result<(), ResolveError> {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = format!("{}/{}", path, key);
+                if UCP_ANNOTATIONS.contains(&key.as_str()) {
+                    if let Value::Object(ops) = child {
+                        if let Some(op) = ops
+                            .keys()
+                            .find(|op| *op != "transition" && !allowed.contains(op))
+                        {
+                            return Err(ResolveError::UnknownOperation {
+                                path: child_path,
+                                op: op.clone(),
+                            });
+                        }
+                    }
+                } else {
+                    check_operations(child, allowed, &child_path)?;
+                }
+            }
+            Ok(())
+        }
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                check_operations(item, allowed, &format!("{}/{}", path, i))?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
 }
 
 /// Recursively close object schemas to reject unknown properties.
@@ -36,31 +169,49 @@ pub fn resolve(schema: &Value, options: &ResolveOptions) -> Result<Value, Resolv
 /// The distinction matters because `additionalProperties` is evaluated per-schema,
 /// while `unevaluatedProperties` (JSON Schema 2020-12) looks across all subschemas.
 /// This allows $ref inheritance patterns to work correctly in strict mode.
-fn close_additional_properties(value: &mut Value) {
-    close_additional_properties_inner(value, false);
+///
+/// `strict` is the run's setting; a resolved `ucp_strict` boolean on an object
+/// replaces it for that object and its descendants.
+fn close_additional_properties(value: &mut Value, strict: bool) {
+    close_additional_properties_inner(value, strict, false);
 }
 
+/// Keywords that only apply to objects; any of them marks an object schema
+/// for strict closing even without `"type": "object"`.
+const OBJECT_SCHEMA_KEYWORDS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "propertyNames",
+    "minProperties",
+    "maxProperties",
+];
+
 /// Inner implementation with context tracking.
 ///
 /// `in_composition_branch` is true when processing direct children of allOf/anyOf/oneOf.
 /// We skip setting additionalProperties on these because each branch is validated
 /// independently and doesn't see properties from sibling branches.
-fn close_additional_properties_inner(value: &mut Value, in_composition_branch: bool) {
+fn close_additional_properties_inner(value: &mut Value, strict: bool, in_composition_branch: bool) {
     if let Value::Object(map) = value {
+        let strict = map
+            .get(UCP_STRICT)
+            .and_then(Value::as_bool)
+            .unwrap_or(strict);
+
         // Check if this schema uses composition keywords
         let has_composition =
             map.contains_key("allOf") || map.contains_key("anyOf") || map.contains_key("oneOf");
 
-        // Check if this is an object schema (has "type": "object" or has "properties")
+        // Check if this is an object schema ("type": "object" or an object keyword)
         let is_object_schema = map
             .get("type")
             .and_then(|t| t.as_str())
             .map(|t| t == "object")
             .unwrap_or(false)
-            || map.contains_key("properties");
+            || OBJECT_SCHEMA_KEYWORDS.iter().any(|k| map.contains_key(*k));
 
         // Close the schema if we're not inside a composition branch
-        if !in_composition_branch && (is_object_schema || has_composition) {
+        if strict && !in_composition_branch && (is_object_schema || has_composition) {
             if has_composition {
                 // Use unevaluatedProperties for composition - it looks across all subschemas
                 // so $ref inheritance works correctly
@@ -94,19 +245,30 @@ fn close_additional_properties_inner(value: &mut Value, in_composition_branch: b
                     // Recurse into each property definition
                     if let Value::Object(props) = child {
                         for prop_value in props.values_mut() {
-                            close_additional_properties_inner(prop_value, false);
+                            close_additional_properties_inner(prop_value, strict, false);
                         }
                     }
                 }
-                "items" | "additionalProperties" | "unevaluatedProperties" => {
-                    // Schema values - recurse
-                    close_additional_properties_inner(child, false);
+                "items"
+                | "additionalItems"
+                | "contains"
+                | "additionalProperties"
+                | "unevaluatedProperties" => {
+                    // Schema values - recurse. Array-form `items` is a Draft 7
+                    // tuple: close each positional schema.
+                    if let Value::Array(arr) = child {
+                        for item in arr {
+                            close_additional_properties_inner(item, strict, false);
+                        }
+                    } else {
+                        close_additional_properties_inner(child, strict, false);
+                    }
                 }
-                "$defs" | "definitions" => {
-                    // Definitions - recurse into each
+                "$defs" | "definitions" | "patternProperties" => {
+                    // Name-keyed subschemas - recurse into each
                     if let Value::Object(defs) = child {
                         for def_value in defs.values_mut() {
-                            close_additional_properties_inner(def_value, false);
+                            close_additional_properties_inner(def_value, strict, false);
                         }
                     }
                 }
@@ -115,7 +277,7 @@ fn close_additional_properties_inner(value: &mut Value, in_composition_branch: b
                     // so we don't set additionalProperties on them directly
                     if let Value::Array(arr) = child {
                         for item in arr {
-                            close_additional_properties_inner(item, true);
+                            close_additional_properties_inner(item, strict, true);
                         }
                     }
                 }
@@ -138,12 +300,65 @@ pub fn get_visibility(
     direction: Direction,
     operation: &str,
     path: &str,
+) -> Result<(Visibility, Option<SchemaTransitionInfo>), ResolveError> {
+    annotation_visibility(prop, direction, operation, path, false)
+}
+
+/// Like [`get_visibility`], taking the direction and annotation parsing
+/// settings (such as [`ResolveOptions::boolean_shorthand`]) from `options`.
+///
+/// `operation` is passed separately so a caller can look up any of the
+/// operations `options` covers.
+///
+/// # Errors
+///
+/// Returns `ResolveError` if the annotation has invalid type or unknown visibility value.
+pub fn get_visibility_with_options(
+    prop: &Value,
+    operation: &str,
+    path: &str,
+    options: &ResolveOptions,
+) -> Result<(Visibility, Option<SchemaTransitionInfo>), ResolveError> {
+    annotation_visibility(
+        prop,
+        options.direction,
+        operation,
+        path,
+        options.boolean_shorthand,
+    )
+}
+
+/// Look up `prop`'s annotation for `direction` and parse its visibility.
+fn annotation_visibility(
+    prop: &Value,
+    direction: Direction,
+    operation: &str,
+    path: &str,
+    boolean_shorthand: bool,
 ) -> Result<(Visibility, Option<SchemaTransitionInfo>), ResolveError> {
     let key = direction.annotation_key();
     let Some(annotation) = prop.get(key) else {
         return Ok((Visibility::Include, None));
     };
-    get_visibility_from_annotation(annotation, operation, path)
+    get_visibility_from_annotation(annotation, operation, path, boolean_shorthand)
+}
+
+/// Visibility of a single property, for ad-hoc lookups outside a schema walk.
+///
+/// Like [`get_visibility`] without a path for error messages, transition info,
+/// or boolean shorthand; `operation` is normalized to lowercase as in
+/// [`ResolveOptions::new`].
+///
+/// # Errors
+///
+/// Returns `ResolveError` if the annotation has invalid type or unknown visibility value.
+pub fn visibility_of(
+    prop: &Value,
+    direction: Direction,
+    operation: &str,
+) -> Result<Visibility, ResolveError> {
+    let (visibility, _) = get_visibility(prop, direction, &operation.to_lowercase(), "")?;
+    Ok(visibility)
 }
 
 /// Parse visibility (and optional transition info) from a raw annotation value.
@@ -154,16 +369,32 @@ fn get_visibility_from_annotation(
     annotation: &Value,
     operation: &str,
     path: &str,
+    boolean_shorthand: bool,
 ) -> Result<(Visibility, Option<SchemaTransitionInfo>), ResolveError> {
     match annotation {
         // Shorthand: "ucp_request": "omit" - applies to all operations
         Value::String(s) => Ok((parse_visibility_string(s, path)?, None)),
 
+        // Opt-in boolean shorthand: "ucp_request": false - omit everywhere
+        Value::Bool(b) if boolean_shorthand => Ok((visibility_from_bool(*b), None)),
+
         // Object form: "ucp_request": { "create": "omit", "update": "required" }
         Value::Object(map) => {
             // Lookup operation (already lowercase from ResolveOptions)
             match map.get(operation) {
                 Some(Value::String(s)) => Ok((parse_visibility_string(s, path)?, None)),
+                Some(Value::Bool(b)) if boolean_shorthand => Ok((visibility_from_bool(*b), None)),
+                // Constraint form: { "create": { "min_items": 1, "visibility": "required" } }
+                Some(Value::Object(obj)) if is_constraint_object(obj) => {
+                    match obj.get("visibility") {
+                        None => Ok((Visibility::Include, None)),
+                        Some(Value::String(s)) => Ok((parse_visibility_string(s, path)?, None)),
+                        Some(other) => Err(ResolveError::InvalidAnnotationType {
+                            path: format!("{}/{}/visibility", path, operation),
+                            actual: json_type_name(other).to_string(),
+                        }),
+                    }
+                }
                 Some(Value::Object(obj)) => {
                     parse_transition_value(obj, &format!("{}/{}", path, operation))
                 }
@@ -190,6 +421,15 @@ fn get_visibility_from_annotation(
     }
 }
 
+/// Map boolean shorthand to visibility: `false` omits, `true` includes.
+fn visibility_from_bool(b: bool) -> Visibility {
+    if b {
+        Visibility::Include
+    } else {
+        Visibility::Omit
+    }
+}
+
 fn parse_transition_value(
     obj: &Map<String, Value>,
     path: &str,
@@ -232,21 +472,28 @@ fn parse_transition_value(
 
 /// Strip all UCP annotations from a schema.
 ///
-/// Recursively removes `ucp_request` and `ucp_response`.
+/// Recursively removes `ucp_request`, `ucp_response`, `ucp_defaults`, and `ucp_strict`. Every
+/// other keyword, including vendor extensions such as `x-ui-widget`, is left untouched.
 pub fn strip_annotations(schema: &Value) -> Value {
-    strip_annotations_recursive(schema)
+    let mut stripped = strip_annotations_recursive(schema);
+    remove_strict_annotations(&mut stripped);
+    stripped
 }
 
 // --- Internal implementation ---
 
+/// `parent` is the effective visibility of the nearest enclosing property,
+/// which `"inherit"` annotations below `value` resolve to.
 fn resolve_value(
     value: &Value,
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     match value {
-        Value::Object(map) => resolve_object(map, options, path),
-        Value::Array(arr) => resolve_array(arr, options, path),
+        Value::Object(map) => resolve_object(map, options, path, parent, explain),
+        Value::Array(arr) => resolve_array(arr, options, path, parent, explain),
         // Primitives pass through unchanged
         other => Ok(other.clone()),
     }
@@ -256,6 +503,8 @@ fn resolve_object(
     map: &Map<String, Value>,
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let mut result = Map::new();
 
@@ -271,10 +520,34 @@ fn resolve_object(
         .unwrap_or_default();
 
     let mut new_required: Vec<String> = original_required.clone();
+    let mut omitted: Vec<String> = Vec::new();
+    let default_visibility = options
+        .operations()
+        .map(|op| default_visibility(map, options.direction, op, path, options.boolean_shorthand))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Per-operation constraints hold for the output only as far as every
+    // resolved operation agrees; where they diverge the loosest one wins
+    let mut strict = Vec::new();
+    let mut min_items = Vec::new();
+    let mut overrides = Vec::new();
+    for op in options.operations() {
+        strict.push(strict_override(map, op, path)?);
+        min_items.push(min_items_override(map, options, op, path)?);
+        overrides.push(keyword_overrides(map, options, op, path)?);
+    }
+    if let Some(strict) = combine_strict(&strict) {
+        result.insert(UCP_STRICT.to_string(), Value::Bool(strict));
+    }
+    let min_items = min_items
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .and_then(|mins| mins.into_iter().min());
+    let keyword_overrides = combine_keyword_overrides(overrides);
 
     for (key, value) in map {
         // Skip UCP annotations in output
-        if UCP_ANNOTATIONS.contains(&key.as_str()) {
+        if UCP_ANNOTATIONS.contains(&key.as_str()) || key == UCP_DEFAULTS || key == UCP_STRICT {
+            explain.stats.annotations_stripped += 1;
             continue;
         }
 
@@ -282,36 +555,45 @@ fn resolve_object(
 
         match key.as_str() {
             "properties" => {
-                let resolved = resolve_properties(value, options, &child_path, &mut new_required)?;
+                let resolved = resolve_properties(
+                    value,
+                    options,
+                    &child_path,
+                    &default_visibility,
+                    parent,
+                    &mut new_required,
+                    &mut omitted,
+                    explain,
+                )?;
                 result.insert(key.clone(), resolved);
             }
             "items" => {
                 // Array items - recurse
-                let resolved = resolve_value(value, options, &child_path)?;
+                let resolved = resolve_value(value, options, &child_path, parent, explain)?;
                 result.insert(key.clone(), resolved);
             }
             "$defs" | "definitions" => {
                 // Definitions - recurse into each definition
-                let resolved = resolve_defs(value, options, &child_path)?;
+                let resolved = resolve_defs(value, options, &child_path, explain)?;
                 result.insert(key.clone(), resolved);
             }
             "allOf" => {
                 // allOf gets special handling: annotations from later branches
                 // propagate to earlier branches (last-writer-wins), enabling
                 // extension schemas to control visibility of inherited fields.
-                let resolved = resolve_allof(value, options, &child_path)?;
+                let resolved = resolve_allof(value, options, &child_path, parent, explain)?;
                 result.insert(key.clone(), resolved);
             }
             "anyOf" | "oneOf" => {
                 // anyOf/oneOf branches are independent alternatives —
                 // no annotation propagation across branches.
-                let resolved = resolve_composition(value, options, &child_path)?;
+                let resolved = resolve_composition(value, options, &child_path, parent, explain)?;
                 result.insert(key.clone(), resolved);
             }
-            "additionalProperties" => {
+            "additionalProperties" | "additionalItems" => {
                 // If it's a schema (object), recurse; otherwise keep as-is
                 if value.is_object() {
-                    let resolved = resolve_value(value, options, &child_path)?;
+                    let resolved = resolve_value(value, options, &child_path, parent, explain)?;
                     result.insert(key.clone(), resolved);
                 } else {
                     result.insert(key.clone(), value.clone());
@@ -321,30 +603,423 @@ fn resolve_object(
                 // Will be handled at the end after processing properties
                 continue;
             }
+            "type" if options.normalize_types => {
+                result.insert(key.clone(), normalize_type(value));
+            }
             _ => {
                 // Other keys - recurse if object/array, otherwise copy
-                let resolved = resolve_value(value, options, &child_path)?;
+                let resolved = resolve_value(value, options, &child_path, parent, explain)?;
                 result.insert(key.clone(), resolved);
             }
         }
     }
 
-    // Add updated required array if non-empty or if original existed
-    if !new_required.is_empty() || map.contains_key("required") {
+    // Add updated required array if non-empty or if original existed (unless
+    // the caller drops emptied arrays). Without a `properties` block
+    // (constraint-only object) nothing was annotated, so the original list is
+    // written back unchanged.
+    let keep_empty = map.contains_key("required") && !options.drop_empty_required;
+    if !new_required.is_empty() || keep_empty {
         result.insert(
             "required".to_string(),
             Value::Array(new_required.into_iter().map(Value::String).collect()),
         );
     }
 
+    if options.reject_dangling_required && !omitted.is_empty() {
+        check_dangling_required(&result, &omitted, path)?;
+    }
+
+    // Never loosen a stricter `minItems` the schema already declares
+    if let Some(min) = min_items {
+        let declared = result.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        result.insert("minItems".to_string(), Value::from(min.max(declared)));
+    }
+    for (keyword, value) in keyword_overrides {
+        apply_keyword_override(&mut result, keyword, value);
+    }
+
     Ok(Value::Object(result))
 }
 
+/// Set an overridden keyword so that the declared schema still applies.
+///
+/// Numeric bounds keep the stricter of the declared and overriding value: the
+/// larger lower bound, the smaller upper bound. An `enum` (already checked to
+/// be a subset of any declared one) replaces it. `pattern`, `format`,
+/// `multipleOf`, and `propertyNames` can't be merged into one value, so a
+/// differing override is added as an `allOf` branch and both must hold.
+fn apply_keyword_override(result: &mut Map<String, Value>, keyword: String, value: Value) {
+    if matches!(
+        keyword.as_str(),
+        "pattern" | "format" | "multipleOf" | "propertyNames"
+    ) {
+        match result.get(&keyword) {
+            Some(declared) if *declared != value => {
+                let branch = Value::Object(Map::from_iter([(keyword, value)]));
+                match result.get_mut("allOf") {
+                    Some(Value::Array(branches)) => branches.push(branch),
+                    _ => {
+                        result.insert("allOf".to_string(), Value::Array(vec![branch]));
+                    }
+                }
+            }
+            Some(_) => {}
+            None => {
+                result.insert(keyword, value);
+            }
+        }
+        return;
+    }
+    let lower = is_lower_bound(&keyword);
+    let upper = is_upper_bound(&keyword);
+    if let (true, Some(declared), Some(overriding)) = (
+        lower || upper,
+        result.get(&keyword).and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        let looser = if lower {
+            overriding < declared
+        } else {
+            overriding > declared
+        };
+        if looser {
+            return;
+        }
+    }
+    result.insert(keyword, value);
+}
+
+fn is_lower_bound(keyword: &str) -> bool {
+    matches!(keyword, "minLength" | "minimum" | "exclusiveMinimum")
+}
+
+fn is_upper_bound(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "maxLength" | "maxItems" | "maximum" | "exclusiveMaximum"
+    )
+}
+
+/// `ucp_strict` across the resolved operations: `false` for any of them
+/// opens the object, `true` closes it only when every operation says so,
+/// and anything else inherits.
+fn combine_strict(overrides: &[Option<bool>]) -> Option<bool> {
+    if overrides.contains(&Some(false)) {
+        Some(false)
+    } else if overrides.iter().all(|o| *o == Some(true)) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Keyword overrides across the resolved operations: a keyword survives only
+/// if every operation sets it, taking the loosest numeric bound, and any other
+/// keyword only if every operation sets the same value.
+fn combine_keyword_overrides(per_operation: Vec<Map<String, Value>>) -> Map<String, Value> {
+    let mut per_operation = per_operation.into_iter();
+    let mut combined = per_operation.next().unwrap_or_default();
+    for overrides in per_operation {
+        combined = combined
+            .into_iter()
+            .filter_map(|(keyword, value)| {
+                let other = overrides.get(&keyword)?;
+                if value == *other {
+                    return Some((keyword, value));
+                }
+                let (a, b) = (value.as_f64()?, other.as_f64()?);
+                let looser = if is_lower_bound(&keyword) {
+                    a.min(b)
+                } else if is_upper_bound(&keyword) {
+                    a.max(b)
+                } else {
+                    return None;
+                };
+                let value = if looser == a { value } else { other.clone() };
+                Some((keyword, value))
+            })
+            .collect();
+    }
+    combined
+}
+
+/// Canonical form of a `type` value: deduplicated, sorted with `"null"` last,
+/// and a lone type unwrapped. Anything but an array of strings is unchanged.
+fn normalize_type(value: &Value) -> Value {
+    let Some(mut types) = value
+        .as_array()
+        .and_then(|arr| arr.iter().map(Value::as_str).collect::<Option<Vec<_>>>())
+    else {
+        return value.clone();
+    };
+    types.sort_by_key(|t| (*t == "null", *t));
+    types.dedup();
+    match types.as_slice() {
+        [single] => Value::String(single.to_string()),
+        _ => Value::Array(types.into_iter().map(Value::from).collect()),
+    }
+}
+
+/// Reject conditional/dependent `required` entries that name an omitted property
+/// (opt-in via [`ResolveOptions::reject_dangling_required`]).
+///
+/// `resolve_properties` drops an omitted field from the object's own `required`,
+/// but the same instance can also be constrained by `then`/`else` branches,
+/// `dependentRequired`, and `dependentSchemas`. A `required` entry there that
+/// names an omitted field makes every payload hitting that branch invalid.
+/// A subschema that declares the property in its own `properties` is left
+/// alone — it re-introduces the field rather than dangling on the omitted one.
+fn check_dangling_required(
+    object: &Map<String, Value>,
+    omitted: &[String],
+    path: &str,
+) -> Result<(), ResolveError> {
+    let dangling = |schema: &Value, at: &str| -> Result<(), ResolveError> {
+        let Some(required) = schema.get("required").and_then(|r| r.as_array()) else {
+            return Ok(());
+        };
+        let declared = schema.get("properties").and_then(|p| p.as_object());
+        for name in required.iter().filter_map(|v| v.as_str()) {
+            let redeclared = declared.is_some_and(|d| d.contains_key(name));
+            if !redeclared && omitted.iter().any(|o| o == name) {
+                return Err(ResolveError::DanglingRequired {
+                    path: format!("{}/required", at),
+                    property: name.to_string(),
+                });
+            }
+        }
+        Ok(())
+    };
+
+    for key in ["then", "else"] {
+        if let Some(branch) = object.get(key) {
+            dangling(branch, &format!("{}/{}", path, key))?;
+        }
+    }
+
+    if let Some(deps) = object.get("dependentRequired").and_then(|d| d.as_object()) {
+        for (trigger, names) in deps {
+            let dep_path = format!("{}/dependentRequired/{}", path, trigger);
+            for name in names.as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str().filter(|n| omitted.iter().any(|o| o == n)) {
+                    return Err(ResolveError::DanglingRequired {
+                        path: dep_path,
+                        property: name.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(deps) = object.get("dependentSchemas").and_then(|d| d.as_object()) {
+        for (trigger, schema) in deps {
+            dangling(schema, &format!("{}/dependentSchemas/{}", path, trigger))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Visibility an object's `ucp_defaults` assigns to its properties for the
+/// current direction and `operation`, if any, with the annotation it came from.
+///
+/// Defaults use the same forms as a field annotation and apply only to the
+/// declaring object's own `properties`; nested objects declare their own.
+fn default_visibility<'a>(
+    map: &'a Map<String, Value>,
+    direction: Direction,
+    operation: &str,
+    path: &str,
+    boolean_shorthand: bool,
+) -> Result<Option<(Visibility, &'a Value)>, ResolveError> {
+    let Some(defaults) = map.get(UCP_DEFAULTS) else {
+        return Ok(None);
+    };
+    let defaults_path = format!("{}/{}", path, UCP_DEFAULTS);
+    let Value::Object(defaults) = defaults else {
+        return Err(ResolveError::InvalidAnnotationType {
+            path: defaults_path,
+            actual: json_type_name(defaults).to_string(),
+        });
+    };
+    let key = direction.annotation_key();
+    let Some(annotation) = defaults.get(key) else {
+        return Ok(None);
+    };
+    let (visibility, _) = get_visibility_from_annotation(
+        annotation,
+        operation,
+        &format!("{}/{}", defaults_path, key),
+        boolean_shorthand,
+    )?;
+    Ok(Some((visibility, annotation)).filter(|(v, _)| *v != Visibility::Include))
+}
+
+/// Visibility of `prop`, a property of the object `map` at `path`, with the
+/// precedence [`resolve`] uses: the property's own annotation, then the
+/// object's `ucp_defaults`.
+#[cfg(feature = "full")]
+pub(crate) fn property_visibility(
+    map: &Map<String, Value>,
+    prop: &Value,
+    direction: Direction,
+    operation: &str,
+    path: &str,
+    prop_path: &str,
+    boolean_shorthand: bool,
+) -> Result<Visibility, ResolveError> {
+    if !has_explicit_visibility(prop, direction.annotation_key(), operation) {
+        if let Some((visibility, _)) =
+            default_visibility(map, direction, operation, path, boolean_shorthand)?
+        {
+            return Ok(visibility);
+        }
+    }
+    annotation_visibility(prop, direction, operation, prop_path, boolean_shorthand)
+        .map(|(visibility, _)| visibility)
+}
+
+/// Strictness an object's `ucp_strict` sets for `operation`, if any.
+///
+/// A boolean applies to every operation; an object maps operations to
+/// booleans, and an operation it doesn't list keeps the inherited setting.
+fn strict_override(
+    map: &Map<String, Value>,
+    operation: &str,
+    path: &str,
+) -> Result<Option<bool>, ResolveError> {
+    let strict_path = format!("{}/{}", path, UCP_STRICT);
+    match map.get(UCP_STRICT) {
+        None => Ok(None),
+        Some(Value::Bool(strict)) => Ok(Some(*strict)),
+        Some(Value::Object(ops)) => match ops.get(operation) {
+            None => Ok(None),
+            Some(Value::Bool(strict)) => Ok(Some(*strict)),
+            Some(other) => Err(ResolveError::InvalidStrictAnnotation {
+                path: format!("{}/{}", strict_path, operation),
+                actual: json_type_name(other).to_string(),
+            }),
+        },
+        Some(other) => Err(ResolveError::InvalidStrictAnnotation {
+            path: strict_path,
+            actual: json_type_name(other).to_string(),
+        }),
+    }
+}
+
+/// Minimum array length a node's own annotation sets for `operation`.
+///
+/// Only the object form carries it: `"ucp_request": { "create": { "min_items": 1 } }`.
+fn min_items_override(
+    map: &Map<String, Value>,
+    options: &ResolveOptions,
+    operation: &str,
+    path: &str,
+) -> Result<Option<u64>, ResolveError> {
+    let key = options.direction.annotation_key();
+    let Some(min) = map
+        .get(key)
+        .and_then(|a| a.get(operation))
+        .and_then(|op| op.get("min_items"))
+    else {
+        return Ok(None);
+    };
+    min.as_u64()
+        .map(Some)
+        .ok_or_else(|| ResolveError::InvalidMinItems {
+            path: format!("{}/{}/{}/min_items", path, key, operation),
+            actual: min.to_string(),
+        })
+}
+
+/// Schema keywords a node's own annotation sets for `operation`:
+/// `"ucp_request": { "create": { "pattern": "..." } }`. Applied by
+/// [`apply_keyword_override`], which never loosens the declared schema; an
+/// `enum` may only narrow a declared `enum`.
+fn keyword_overrides(
+    map: &Map<String, Value>,
+    options: &ResolveOptions,
+    operation: &str,
+    path: &str,
+) -> Result<Map<String, Value>, ResolveError> {
+    let key = options.direction.annotation_key();
+    let Some(Value::Object(op)) = map.get(key).and_then(|a| a.get(operation)) else {
+        return Ok(Map::new());
+    };
+    if !is_constraint_object(op) {
+        return Ok(Map::new());
+    }
+    let mut overrides = Map::new();
+    for (keyword, value) in op {
+        match keyword.as_str() {
+            "visibility" | "min_items" => {}
+            k if UCP_OVERRIDABLE_KEYWORDS.contains(&k) => {
+                if let Some(expected) = override_value_error(k, value) {
+                    return Err(ResolveError::InvalidOverrideKeyword {
+                        path: format!("{}/{}/{}/{}", path, key, operation, keyword),
+                        keyword: keyword.clone(),
+                        reason: format!("must be {}, found {}", expected, json_type_name(value)),
+                    });
+                }
+                if let (Some(Value::Array(declared)), Value::Array(members)) =
+                    (map.get(k).filter(|_| k == "enum"), value)
+                {
+                    let added: Vec<String> = members
+                        .iter()
+                        .filter(|member| !declared.contains(member))
+                        .map(Value::to_string)
+                        .collect();
+                    if !added.is_empty() {
+                        return Err(ResolveError::InvalidOverrideKeyword {
+                            path: format!("{}/{}/{}/{}", path, key, operation, keyword),
+                            keyword: keyword.clone(),
+                            reason: format!(
+                                "adds values the declared enum rejects: {}",
+                                added.join(", ")
+                            ),
+                        });
+                    }
+                }
+                overrides.insert(keyword.clone(), value.clone());
+            }
+            _ => {
+                return Err(ResolveError::InvalidOverrideKeyword {
+                    path: format!("{}/{}/{}/{}", path, key, operation, keyword),
+                    keyword: keyword.clone(),
+                    reason: "cannot be overridden per operation".to_string(),
+                })
+            }
+        }
+    }
+    Ok(overrides)
+}
+
+/// Whether a property's own annotation decides its visibility for `operation`,
+/// so that `ucp_defaults` does not apply.
+fn has_explicit_visibility(prop: &Value, key: &str, operation: &str) -> bool {
+    match prop.get(key) {
+        None => false,
+        Some(Value::Object(ops)) => match ops.get(operation) {
+            // A constraint object without `visibility` leaves it to the defaults
+            Some(Value::Object(op)) if is_constraint_object(op) => op.contains_key("visibility"),
+            Some(_) => true,
+            None => ops.contains_key("transition"),
+        },
+        Some(_) => true,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn resolve_properties(
     value: &Value,
     options: &ResolveOptions,
     path: &str,
+    defaults: &[Option<(Visibility, &Value)>],
+    parent: Visibility,
     required: &mut Vec<String>,
+    omitted: &mut Vec<String>,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let Some(props) = value.as_object() else {
         return Ok(value.clone());
@@ -355,35 +1030,87 @@ fn resolve_properties(
     for (prop_name, prop_value) in props {
         let prop_path = format!("{}/{}", path, prop_name);
 
-        // Get visibility for this property
-        let (visibility, transition) = get_visibility(
-            prop_value,
-            options.direction,
-            &options.operation,
-            &prop_path,
-        )?;
+        // Get visibility for this property for each operation; the field's
+        // own annotation wins over the object's `ucp_defaults`
+        let ann_key = options.direction.annotation_key();
+        let mut per_operation = Vec::new();
+        for (op, default) in options.operations().zip(defaults) {
+            let defaulted = default.filter(|_| !has_explicit_visibility(prop_value, ann_key, op));
+            let (declared, transition) = match defaulted {
+                Some((default, _)) => (default, None),
+                None => get_visibility_with_options(prop_value, op, &prop_path, options)?,
+            };
+            per_operation.push((declared, transition, defaulted));
+        }
+        let visibility = combine_visibility(
+            per_operation
+                .iter()
+                .map(|(v, _, _)| inherit_from(*v, parent)),
+            options.required_policy,
+        );
+        // The first operation with a transition carries it into the output
+        let transition = per_operation.iter().find_map(|(_, t, _)| t.clone());
+        trace!(
+            "{}: {:?} for {}/{}",
+            prop_path,
+            visibility,
+            options.direction.dir_str(),
+            options.operation
+        );
+        explain.stats.properties_total += 1;
 
         match visibility {
             Visibility::Omit => {
+                explain.stats.omitted += 1;
                 // Include future fields: currently omit but transitioning to non-omit.
                 // Completes transition lifecycle symmetry — deprecations (to=omit) are
                 // already surfaced; this surfaces planned additions (from=omit).
-                let is_future =
-                    options.include_future && transition.as_ref().is_some_and(|t| t.to != "omit");
+                let is_future = options.include_future
+                    && per_operation
+                        .iter()
+                        .any(|(_, t, _)| t.as_ref().is_some_and(|t| t.to != "omit"));
 
                 if is_future {
-                    let resolved = resolve_value(prop_value, options, &prop_path)?;
-                    let mut stripped = strip_annotations(&resolved);
+                    let resolved =
+                        resolve_value(prop_value, options, &prop_path, visibility, explain)?;
+                    let mut stripped = strip_annotations_recursive(&resolved);
                     apply_transition_metadata(&mut stripped, &transition);
                     result.insert(prop_name.clone(), stripped);
                     // NOT added to required — current visibility is omit
+                } else {
+                    omitted.push(prop_name.clone());
+                    // Report what omitted the field for each operation
+                    for (op, (declared, _, defaulted)) in options.operations().zip(&per_operation) {
+                        if inherit_from(*declared, parent) != Visibility::Omit {
+                            continue;
+                        }
+                        let (annotation, value) = match defaulted {
+                            Some((_, value)) => {
+                                let object_path = path.strip_suffix("/properties").unwrap_or(path);
+                                (
+                                    format!("{}/{}/{}", object_path, UCP_DEFAULTS, ann_key),
+                                    *value,
+                                )
+                            }
+                            None => (format!("{}/{}", prop_path, ann_key), &prop_value[ann_key]),
+                        };
+                        let omission = Omission {
+                            path: prop_path.clone(),
+                            annotation,
+                            matched: omit_match(*declared, value, op),
+                        };
+                        if !explain.omissions.contains(&omission) {
+                            explain.omissions.push(omission);
+                        }
+                    }
                 }
                 required.retain(|r| r != prop_name);
             }
             Visibility::Required => {
                 // Keep property, ensure in required
-                let resolved = resolve_value(prop_value, options, &prop_path)?;
-                let mut stripped = strip_annotations(&resolved);
+                explain.stats.required += 1;
+                let resolved = resolve_value(prop_value, options, &prop_path, visibility, explain)?;
+                let mut stripped = strip_annotations_recursive(&resolved);
                 apply_transition_metadata(&mut stripped, &transition);
                 result.insert(prop_name.clone(), stripped);
                 if !required.contains(prop_name) {
@@ -392,16 +1119,31 @@ fn resolve_properties(
             }
             Visibility::Optional => {
                 // Keep property, remove from required
-                let resolved = resolve_value(prop_value, options, &prop_path)?;
-                let mut stripped = strip_annotations(&resolved);
+                explain.stats.optional += 1;
+                let resolved = resolve_value(prop_value, options, &prop_path, visibility, explain)?;
+                let mut stripped = strip_annotations_recursive(&resolved);
                 apply_transition_metadata(&mut stripped, &transition);
                 result.insert(prop_name.clone(), stripped);
                 required.retain(|r| r != prop_name);
             }
-            Visibility::Include => {
+            Visibility::Deprecate => {
+                // Keep property, remove from required, mark deprecated
+                explain.stats.optional += 1;
+                let resolved = resolve_value(prop_value, options, &prop_path, visibility, explain)?;
+                let mut stripped = strip_annotations_recursive(&resolved);
+                apply_transition_metadata(&mut stripped, &transition);
+                if let Some(obj) = stripped.as_object_mut() {
+                    obj.insert("deprecated".to_string(), Value::Bool(true));
+                }
+                result.insert(prop_name.clone(), stripped);
+                required.retain(|r| r != prop_name);
+            }
+            // `Inherit` was replaced above; the arm only keeps the match exhaustive
+            Visibility::Include | Visibility::Inherit => {
                 // Keep as-is (preserve original required status)
-                let resolved = resolve_value(prop_value, options, &prop_path)?;
-                let mut stripped = strip_annotations(&resolved);
+                explain.stats.included += 1;
+                let resolved = resolve_value(prop_value, options, &prop_path, visibility, explain)?;
+                let mut stripped = strip_annotations_recursive(&resolved);
                 apply_transition_metadata(&mut stripped, &transition);
                 result.insert(prop_name.clone(), stripped);
             }
@@ -411,19 +1153,33 @@ fn resolve_properties(
     Ok(Value::Object(result))
 }
 
+/// Which part of an already-parsed annotation omitted a property: the entry
+/// for `operation`, or a form covering every operation.
+fn omit_match(declared: Visibility, annotation: &Value, operation: &str) -> OmitMatch {
+    match annotation {
+        _ if declared == Visibility::Inherit => OmitMatch::Inherited,
+        Value::Object(ops) if ops.contains_key(operation) => {
+            OmitMatch::Operation(operation.to_string())
+        }
+        _ => OmitMatch::Shorthand,
+    }
+}
+
 fn resolve_defs(
     value: &Value,
     options: &ResolveOptions,
     path: &str,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let Some(defs) = value.as_object() else {
         return Ok(value.clone());
     };
 
+    // A definition stands on its own, not under the property that holds it
     let mut result = Map::new();
     for (name, def) in defs {
         let def_path = format!("{}/{}", path, name);
-        let resolved = resolve_value(def, options, &def_path)?;
+        let resolved = resolve_value(def, options, &def_path, Visibility::Include, explain)?;
         result.insert(name.clone(), resolved);
     }
 
@@ -434,35 +1190,99 @@ fn resolve_array(
     arr: &[Value],
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let mut result = Vec::new();
     for (i, item) in arr.iter().enumerate() {
         let item_path = format!("{}/{}", path, i);
-        let resolved = resolve_value(item, options, &item_path)?;
+        let resolved = resolve_value(item, options, &item_path, parent, explain)?;
         result.push(resolved);
     }
     Ok(Value::Array(result))
 }
 
+/// Resolve the branches of an `anyOf`/`oneOf`.
+///
+/// A branch carrying its own `ucp_request`/`ucp_response` that resolves to
+/// `omit` for this operation is replaced by `false`, which no instance
+/// matches, and reported as an omission; the remaining branches are resolved
+/// as usual. The placeholder keeps branch indices, so source pointers still
+/// address the same branch in the output. Omitting every branch is an error,
+/// since the keyword would then match nothing.
 fn resolve_composition(
     value: &Value,
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let Some(arr) = value.as_array() else {
-        return Ok(value.clone());
+        return resolve_malformed_composition(value, options, path, parent, explain);
     };
 
+    let ann_key = options.direction.annotation_key();
     let mut result = Vec::new();
+    let mut kept = 0;
     for (i, item) in arr.iter().enumerate() {
         let item_path = format!("{}/{}", path, i);
-        let resolved = resolve_value(item, options, &item_path)?;
+        // A branch annotated `omit` for every operation becomes `false`
+        let mut declared = Vec::new();
+        for op in options.operations() {
+            let (visibility, _) = get_visibility_with_options(item, op, &item_path, options)?;
+            declared.push(visibility);
+        }
+        let visibility = combine_visibility(
+            declared.iter().map(|v| inherit_from(*v, parent)),
+            options.required_policy,
+        );
+        if visibility == Visibility::Omit {
+            trace!("{}: branch omitted", item_path);
+            explain.omissions.push(Omission {
+                path: item_path.clone(),
+                annotation: format!("{}/{}", item_path, ann_key),
+                matched: omit_match(declared[0], &item[ann_key], &options.operation),
+            });
+            result.push(Value::Bool(false));
+            continue;
+        }
+        kept += 1;
+        let resolved = resolve_value(item, options, &item_path, parent, explain)?;
         result.push(resolved);
     }
+    if kept == 0 && !arr.is_empty() {
+        return Err(ResolveError::EmptyComposition {
+            path: path.to_string(),
+        });
+    }
 
     Ok(Value::Array(result))
 }
 
+/// A composition keyword whose value is not an array (lint E010).
+///
+/// An object is resolved as a single schema so its annotations are applied
+/// and stripped instead of leaking into the output; validators will still
+/// reject the keyword, so this only keeps the damage contained.
+fn resolve_malformed_composition(
+    value: &Value,
+    options: &ResolveOptions,
+    path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
+) -> Result<Value, ResolveError> {
+    warn!(
+        "{}: composition keyword must be an array of schemas, found {}",
+        path,
+        json_type_name(value)
+    );
+    if value.is_object() {
+        resolve_value(value, options, path, parent, explain)
+    } else {
+        Ok(value.clone())
+    }
+}
+
 /// allOf-specific resolution with cross-branch annotation propagation.
 ///
 /// Three-phase approach:
@@ -477,9 +1297,11 @@ fn resolve_allof(
     value: &Value,
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
+    explain: &mut Explain,
 ) -> Result<Value, ResolveError> {
     let Some(arr) = value.as_array() else {
-        return Ok(value.clone());
+        return resolve_malformed_composition(value, options, path, parent, explain);
     };
 
     let ann_key = options.direction.annotation_key();
@@ -490,11 +1312,11 @@ fn resolve_allof(
     for (i, item) in arr.iter().enumerate() {
         let item_path = format!("{}/{}", path, i);
         let item = if !merged.is_empty() {
-            inject_annotations(item, &merged, ann_key, options, &item_path)?
+            inject_annotations(item, &merged, ann_key, options, &item_path, parent)?
         } else {
             item.clone()
         };
-        let resolved = resolve_value(&item, options, &item_path)?;
+        let resolved = resolve_value(&item, options, &item_path, parent, explain)?;
         result.push(resolved);
     }
 
@@ -541,6 +1363,7 @@ fn inject_annotations(
     ann_key: &str,
     options: &ResolveOptions,
     path: &str,
+    parent: Visibility,
 ) -> Result<Value, ResolveError> {
     let mut branch = branch.clone();
 
@@ -574,8 +1397,13 @@ fn inject_annotations(
                             ann,
                             &options.operation,
                             &format!("{}/properties/{}", path, name),
+                            options.boolean_shorthand,
                         )?;
-                        if matches!(vis, Visibility::Omit | Visibility::Optional) {
+                        let vis = inherit_from(vis, parent);
+                        if matches!(
+                            vis,
+                            Visibility::Omit | Visibility::Optional | Visibility::Deprecate
+                        ) {
                             return Err(ResolveError::MonotonicityViolation {
                                 path: format!("{}/properties/{}", path, name),
                                 field: name.clone(),
@@ -583,8 +1411,9 @@ fn inject_annotations(
                                 attempted: match vis {
                                     Visibility::Omit => "omit",
                                     Visibility::Optional => "optional",
+                                    Visibility::Deprecate => "deprecate",
                                     Visibility::Required => "required",
-                                    Visibility::Include => "include",
+                                    Visibility::Include | Visibility::Inherit => "include",
                                 }
                                 .into(),
                             });
@@ -640,7 +1469,7 @@ fn strip_annotations_recursive(value: &Value) -> Value {
         Value::Object(map) => {
             let mut result = Map::new();
             for (k, v) in map {
-                if !UCP_ANNOTATIONS.contains(&k.as_str()) {
+                if !UCP_ANNOTATIONS.contains(&k.as_str()) && k != UCP_DEFAULTS {
                     result.insert(k.clone(), strip_annotations_recursive(v));
                 }
             }
@@ -651,6 +1480,18 @@ fn strip_annotations_recursive(value: &Value) -> Value {
     }
 }
 
+/// Remove every `ucp_strict` key, wherever it sits.
+fn remove_strict_annotations(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.remove(UCP_STRICT);
+            map.values_mut().for_each(remove_strict_annotations);
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(remove_strict_annotations),
+        _ => {}
+    }
+}
+
 fn apply_transition_metadata(value: &mut Value, transition: &Option<SchemaTransitionInfo>) {
     if let (Value::Object(map), Some(info)) = (value, transition) {
         map.insert(
@@ -667,6 +1508,63 @@ fn apply_transition_metadata(value: &mut Value, transition: &Option<SchemaTransi
     }
 }
 
+/// Combine a property's visibilities across the operations being resolved
+/// (see [`ResolveOptions::also_operations`]).
+///
+/// Omitted only when every operation omits it. Otherwise, over the
+/// operations that keep it: with [`RequiredPolicy::All`] it is `Required` when
+/// every operation requires it, and `Include` (original `required` status)
+/// when each says `required` or `include`; with [`RequiredPolicy::Any`] one
+/// `required` makes it `Required` and one `include` makes it `Include`. All
+/// `deprecate` stays `Deprecate`; anything else is `Optional`. A single
+/// visibility is returned unchanged.
+pub fn combine_visibility(
+    visibilities: impl IntoIterator<Item = Visibility>,
+    policy: RequiredPolicy,
+) -> Visibility {
+    let visibilities: Vec<Visibility> = visibilities.into_iter().collect();
+    if let [single] = visibilities[..] {
+        return single;
+    }
+    let omitted = visibilities.contains(&Visibility::Omit);
+    let kept: Vec<Visibility> = visibilities
+        .into_iter()
+        .filter(|v| *v != Visibility::Omit)
+        .collect();
+    let all = |f: fn(&Visibility) -> bool| kept.iter().all(f);
+    let any = |v: Visibility| kept.contains(&v);
+
+    if kept.is_empty() {
+        return Visibility::Omit;
+    }
+    match policy {
+        RequiredPolicy::All if !omitted && all(|v| *v == Visibility::Required) => {
+            Visibility::Required
+        }
+        RequiredPolicy::Any if any(Visibility::Required) => Visibility::Required,
+        _ if all(|v| *v == Visibility::Deprecate) => Visibility::Deprecate,
+        RequiredPolicy::All
+            if !omitted && all(|v| matches!(v, Visibility::Required | Visibility::Include)) =>
+        {
+            Visibility::Include
+        }
+        RequiredPolicy::Any if any(Visibility::Include) => Visibility::Include,
+        _ => Visibility::Optional,
+    }
+}
+
+/// Replace `Inherit` with the enclosing property's visibility.
+///
+/// `parent` is itself already resolved, so chains of `"inherit"` collapse to
+/// the nearest concrete ancestor.
+fn inherit_from(visibility: Visibility, parent: Visibility) -> Visibility {
+    if visibility == Visibility::Inherit {
+        parent
+    } else {
+        visibility
+    }
+}
+
 fn parse_visibility_string(s: &str, path: &str) -> Result<Visibility, ResolveError> {
     Visibility::parse(s).ok_or_else(|| ResolveError::UnknownVisibility {
         path: path.to_string(),
@@ -691,6 +1589,22 @@ mod tests {
         assert_eq!(vis, Visibility::Omit);
     }
 
+    #[test]
+    fn visibility_of_without_path() {
+        let prop = json!({
+            "type": "string",
+            "ucp_request": { "create": "omit", "update": "required" }
+        });
+        assert_eq!(
+            visibility_of(&prop, Direction::Request, "Update").unwrap(),
+            Visibility::Required
+        );
+        assert_eq!(
+            visibility_of(&prop, Direction::Response, "create").unwrap(),
+            Visibility::Include
+        );
+    }
+
     #[test]
     fn get_visibility_shorthand_required() {
         let prop = json!({
@@ -788,6 +1702,56 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn get_visibility_boolean_rejected_by_default() {
+        let prop = json!({
+            "type": "string",
+            "ucp_request": false
+        });
+        let result = get_visibility(&prop, Direction::Request, "create", "/test");
+        assert!(matches!(
+            result,
+            Err(ResolveError::InvalidAnnotationType { actual, .. }) if actual == "boolean"
+        ));
+    }
+
+    #[test]
+    fn get_visibility_boolean_shorthand() {
+        let prop = json!({
+            "type": "string",
+            "ucp_request": false,
+            "ucp_response": { "create": true, "update": false }
+        });
+        let request = ResolveOptions::new(Direction::Request, "create").boolean_shorthand(true);
+        let (vis, _) = get_visibility_with_options(&prop, "create", "/test", &request).unwrap();
+        assert_eq!(vis, Visibility::Omit);
+
+        let response = ResolveOptions::new(Direction::Response, "create").boolean_shorthand(true);
+        let (vis, _) = get_visibility_with_options(&prop, "create", "/test", &response).unwrap();
+        assert_eq!(vis, Visibility::Include);
+        let (vis, _) = get_visibility_with_options(&prop, "update", "/test", &response).unwrap();
+        assert_eq!(vis, Visibility::Omit);
+    }
+
+    #[test]
+    fn combine_visibility_policies() {
+        use Visibility::*;
+        let all = |v: &[Visibility]| combine_visibility(v.to_vec(), RequiredPolicy::All);
+        let any = |v: &[Visibility]| combine_visibility(v.to_vec(), RequiredPolicy::Any);
+
+        assert_eq!(all(&[Omit, Omit]), Omit);
+        assert_eq!(all(&[Required, Required]), Required);
+        assert_eq!(all(&[Required, Include]), Include);
+        assert_eq!(all(&[Required, Omit]), Optional);
+        assert_eq!(all(&[Deprecate, Omit]), Deprecate);
+        assert_eq!(all(&[Include, Optional]), Optional);
+
+        assert_eq!(any(&[Required, Omit]), Required);
+        assert_eq!(any(&[Include, Optional]), Include);
+        assert_eq!(any(&[Optional, Deprecate]), Optional);
+        assert_eq!(any(&[Omit]), Omit);
+    }
+
     #[test]
     fn get_visibility_unknown_visibility_errors() {
         let prop = json!({
@@ -1227,12 +2191,80 @@ mod tests {
         assert_eq!(result["required"], json!([]));
     }
 
+    #[test]
+    fn resolve_omit_with_conditional_required_errors() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": { "type": "string", "ucp_request": { "create": "omit" } },
+                "kind": { "type": "string" }
+            },
+            "if": { "properties": { "kind": { "const": "order" } } },
+            "then": { "required": ["status"] }
+        });
+
+        // Left as written unless the caller opts in
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let resolved = resolve(&schema, &options).unwrap();
+        assert_eq!(resolved["then"]["required"], json!(["status"]));
+
+        let options = options.reject_dangling_required(true);
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::DanglingRequired { ref path, ref property }
+                if path == "/then/required" && property == "status"
+        ));
+
+        // Not omitted for update: no contradiction
+        let options =
+            ResolveOptions::new(Direction::Request, "update").reject_dangling_required(true);
+        assert!(resolve(&schema, &options).is_ok());
+    }
+
+    #[test]
+    fn resolve_omit_with_dependent_required_errors() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "card": { "type": "string" },
+                "billing": { "type": "object", "ucp_request": "omit" }
+            },
+            "dependentRequired": { "card": ["billing"] }
+        });
+        let options =
+            ResolveOptions::new(Direction::Request, "create").reject_dangling_required(true);
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::DanglingRequired { ref path, .. } if path == "/dependentRequired/card"
+        ));
+    }
+
+    #[test]
+    fn resolve_omit_redeclared_in_branch_is_not_dangling() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": { "type": "string", "ucp_request": "omit" }
+            },
+            "else": {
+                "properties": { "status": { "type": "string" } },
+                "required": ["status"]
+            }
+        });
+        let options =
+            ResolveOptions::new(Direction::Request, "create").reject_dangling_required(true);
+        assert!(resolve(&schema, &options).is_ok());
+    }
+
     // === Strip Annotations Tests ===
 
     #[test]
     fn strip_annotations_removes_all_ucp() {
         let schema = json!({
             "type": "object",
+            "ucp_strict": { "create": true },
             "properties": {
                 "id": {
                     "type": "string",
@@ -1245,5 +2277,65 @@ mod tests {
 
         assert!(result["properties"]["id"].get("ucp_request").is_none());
         assert!(result["properties"]["id"].get("ucp_response").is_none());
+        assert!(result.get("ucp_strict").is_none());
+    }
+
+    // === Single Property Tests ===
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn resolve_property_returns_subschema_or_none_when_omitted() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "buyer": {
+                    "type": "object",
+                    "ucp_request": { "create": "required", "update": "omit" },
+                    "properties": {
+                        "email": { "type": "string", "ucp_request": "required" }
+                    }
+                }
+            }
+        });
+        let email = "/properties/buyer/properties/email";
+
+        let create = ResolveOptions::new(Direction::Request, "create");
+        let resolved = resolve_property(&schema, email, &create).unwrap();
+        assert_eq!(resolved, Some(json!({ "type": "string" })));
+
+        // Omitted ancestor omits the property too
+        let update = ResolveOptions::new(Direction::Request, "update");
+        assert_eq!(resolve_property(&schema, email, &update).unwrap(), None);
+
+        // A pointer missing from the source schema is an error, not None
+        let typo = "/properties/buyer/properties/emial";
+        assert!(resolve_property(&schema, typo, &create).is_err());
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn resolve_property_after_omitted_branch() {
+        let schema = json!({
+            "oneOf": [
+                { "type": "string", "ucp_request": { "create": "omit" } },
+                { "type": "integer", "ucp_request": { "create": "omit" } },
+                {
+                    "type": "object",
+                    "properties": { "token": { "type": "string", "ucp_request": "required" } }
+                }
+            ]
+        });
+        let create = ResolveOptions::new(Direction::Request, "create");
+
+        // Earlier omitted branches don't shift the target branch
+        let token = resolve_property(&schema, "/oneOf/2/properties/token", &create).unwrap();
+        assert_eq!(token, Some(json!({ "type": "string" })));
+        let branch = resolve_property(&schema, "/oneOf/2/type", &create).unwrap();
+        assert_eq!(branch, Some(json!("object")));
+        // A pointer into an omitted branch reports it omitted
+        assert_eq!(
+            resolve_property(&schema, "/oneOf/1/type", &create).unwrap(),
+            None
+        );
     }
 }
@@ -0,0 +1,51 @@
+//! Internal logging macros.
+//!
+//! With the `tracing` feature enabled these forward to the `tracing` crate, so
+//! embedding services see schema loads, bundling steps, and annotation
+//! decisions through whatever subscriber they install. Without the feature they
+//! compile to nothing; the arguments are type-checked but never evaluated.
+
+// The core build (no `full`) only traces annotation decisions.
+#![cfg_attr(not(feature = "full"), allow(unused_macros))]
+
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+    ($($arg:tt)*) => { ::tracing::debug!($($arg)*) };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! trace {
+    ($($arg:tt)*) => { ::tracing::trace!($($arg)*) };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! warn {
+    ($($arg:tt)*) => { ::tracing::warn!($($arg)*) };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = ::std::format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = ::std::format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = ::std::format_args!($($arg)*);
+        }
+    };
+}
@@ -1,7 +1,9 @@
 //! Core types for UCP schema resolution.
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+
+use crate::error::ResolveError;
 
 /// Schema transition: from/to are visibility values (omit, optional, required).
 /// During the transition period the field is always the `from` visibility.
@@ -12,12 +14,117 @@ pub struct SchemaTransitionInfo {
     pub description: String,
 }
 
+/// A property (or `anyOf`/`oneOf` branch) that resolution dropped, and the
+/// annotation responsible.
+///
+/// Reported by [`resolve_explained`](crate::resolve_explained).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Omission {
+    /// JSON Pointer to the property or branch in the source schema.
+    pub path: String,
+    /// JSON Pointer to the annotation that decided it: the property's own
+    /// `ucp_request`/`ucp_response`, or the enclosing object's `ucp_defaults`.
+    pub annotation: String,
+    /// How that annotation applied to the resolved operation.
+    pub matched: OmitMatch,
+}
+
+/// How an annotation came to omit a property for the resolved operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OmitMatch {
+    /// The annotation's entry for the operation: `{ "create": "omit" }`.
+    Operation(String),
+    /// A form covering every operation: `"omit"`, `false` with boolean
+    /// shorthand, or a top-level `transition`.
+    Shorthand,
+    /// The annotation is `"inherit"` and the enclosing property is omitted.
+    Inherited,
+}
+
+/// Counts of the annotation decisions made by one resolve.
+///
+/// Reported by [`resolve_with_stats`](crate::resolve_with_stats). Properties
+/// are counted where the walk reaches them, so nothing below an omitted
+/// property is counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ResolveStats {
+    /// Properties dropped from the output (planned additions kept by
+    /// `include_future` included).
+    pub omitted: usize,
+    /// Properties made required.
+    pub required: usize,
+    /// Properties made optional, deprecated ones included.
+    pub optional: usize,
+    /// Properties kept with their declared required status.
+    pub included: usize,
+    /// `ucp_*` annotation keywords removed from visited schema objects.
+    pub annotations_stripped: usize,
+    /// Every property visited: the sum of the four visibility counts.
+    pub properties_total: usize,
+}
+
 /// Valid UCP operations for annotation object form.
+///
+/// Keys outside this list are reported by the linter (W003) but still honoured
+/// by the resolver.
 pub const VALID_OPERATIONS: &[&str] = &["create", "update", "complete", "read"];
 
-/// UCP annotation keys.
+/// UCP annotation keys, one per [`Direction`] (see [`Direction::annotation_key`]).
 pub const UCP_ANNOTATIONS: &[&str] = &["ucp_request", "ucp_response"];
 
+/// Object-level key holding default annotations for that object's properties,
+/// e.g. `"ucp_defaults": { "ucp_request": { "read": "omit" } }`.
+pub const UCP_DEFAULTS: &str = "ucp_defaults";
+
+/// Object-level key overriding `ResolveOptions::strict` per operation for
+/// that object and everything nested in it, e.g.
+/// `"ucp_strict": { "create": true, "update": false }` or `"ucp_strict": true`.
+pub const UCP_STRICT: &str = "ucp_strict";
+
+/// Schema keywords a per-operation annotation object may set for that
+/// operation, e.g. `"ucp_request": { "create": { "pattern": "^[A-Z0-9]{8}$" } }`.
+/// Any other key in such an object, besides `visibility` and `min_items`, is
+/// rejected.
+pub const UCP_OVERRIDABLE_KEYWORDS: &[&str] = &[
+    "minLength",
+    "maxLength",
+    "pattern",
+    "format",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "maxItems",
+    "enum",
+    "propertyNames",
+];
+
+/// What an overridable keyword's value must be, if `value` isn't that.
+///
+/// Lengths and item counts are non-negative integers, bounds are numbers,
+/// `multipleOf` is positive, `pattern`/`format` are strings, `enum` is an
+/// array, and `propertyNames` is a schema.
+pub(crate) fn override_value_error(keyword: &str, value: &Value) -> Option<&'static str> {
+    let (valid, expected) = match keyword {
+        "minLength" | "maxLength" | "maxItems" => {
+            (value.as_u64().is_some(), "a non-negative integer")
+        }
+        "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => {
+            (value.is_number(), "a number")
+        }
+        "multipleOf" => (value.as_f64().is_some_and(|n| n > 0.0), "a positive number"),
+        "pattern" | "format" => (value.is_string(), "a string"),
+        "enum" => (value.is_array(), "an array"),
+        "propertyNames" => (
+            matches!(value, Value::Object(_) | Value::Bool(_)),
+            "a schema",
+        ),
+        _ => (true, ""),
+    };
+    (!valid).then_some(expected)
+}
+
 /// Returns the JSON type name for error messages.
 pub fn json_type_name(value: &Value) -> &'static str {
     match value {
@@ -75,6 +182,7 @@ impl Direction {
 ///
 /// Determines how a field is transformed in the output schema.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
 pub enum Visibility {
     /// No transformation - keep field as-is with original required status.
     #[default]
@@ -85,6 +193,13 @@ pub enum Visibility {
     Required,
     /// Keep field but remove from required array.
     Optional,
+    /// Keep field, remove from required array, and mark it `deprecated: true`
+    /// (a softer phase-out than `Omit`).
+    Deprecate,
+    /// Take the effective visibility of the nearest enclosing property for
+    /// the same direction and operation (`Include` when there is none).
+    /// Resolution replaces it before applying it, so it never reaches output.
+    Inherit,
 }
 
 impl Visibility {
@@ -96,15 +211,27 @@ impl Visibility {
             "omit" => Some(Visibility::Omit),
             "required" => Some(Visibility::Required),
             "optional" => Some(Visibility::Optional),
+            "deprecate" => Some(Visibility::Deprecate),
+            "inherit" => Some(Visibility::Inherit),
             _ => None,
         }
     }
 }
 
+/// Whether a per-operation annotation object is the constraint form
+/// (`visibility`, `min_items`, or an overridable keyword) rather than a
+/// schema transition.
+pub(crate) fn is_constraint_object(obj: &Map<String, Value>) -> bool {
+    obj.keys().any(|k| {
+        k == "visibility" || k == "min_items" || UCP_OVERRIDABLE_KEYWORDS.contains(&k.as_str())
+    })
+}
+
 /// Returns true if (from, to) is a valid schema transition: both are visibility
-/// values (omit, optional, required) and from != to.
+/// values (omit, optional, required, deprecate) and from != to.
 pub fn is_valid_schema_transition(from: &str, to: &str) -> bool {
-    from != to && Visibility::parse(from).is_some() && Visibility::parse(to).is_some()
+    let concrete = |s: &str| Visibility::parse(s).is_some_and(|v| v != Visibility::Inherit);
+    from != to && concrete(from) && concrete(to)
 }
 
 // ---------------------------------------------------------------------------
@@ -246,14 +373,56 @@ impl Requires {
     }
 }
 
+/// How `required` combines when resolving for several operations at once
+/// (see [`ResolveOptions::also_operations`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequiredPolicy {
+    /// Required only when required for every operation, so the schema
+    /// accepts any payload that is valid for one of them.
+    #[default]
+    All,
+    /// Required when required for any operation.
+    Any,
+}
+
+impl std::str::FromStr for RequiredPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(RequiredPolicy::All),
+            "any" => Ok(RequiredPolicy::Any),
+            other => Err(format!(
+                "unknown required policy '{}': expected all or any",
+                other
+            )),
+        }
+    }
+}
+
 /// Options for schema resolution.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ResolveOptions {
     /// Whether resolving for request or response.
     pub direction: Direction,
     /// The operation to resolve for (e.g., "create", "update").
     /// Will be normalized to lowercase.
     pub operation: String,
+    /// Further operations resolved together with `operation` into one
+    /// superset schema (lowercase). A property is kept if any operation keeps
+    /// it and omitted only if every operation omits it; whether it is
+    /// required follows `required_policy`. A property whose operations
+    /// disagree otherwise becomes optional, unless all of them say
+    /// `deprecate` (deprecated) or only `required`/`include` (original
+    /// `required` status kept). Other per-operation constraints apply only
+    /// as far as every operation agrees: `ucp_strict` closes an object only
+    /// if all of them do, `min_items` and bound overrides take the loosest
+    /// value, and any other keyword override needs the same value everywhere.
+    /// Empty by default.
+    pub also_operations: Vec<String>,
+    /// How `required` combines across `operation` and `also_operations`.
+    pub required_policy: RequiredPolicy,
     /// When true, sets `additionalProperties: false` on all object schemas
     /// to reject unknown fields. Defaults to false to respect schema extensibility.
     pub strict: bool,
@@ -271,6 +440,28 @@ pub struct ResolveOptions {
     /// (`cart` → `checkout`). When set, selection ignores the container check
     /// so it works on schemas that also have a root body.
     pub def_name: Option<String>,
+    /// When true, accepts boolean annotation values as shorthand: `false`
+    /// means omit and `true` means include. Off by default so vocabularies
+    /// that only allow the string forms keep rejecting booleans.
+    pub boolean_shorthand: bool,
+    /// When set, every operation key in a per-operation annotation must be in
+    /// this list (lowercase); any other key fails resolution with
+    /// `UnknownOperation`, whichever operation is being resolved.
+    pub allowed_operations: Option<Vec<String>>,
+    /// When true, a `required` array left empty after resolution is removed
+    /// instead of kept as `[]`. Off by default: an object that declared
+    /// `required` keeps the key even when every entry was omitted.
+    pub drop_empty_required: bool,
+    /// When true, a `then`/`else`, `dependentRequired`, or `dependentSchemas`
+    /// `required` entry naming an omitted property fails resolution with
+    /// `DanglingRequired`. Off by default: such entries are left as written,
+    /// since the branch may never be hit for this operation's payloads.
+    pub reject_dangling_required: bool,
+    /// When true, `type` arrays are deduplicated and sorted alphabetically
+    /// with `"null"` last, and a single remaining type becomes a plain string
+    /// (`["string"]` → `"string"`). Off by default so output keeps the
+    /// schema's own spelling.
+    pub normalize_types: bool,
 }
 
 impl ResolveOptions {
@@ -283,9 +474,16 @@ impl ResolveOptions {
         Self {
             direction,
             operation: operation.into().to_lowercase(),
+            also_operations: Vec::new(),
+            required_policy: RequiredPolicy::All,
             strict: false,
             include_future: false,
             def_name: None,
+            boolean_shorthand: false,
+            allowed_operations: None,
+            drop_empty_required: false,
+            reject_dangling_required: false,
+            normalize_types: false,
         }
     }
 
@@ -295,6 +493,31 @@ impl ResolveOptions {
         self
     }
 
+    /// Resolve for these operations too (see [`Self::also_operations`]).
+    /// Names are normalized to lowercase; repeats of `operation` are dropped.
+    pub fn also_operations(mut self, operations: Vec<String>) -> Self {
+        let mut also = Vec::new();
+        for op in operations.into_iter().map(|op| op.to_lowercase()) {
+            if op != self.operation && !also.contains(&op) {
+                also.push(op);
+            }
+        }
+        self.also_operations = also;
+        self
+    }
+
+    /// See [`Self::required_policy`].
+    pub fn required_policy(mut self, required_policy: RequiredPolicy) -> Self {
+        self.required_policy = required_policy;
+        self
+    }
+
+    /// `operation` followed by `also_operations`.
+    pub fn operations(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.operation.as_str())
+            .chain(self.also_operations.iter().map(String::as_str))
+    }
+
     /// Include future fields (omit-visibility with non-omit transition target).
     pub fn include_future(mut self, include_future: bool) -> Self {
         self.include_future = include_future;
@@ -307,6 +530,180 @@ impl ResolveOptions {
         self.def_name = def_name;
         self
     }
+
+    /// Accept `true`/`false` as annotation shorthand (see [`Self::boolean_shorthand`]).
+    pub fn boolean_shorthand(mut self, boolean_shorthand: bool) -> Self {
+        self.boolean_shorthand = boolean_shorthand;
+        self
+    }
+
+    /// Reject annotation operation keys outside `allowed` (see
+    /// [`Self::allowed_operations`]). Names are normalized to lowercase.
+    pub fn validate_operations(mut self, allowed: Vec<String>) -> Self {
+        self.allowed_operations = Some(allowed.into_iter().map(|op| op.to_lowercase()).collect());
+        self
+    }
+
+    /// Remove emptied `required` arrays (see [`Self::drop_empty_required`]).
+    pub fn drop_empty_required(mut self, drop_empty_required: bool) -> Self {
+        self.drop_empty_required = drop_empty_required;
+        self
+    }
+
+    /// Reject conditional `required` entries naming omitted properties (see
+    /// [`Self::reject_dangling_required`]).
+    pub fn reject_dangling_required(mut self, reject_dangling_required: bool) -> Self {
+        self.reject_dangling_required = reject_dangling_required;
+        self
+    }
+
+    /// Canonicalize `type` arrays (see [`Self::normalize_types`]).
+    pub fn normalize_types(mut self, normalize_types: bool) -> Self {
+        self.normalize_types = normalize_types;
+        self
+    }
+
+    /// Start a [`ResolveOptionsBuilder`].
+    pub fn builder() -> ResolveOptionsBuilder {
+        ResolveOptionsBuilder::default()
+    }
+}
+
+/// Builder for [`ResolveOptions`].
+///
+/// `direction` and `operation` are required; everything else defaults as in
+/// [`ResolveOptions::new`]. [`build`](Self::build) goes through `new` and the
+/// setters there, so normalization (lowercase operation names) stays in one
+/// place.
+///
+/// ```
+/// use ucp_schema::{Direction, ResolveOptions};
+///
+/// let options = ResolveOptions::builder()
+///     .direction(Direction::Request)
+///     .operation("Create")
+///     .strict(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(options.operation, "create");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptionsBuilder {
+    direction: Option<Direction>,
+    operation: Option<String>,
+    also_operations: Vec<String>,
+    required_policy: RequiredPolicy,
+    strict: bool,
+    include_future: bool,
+    def_name: Option<String>,
+    boolean_shorthand: bool,
+    allowed_operations: Option<Vec<String>>,
+    drop_empty_required: bool,
+    reject_dangling_required: bool,
+    normalize_types: bool,
+}
+
+impl ResolveOptionsBuilder {
+    /// Direction to resolve for (required).
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Operation to resolve for (required; normalized to lowercase).
+    pub fn operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    /// See [`ResolveOptions::also_operations`].
+    pub fn also_operations(mut self, operations: Vec<String>) -> Self {
+        self.also_operations = operations;
+        self
+    }
+
+    /// See [`ResolveOptions::required_policy`].
+    pub fn required_policy(mut self, required_policy: RequiredPolicy) -> Self {
+        self.required_policy = required_policy;
+        self
+    }
+
+    /// See [`ResolveOptions::strict`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// See [`ResolveOptions::include_future`].
+    pub fn include_future(mut self, include_future: bool) -> Self {
+        self.include_future = include_future;
+        self
+    }
+
+    /// See [`ResolveOptions::def_name`].
+    pub fn def_name(mut self, def_name: Option<String>) -> Self {
+        self.def_name = def_name;
+        self
+    }
+
+    /// See [`ResolveOptions::boolean_shorthand`].
+    pub fn boolean_shorthand(mut self, boolean_shorthand: bool) -> Self {
+        self.boolean_shorthand = boolean_shorthand;
+        self
+    }
+
+    /// See [`ResolveOptions::validate_operations`].
+    pub fn validate_operations(mut self, allowed: Vec<String>) -> Self {
+        self.allowed_operations = Some(allowed);
+        self
+    }
+
+    /// See [`ResolveOptions::drop_empty_required`].
+    pub fn drop_empty_required(mut self, drop_empty_required: bool) -> Self {
+        self.drop_empty_required = drop_empty_required;
+        self
+    }
+
+    /// See [`ResolveOptions::reject_dangling_required`].
+    pub fn reject_dangling_required(mut self, reject_dangling_required: bool) -> Self {
+        self.reject_dangling_required = reject_dangling_required;
+        self
+    }
+
+    /// See [`ResolveOptions::normalize_types`].
+    pub fn normalize_types(mut self, normalize_types: bool) -> Self {
+        self.normalize_types = normalize_types;
+        self
+    }
+
+    /// Assemble the options.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IncompleteOptions` if `direction` or `operation` was not set.
+    pub fn build(self) -> Result<ResolveOptions, ResolveError> {
+        let direction = self.direction.ok_or(ResolveError::IncompleteOptions {
+            option: "direction",
+        })?;
+        let operation = self.operation.ok_or(ResolveError::IncompleteOptions {
+            option: "operation",
+        })?;
+
+        let mut options = ResolveOptions::new(direction, operation)
+            .also_operations(self.also_operations)
+            .required_policy(self.required_policy)
+            .strict(self.strict)
+            .include_future(self.include_future)
+            .def_name(self.def_name)
+            .boolean_shorthand(self.boolean_shorthand)
+            .drop_empty_required(self.drop_empty_required)
+            .reject_dangling_required(self.reject_dangling_required)
+            .normalize_types(self.normalize_types);
+        if let Some(allowed) = self.allowed_operations {
+            options = options.validate_operations(allowed);
+        }
+        Ok(options)
+    }
 }
 
 #[cfg(test)]
@@ -317,6 +714,51 @@ mod tests {
     fn direction_annotation_key() {
         assert_eq!(Direction::Request.annotation_key(), "ucp_request");
         assert_eq!(Direction::Response.annotation_key(), "ucp_response");
+        assert_eq!(
+            UCP_ANNOTATIONS,
+            [
+                Direction::Request.annotation_key(),
+                Direction::Response.annotation_key()
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_options_builder_matches_setters() {
+        let built = ResolveOptions::builder()
+            .direction(Direction::Response)
+            .operation("Update")
+            .include_future(true)
+            .def_name(Some("cart".to_string()))
+            .validate_operations(vec!["Create".to_string(), "update".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(built.direction, Direction::Response);
+        assert_eq!(built.operation, "update");
+        assert!(built.include_future && !built.strict && !built.boolean_shorthand);
+        assert_eq!(built.def_name.as_deref(), Some("cart"));
+        assert_eq!(
+            built.allowed_operations,
+            Some(vec!["create".to_string(), "update".to_string()])
+        );
+
+        let multi = ResolveOptions::builder()
+            .direction(Direction::Request)
+            .operation("create")
+            .also_operations(vec!["Update".to_string(), "create".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(multi.operations().collect::<Vec<_>>(), ["create", "update"]);
+        assert_eq!(multi.required_policy, RequiredPolicy::All);
+
+        let missing = ResolveOptions::builder()
+            .direction(Direction::Request)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "resolve options are missing required `operation`"
+        );
     }
 
     #[test]
@@ -324,6 +766,8 @@ mod tests {
         assert_eq!(Visibility::parse("omit"), Some(Visibility::Omit));
         assert_eq!(Visibility::parse("required"), Some(Visibility::Required));
         assert_eq!(Visibility::parse("optional"), Some(Visibility::Optional));
+        assert_eq!(Visibility::parse("deprecate"), Some(Visibility::Deprecate));
+        assert_eq!(Visibility::parse("inherit"), Some(Visibility::Inherit));
     }
 
     #[test]
@@ -353,6 +797,8 @@ mod tests {
         // Disbarred: invalid visibility value
         assert!(!super::is_valid_schema_transition("readonly", "omit"));
         assert!(!super::is_valid_schema_transition("required", "invalid"));
+        // Disbarred: inherit is not a concrete visibility
+        assert!(!super::is_valid_schema_transition("inherit", "omit"));
     }
 
     #[test]
@@ -1,11 +1,20 @@
 //! Payload validation against resolved schemas.
 
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use serde_json::{Map, Value};
 
-use crate::compose::is_container_schema;
-use crate::error::{ResolveError, SchemaError, ValidateError};
+use crate::compose::{
+    compose_schema, detect_direction_at, extract_capabilities, extract_jsonrpc_payload,
+    is_container_schema, map_schema_url, payload_metadata_at, Capability, DetectedDirection,
+    PayloadMetadata, SchemaBaseConfig,
+};
+use crate::error::{ComposeError, ResolveError, SchemaError, ValidateError};
+use crate::loader::{escape_pointer_token, load_schema, navigate_fragment};
 use crate::resolver::resolve;
-use crate::types::ResolveOptions;
+use crate::types::{Direction, ResolveOptions};
 
 /// Validate a payload against a UCP schema.
 ///
@@ -32,6 +41,265 @@ pub fn validate(
     validate_against_schema(&target, payload)
 }
 
+/// How [`SchemaError::path`] locates the invalid value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathFormat {
+    /// JSON Pointer (RFC 6901), e.g. `/buyer/email` or `/items/0`.
+    #[default]
+    Pointer,
+    /// JSONPath (RFC 9535) normalized path, e.g. `$['buyer']['email']` or
+    /// `$['items'][0]`.
+    JsonPath,
+}
+
+/// Options for presenting validation results.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ValidateOptions {
+    /// Notation for error paths. Defaults to JSON Pointer.
+    pub path_format: PathFormat,
+}
+
+impl ValidateOptions {
+    /// Default options: JSON Pointer paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the notation for error paths.
+    pub fn path_format(mut self, path_format: PathFormat) -> Self {
+        self.path_format = path_format;
+        self
+    }
+
+    /// Rewrite the error paths of a failed validation of `payload`.
+    fn apply(&self, err: ValidateError, payload: &Value) -> ValidateError {
+        match (self.path_format, err) {
+            (PathFormat::JsonPath, ValidateError::Invalid { mut errors }) => {
+                for error in &mut errors {
+                    error.path = json_path(&error.path, payload);
+                }
+                ValidateError::Invalid { errors }
+            }
+            (_, err) => err,
+        }
+    }
+}
+
+/// Like [`validate`], with error paths presented per `validate_options`.
+///
+/// # Errors
+///
+/// Same as [`validate`].
+pub fn validate_with_options(
+    schema: &Value,
+    payload: &Value,
+    options: &ResolveOptions,
+    validate_options: &ValidateOptions,
+) -> Result<(), ValidateError> {
+    validate(schema, payload, options).map_err(|e| validate_options.apply(e, payload))
+}
+
+/// Convert an instance JSON Pointer into an RFC 9535 normalized JSONPath.
+///
+/// A segment indexes an array when the payload holds an array there, so
+/// `/items/0` becomes `$['items'][0]` while an object key `"0"` stays
+/// `$['0']`. Names are always single-quoted, escaped per the RFC's
+/// normalized form.
+fn json_path(pointer: &str, payload: &Value) -> String {
+    let mut path = String::from("$");
+    let mut node = Some(payload);
+    for raw in pointer.split('/').skip(1) {
+        let segment = raw.replace("~1", "/").replace("~0", "~");
+        if let (Some(Value::Array(items)), Ok(index)) = (node, segment.parse::<usize>()) {
+            path.push_str(&format!("[{}]", index));
+            node = items.get(index);
+            continue;
+        }
+
+        path.push_str("['");
+        for c in segment.chars() {
+            match c {
+                '\'' => path.push_str("\\'"),
+                '\\' => path.push_str("\\\\"),
+                '\u{8}' => path.push_str("\\b"),
+                '\u{c}' => path.push_str("\\f"),
+                '\n' => path.push_str("\\n"),
+                '\r' => path.push_str("\\r"),
+                '\t' => path.push_str("\\t"),
+                c if c < '\u{20}' => path.push_str(&format!("\\u{:04x}", c as u32)),
+                c => path.push(c),
+            }
+        }
+        path.push_str("']");
+        node = node.and_then(|n| n.get(&segment));
+    }
+    path
+}
+
+/// Validate a payload against several schemas at once.
+///
+/// Each schema is resolved and its operation shape selected independently
+/// (direction and operation from `options` apply to all), then the results
+/// are combined under `allOf`. Layers are conjunctive: the payload must
+/// satisfy every one, so an overlay can only add constraints, and layers
+/// that contradict each other make every payload invalid. Each layer without
+/// an `$id` gets a synthetic one so its internal `#/...` refs keep resolving
+/// within that layer. A single schema behaves exactly like [`validate`].
+///
+/// # Errors
+///
+/// Same as [`validate`]; the first layer that fails to resolve is reported.
+pub fn validate_layered(
+    schemas: &[Value],
+    payload: &Value,
+    options: &ResolveOptions,
+) -> Result<(), ValidateError> {
+    if let [schema] = schemas {
+        return validate(schema, payload, options);
+    }
+    validate_against_schema(&resolve_layered(schemas, options)?, payload)
+}
+
+/// The schema [`validate_layered`] validates against: each layer resolved and
+/// its operation shape selected, combined under `allOf` (a single schema is
+/// returned as is).
+///
+/// Resolve once with this and pass the result to [`validate_against_schema`]
+/// to validate many payloads for the same direction and operation.
+///
+/// # Errors
+///
+/// Returns `ResolveError` for the first layer that fails to resolve or has no
+/// shape for the operation.
+pub fn resolve_layered(schemas: &[Value], options: &ResolveOptions) -> Result<Value, ResolveError> {
+    if let [schema] = schemas {
+        return select_operation_schema(&resolve(schema, options)?, options);
+    }
+
+    let mut layers = Vec::with_capacity(schemas.len());
+    for (i, schema) in schemas.iter().enumerate() {
+        let resolved = resolve(schema, options)?;
+        let mut target = select_operation_schema(&resolved, options)?;
+        if let Some(obj) = target.as_object_mut() {
+            if !obj.contains_key("$id") {
+                obj.insert(
+                    "$id".to_string(),
+                    Value::String(format!("urn:ucp-schema:layer:{}", i)),
+                );
+            }
+        }
+        layers.push(target);
+    }
+
+    Ok(serde_json::json!({ "allOf": layers }))
+}
+
+/// Validate a payload and return it with schema defaults filled in.
+///
+/// Unlike [`validate`], which only checks the payload, this produces a new
+/// "effective" payload for downstream processing: once the input validates,
+/// every property absent from an object whose schema declares a `default` is
+/// inserted with that default. Defaults are read from the resolved schema, so
+/// fields omitted for this operation are never filled. Nested objects, array
+/// `items`, `allOf` branches, and local `$ref`s are followed. The input is not
+/// modified, and present values are returned unchanged (no type coercion).
+///
+/// # Errors
+///
+/// Same as [`validate`]; no payload is returned when validation fails.
+pub fn validate_and_normalize(
+    schema: &Value,
+    payload: &Value,
+    options: &ResolveOptions,
+) -> Result<Value, ValidateError> {
+    let resolved = resolve(schema, options)?;
+    let target = select_operation_schema(&resolved, options)?;
+    validate_against_schema(&target, payload)?;
+
+    let mut normalized = payload.clone();
+    apply_defaults(&target, &mut normalized, &target, &mut HashSet::new());
+    Ok(normalized)
+}
+
+/// Fill absent properties of `payload` from `default`s declared in `schema`.
+///
+/// `seen` guards against `$ref` cycles at one payload level; descending into
+/// a child starts a fresh set because the payload itself is finite.
+fn apply_defaults(schema: &Value, payload: &mut Value, root: &Value, seen: &mut HashSet<String>) {
+    let Some(map) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(target) = follow_local_ref(map, root, seen) {
+        apply_defaults(&target, payload, root, seen);
+    }
+    if let Some(Value::Array(branches)) = map.get("allOf") {
+        for branch in branches {
+            apply_defaults(branch, payload, root, seen);
+        }
+    }
+
+    match payload {
+        Value::Object(obj) => {
+            let Some(Value::Object(props)) = map.get("properties") else {
+                return;
+            };
+            for (name, prop_schema) in props {
+                if !obj.contains_key(name) {
+                    if let Some(default) = declared_default(prop_schema, root) {
+                        obj.insert(name.clone(), default);
+                    }
+                }
+                if let Some(child) = obj.get_mut(name) {
+                    apply_defaults(prop_schema, child, root, &mut HashSet::new());
+                }
+            }
+        }
+        Value::Array(items) => match map.get("items") {
+            // Draft 7 tuple form: positional schemas
+            Some(Value::Array(tuple)) => {
+                for (item_schema, item) in tuple.iter().zip(items.iter_mut()) {
+                    apply_defaults(item_schema, item, root, &mut HashSet::new());
+                }
+            }
+            Some(item_schema) => {
+                for item in items {
+                    apply_defaults(item_schema, item, root, &mut HashSet::new());
+                }
+            }
+            None => {}
+        },
+        _ => {}
+    }
+}
+
+/// The `default` of a property schema, looking through local `$ref`s.
+fn declared_default(schema: &Value, root: &Value) -> Option<Value> {
+    let mut seen = HashSet::new();
+    let mut current = schema.clone();
+    loop {
+        let map = current.as_object()?;
+        if let Some(default) = map.get("default") {
+            return Some(default.clone());
+        }
+        current = follow_local_ref(map, root, &mut seen)?;
+    }
+}
+
+/// Target of a not-yet-visited `#/...` `$ref` in `map`, if any.
+fn follow_local_ref(
+    map: &Map<String, Value>,
+    root: &Value,
+    seen: &mut HashSet<String>,
+) -> Option<Value> {
+    let reference = map.get("$ref")?.as_str()?;
+    if !reference.starts_with('#') || !seen.insert(reference.to_string()) {
+        return None;
+    }
+    navigate_fragment(root, reference).ok()
+}
+
 /// Resolve a (possibly container-shaped) schema to its validation target.
 ///
 /// Selection has two modes:
@@ -117,29 +385,734 @@ fn select_def(schema: &Value, name: &str, mode: SelectMode) -> Result<Value, Res
     Ok(Value::Object(wrapper))
 }
 
+/// Pick the schema declaring `$id: id` out of a multi-schema bundle.
+///
+/// Bundle members are the root and the (recursively nested) `$defs` /
+/// `definitions` entries; ids compare exactly, ignoring an empty trailing `#`.
+/// A root match returns the document unchanged. A nested match is rooted via
+/// a `$ref` wrapper that keeps the root's `$schema`, `$id`, `$defs`, and
+/// `definitions` in scope, so references between members still resolve.
+///
+/// # Errors
+///
+/// `SchemaIdNotFound` (listing the ids present) if nothing matches;
+/// `AmbiguousSchemaId` (listing the JSON pointers) if several members match.
+pub fn select_schema_by_id(document: &Value, id: &str) -> Result<Value, ResolveError> {
+    let wanted = id.strip_suffix('#').unwrap_or(id);
+    let mut members = Vec::new();
+    collect_bundle_ids(document, String::new(), &mut members);
+
+    let matches: Vec<&str> = members
+        .iter()
+        .filter(|(_, member)| member.strip_suffix('#').unwrap_or(member) == wanted)
+        .map(|(pointer, _)| pointer.as_str())
+        .collect();
+    let pointer = match matches.as_slice() {
+        [] => {
+            return Err(ResolveError::SchemaIdNotFound {
+                id: id.to_string(),
+                available: members
+                    .iter()
+                    .map(|(_, member)| member.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+        }
+        [pointer] => *pointer,
+        _ => {
+            return Err(ResolveError::AmbiguousSchemaId {
+                id: id.to_string(),
+                paths: matches
+                    .iter()
+                    .map(|p| if p.is_empty() { "/" } else { p })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+        }
+    };
+    if pointer.is_empty() {
+        return Ok(document.clone());
+    }
+
+    let mut wrapper = Map::new();
+    for key in ["$schema", "$id"] {
+        if let Some(value) = document.get(key) {
+            wrapper.insert(key.to_string(), value.clone());
+        }
+    }
+    wrapper.insert("$ref".to_string(), Value::String(format!("#{}", pointer)));
+    for key in ["$defs", "definitions"] {
+        if let Some(value) = document.get(key) {
+            wrapper.insert(key.to_string(), value.clone());
+        }
+    }
+    Ok(Value::Object(wrapper))
+}
+
+/// `(pointer, $id)` for the root and every nested `$defs`/`definitions` entry.
+fn collect_bundle_ids(schema: &Value, pointer: String, out: &mut Vec<(String, String)>) {
+    let Some(obj) = schema.as_object() else {
+        return;
+    };
+    if let Some(id) = obj.get("$id").and_then(Value::as_str) {
+        out.push((pointer.clone(), id.to_string()));
+    }
+    for key in ["$defs", "definitions"] {
+        if let Some(Value::Object(defs)) = obj.get(key) {
+            for (name, def) in defs {
+                let segment = escape_pointer_token(name);
+                collect_bundle_ids(def, format!("{}/{}/{}", pointer, key, segment), out);
+            }
+        }
+    }
+}
+
+/// Validate a batch of self-describing payloads.
+///
+/// Payloads are grouped by their capability declaration (`ucp.capabilities`
+/// for responses, `meta.profile` for JSONRPC requests, or wherever
+/// `schema_base.payload_pointers` point). Each distinct
+/// declaration is composed, resolved, and compiled once, and every payload in
+/// the group is validated against that compiled schema. JSONRPC requests are
+/// validated at their nested capability payload, as in the CLI.
+///
+/// The direction comes from each payload's declaration: inline capabilities
+/// validate as a response, a `meta.profile` request as a request, so one batch
+/// may mix both. `options.direction` is ignored; its operation, policies, and
+/// other settings apply to every group.
+///
+/// Returns one result per payload, in input order. A payload that is not
+/// self-describing yields `ValidateError::Compose(NotSelfDescribing)`; when a
+/// group's schema cannot be built, every member reports that one error as
+/// `ValidateError::Group`.
+pub fn validate_many(
+    payloads: &[Value],
+    schema_base: &SchemaBaseConfig,
+    options: &ResolveOptions,
+) -> Vec<Result<(), ValidateError>> {
+    // Group by declaration, preserving first-seen order.
+    let mut groups: Vec<(Value, Direction, Vec<usize>)> = Vec::new();
+    let mut results: Vec<Option<Result<(), ValidateError>>> =
+        payloads.iter().map(|_| None).collect();
+    for (i, payload) in payloads.iter().enumerate() {
+        let Some((key, direction)) = declaration_key(payload, schema_base) else {
+            results[i] = Some(Err(ComposeError::NotSelfDescribing.into()));
+            continue;
+        };
+        match groups.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, members)) => members.push(i),
+            None => groups.push((key, direction, vec![i])),
+        }
+    }
+
+    for (_, direction, members) in groups {
+        debug!(
+            "validating {} payloads against one composed schema ({:?})",
+            members.len(),
+            direction
+        );
+        let group_options = ResolveOptions {
+            direction,
+            ..options.clone()
+        };
+        match prepare_batch_schema(&payloads[members[0]], schema_base, &group_options) {
+            Ok((validator, target, envelope_caps)) => {
+                for i in members {
+                    results[i] = Some(validate_batch_member(
+                        &validator,
+                        &target,
+                        &payloads[i],
+                        envelope_caps.as_deref(),
+                    ));
+                }
+            }
+            Err(err) => {
+                // Errors are not `Clone`; the members share the one error
+                let err = Arc::new(err);
+                for i in members {
+                    results[i] = Some(Err(ValidateError::Group(Arc::clone(&err))));
+                }
+            }
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|r| r.expect("every payload is assigned a result"))
+        .collect()
+}
+
+/// The part of a payload that determines its composed schema, and the
+/// direction its declaration implies.
+fn declaration_key(payload: &Value, schema_base: &SchemaBaseConfig) -> Option<(Value, Direction)> {
+    let metadata = payload_metadata_at(payload, &schema_base.payload_pointers)?;
+    let key = match metadata {
+        PayloadMetadata::Capabilities(caps) => serde_json::json!({ "capabilities": caps }),
+        PayloadMetadata::Profile(profile) => serde_json::json!({ "profile": profile }),
+    };
+    Some((key, metadata.direction().into()))
+}
+
+/// Compose, resolve, select, and compile the schema for one declaration.
+///
+/// Also returns the selected schema (for error refinement) and the
+/// capabilities for JSONRPC requests, whose validation target is nested under
+/// the root capability's short name.
+fn prepare_batch_schema(
+    payload: &Value,
+    schema_base: &SchemaBaseConfig,
+    options: &ResolveOptions,
+) -> Result<(jsonschema::Validator, Value, Option<Vec<Capability>>), ValidateError> {
+    let capabilities = extract_capabilities(payload, schema_base)?;
+    let composed = compose_schema(&capabilities, schema_base)?;
+    let resolved = resolve(&composed, options)?;
+    let target = select_operation_schema(&resolved, options)?;
+    let validator = jsonschema::validator_for(&target).map_err(|e| invalid_schema(&target, &e))?;
+
+    let envelope_caps = matches!(
+        detect_direction_at(payload, &schema_base.payload_pointers),
+        Some(DetectedDirection::Request)
+    )
+    .then_some(capabilities);
+    Ok((validator, target, envelope_caps))
+}
+
+/// Validate one batch member, unwrapping a JSONRPC envelope when present.
+fn validate_batch_member(
+    validator: &jsonschema::Validator,
+    schema: &Value,
+    payload: &Value,
+    envelope_caps: Option<&[Capability]>,
+) -> Result<(), ValidateError> {
+    let target = match envelope_caps {
+        Some(caps) => extract_jsonrpc_payload(payload, caps)?.0,
+        None => payload,
+    };
+    collect_errors(validator, schema, target)
+}
+
 /// Validate a payload against an already-resolved schema.
 ///
 /// Use this when you've already resolved the schema and want to validate
 /// multiple payloads against it.
 pub fn validate_against_schema(schema: &Value, payload: &Value) -> Result<(), ValidateError> {
-    let validator = jsonschema::validator_for(schema).map_err(|e| {
-        ValidateError::Resolve(ResolveError::InvalidSchema {
+    let validator = jsonschema::validator_for(schema).map_err(|e| invalid_schema(schema, &e))?;
+    collect_errors(&validator, schema, payload)
+}
+
+/// Like [`validate_against_schema`], with error paths presented per `options`.
+pub fn validate_against_schema_with_options(
+    schema: &Value,
+    payload: &Value,
+    options: &ValidateOptions,
+) -> Result<(), ValidateError> {
+    validate_against_schema(schema, payload).map_err(|e| options.apply(e, payload))
+}
+
+/// Validate against a resolved schema that may still contain external `$ref`s.
+///
+/// Like [`validate_against_schema`], but the validator fetches `$ref` targets
+/// on demand instead of requiring a bundling step first. Refs resolve against
+/// the schema's `$id` (or, without one, as paths under `local_base`):
+///
+/// - With `schema_base.local_base`, HTTP(S) URLs map to local files exactly as
+///   in composition (`remote_base` is stripped when it matches).
+/// - Otherwise HTTP(S) URLs are fetched over the network, which requires the
+///   `remote` feature; without it they fail to resolve.
+/// - `file://` URIs are read from disk.
+///
+/// Fetched schemas are used as-is; their own relative refs resolve against
+/// the URI they were fetched from.
+///
+/// # Errors
+///
+/// `ValidateError::Resolve(InvalidSchema)` if a ref cannot be fetched, or
+/// `ValidateError::Invalid` if the payload doesn't match.
+pub fn validate_against_schema_with_refs(
+    schema: &Value,
+    payload: &Value,
+    schema_base: &SchemaBaseConfig,
+) -> Result<(), ValidateError> {
+    let validator = jsonschema::options()
+        .with_retriever(RefRetriever {
+            local_base: schema_base.local_base.map(Path::to_path_buf),
+            remote_base: schema_base.remote_base.map(String::from),
+        })
+        .build(schema)
+        .map_err(|e| invalid_schema(schema, &e))?;
+    collect_errors(&validator, schema, payload)
+}
+
+/// Fetches external `$ref` targets for the validator, using the same URL
+/// mapping as composition.
+struct RefRetriever {
+    local_base: Option<PathBuf>,
+    remote_base: Option<String>,
+}
+
+impl jsonschema::Retrieve for RefRetriever {
+    fn retrieve(
+        &self,
+        uri: &jsonschema::Uri<&str>,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let url = uri.as_str();
+        debug!("retrieving $ref target {}", url);
+        match (uri.scheme().as_str(), &self.local_base) {
+            ("file", _) => Ok(load_schema(Path::new(uri.path().as_str()))?),
+            ("http" | "https", Some(local_base)) => {
+                let path = map_schema_url(url, local_base, self.remote_base.as_deref())?;
+                Ok(load_schema(&path)?)
+            }
+            #[cfg(feature = "remote")]
+            ("http" | "https", None) => Ok(crate::loader::load_schema_url(url)?),
+            #[cfg(not(feature = "remote"))]
+            ("http" | "https", None) => Err(format!(
+                "cannot fetch {}: HTTP fetching requires the 'remote' feature",
+                url
+            )
+            .into()),
+            // Relative refs in a schema without `$id` land on the validator's
+            // placeholder base (`json-schema:///`); treat them as local paths.
+            ("json-schema", Some(local_base)) => Ok(load_schema(
+                &local_base.join(uri.path().as_str().trim_start_matches('/')),
+            )?),
+            _ => Err(format!("cannot resolve {}: no local base to map it to", url).into()),
+        }
+    }
+}
+
+/// Run `validator` over `payload`, refining `oneOf` failures where possible.
+///
+/// A failed `oneOf` only reports that no branch matched. When its branches
+/// carry a `const` discriminator (see [`discriminated_branch_errors`]), the
+/// errors of the branch the payload selected are reported instead.
+fn collect_errors(
+    validator: &jsonschema::Validator,
+    schema: &Value,
+    payload: &Value,
+) -> Result<(), ValidateError> {
+    use jsonschema::error::ValidationErrorKind;
+
+    let mut errors: Vec<SchemaError> = Vec::new();
+    for e in validator.iter_errors(payload) {
+        let instance_path = e.instance_path.to_string();
+        if matches!(e.kind, ValidationErrorKind::OneOfNotValid) {
+            if let Some(branch_errors) = discriminated_branch_errors(
+                schema,
+                payload,
+                &e.schema_path.to_string(),
+                &instance_path,
+            ) {
+                errors.extend(branch_errors);
+                continue;
+            }
+        }
+        errors.push(SchemaError {
+            path: instance_path,
             message: e.to_string(),
+        });
+    }
+
+    // Branch errors can repeat errors reported outside the `oneOf`.
+    let mut unique: Vec<SchemaError> = Vec::with_capacity(errors.len());
+    for err in errors {
+        if !unique
+            .iter()
+            .any(|u| u.path == err.path && u.message == err.message)
+        {
+            unique.push(err);
+        }
+    }
+
+    if unique.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidateError::Invalid { errors: unique })
+    }
+}
+
+/// Errors of the `oneOf` branch a payload's discriminator selects.
+///
+/// A `oneOf` is discriminated when every branch declares the same property
+/// with a `const` (or single-value `enum`) and the values are distinct, e.g.
+/// `type: "credit_card"` vs `type: "wallet"`. The payload's value picks the
+/// branch; the schema is re-validated with that `oneOf` replaced by the
+/// chosen branch, keeping errors at or below the failing instance. Returns
+/// `None` when the `oneOf` is not discriminated or the value matches no branch.
+fn discriminated_branch_errors(
+    schema: &Value,
+    payload: &Value,
+    schema_path: &str,
+    instance_path: &str,
+) -> Option<Vec<SchemaError>> {
+    let one_of_pointer = document_pointer(schema, schema_path)?;
+    let branches = schema.pointer(&one_of_pointer)?.as_array()?;
+    let branch_props: Vec<&Map<String, Value>> = branches
+        .iter()
+        .map(|b| {
+            local_target(schema, b)
+                .get("properties")
+                .and_then(|p| p.as_object())
         })
+        .collect::<Option<_>>()?;
+
+    let discriminator = branch_props.first()?.keys().find(|name| {
+        let values: Option<Vec<&Value>> = branch_props
+            .iter()
+            .map(|props| {
+                props
+                    .get(*name)
+                    .and_then(|p| const_value(local_target(schema, p)))
+            })
+            .collect();
+        values.is_some_and(|v| v.iter().enumerate().all(|(i, a)| !v[..i].contains(a)))
+    })?;
+
+    let actual = payload.pointer(instance_path)?.get(discriminator)?;
+    let chosen = branch_props.iter().position(|props| {
+        props
+            .get(discriminator)
+            .and_then(|p| const_value(local_target(schema, p)))
+            == Some(actual)
     })?;
 
+    // Swap the `oneOf` for the chosen branch, kept under `allOf`.
+    let mut narrowed = schema.clone();
+    let parent_pointer = one_of_pointer.strip_suffix("/oneOf")?;
+    let parent = narrowed.pointer_mut(parent_pointer)?.as_object_mut()?;
+    parent.remove("oneOf");
+    let branch = branches[chosen].clone();
+    match parent.get_mut("allOf") {
+        Some(Value::Array(all_of)) => all_of.push(branch),
+        _ => {
+            parent.insert("allOf".to_string(), Value::Array(vec![branch]));
+        }
+    }
+
+    let validator = jsonschema::validator_for(&narrowed).ok()?;
     let errors: Vec<SchemaError> = validator
         .iter_errors(payload)
         .map(|e| SchemaError {
             path: e.instance_path.to_string(),
             message: e.to_string(),
         })
+        .filter(|e| {
+            instance_path.is_empty()
+                || e.path == instance_path
+                || e.path.starts_with(&format!("{}/", instance_path))
+        })
         .collect();
+    (!errors.is_empty()).then_some(errors)
+}
 
-    if errors.is_empty() {
-        Ok(())
+/// Convert a validator keyword location, which passes through `$ref`s, into
+/// a JSON Pointer within `schema`. Only local (`#...`) refs are followed.
+fn document_pointer(schema: &Value, keyword_path: &str) -> Option<String> {
+    let mut pointer = String::new();
+    let mut node = schema;
+    for segment in keyword_path.split('/').skip(1) {
+        if segment == "$ref" {
+            let target = node.get("$ref")?.as_str()?.strip_prefix('#')?;
+            node = schema.pointer(target)?;
+            pointer = target.to_string();
+        } else {
+            node = node.pointer(&format!("/{}", segment))?;
+            pointer = format!("{}/{}", pointer, segment);
+        }
+    }
+    Some(pointer)
+}
+
+/// Follow local `$ref`s from `value` to the schema they name.
+fn local_target<'a>(schema: &'a Value, value: &'a Value) -> &'a Value {
+    let mut current = value;
+    // Bounded to stay safe on `$ref` cycles.
+    for _ in 0..32 {
+        match current
+            .get("$ref")
+            .and_then(|r| r.as_str())
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|r| schema.pointer(r))
+        {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    current
+}
+
+/// The single value a property schema pins: `const`, or a one-value `enum`.
+fn const_value(prop: &Value) -> Option<&Value> {
+    prop.get("const").or_else(|| match prop.get("enum") {
+        Some(Value::Array(values)) if values.len() == 1 => values.first(),
+        _ => None,
+    })
+}
+
+/// Convert a schema compilation failure into `InvalidSchema`, locating it.
+///
+/// Meta-schema violations carry the offending location as their instance path
+/// (the schema is the instance being checked). Reference failures carry no
+/// path, so the `$ref` they name is looked up in the schema instead.
+fn invalid_schema(schema: &Value, err: &jsonschema::ValidationError) -> ResolveError {
+    use jsonschema::error::ValidationErrorKind;
+    use jsonschema::ReferencingError;
+
+    let instance_path = err.instance_path.to_string();
+    let path = if !instance_path.is_empty() {
+        Some(instance_path)
     } else {
-        Err(ValidateError::Invalid { errors })
+        match &err.kind {
+            ValidationErrorKind::Referencing(ReferencingError::PointerToNowhere { pointer }) => {
+                find_ref(schema, String::new(), &|r: &str| {
+                    r.split_once('#').is_some_and(|(_, frag)| frag == pointer)
+                })
+            }
+            ValidationErrorKind::Referencing(ReferencingError::Unretrievable { uri, .. }) => {
+                find_ref(schema, String::new(), &|r: &str| {
+                    let file = r.split('#').next().unwrap_or(r);
+                    !file.is_empty() && uri.ends_with(file)
+                })
+            }
+            ValidationErrorKind::Referencing(
+                ReferencingError::NoSuchAnchor { anchor }
+                | ReferencingError::InvalidAnchor { anchor },
+            ) => find_ref(schema, String::new(), &|r: &str| {
+                r.split_once('#').is_some_and(|(_, frag)| frag == anchor)
+            }),
+            _ => None,
+        }
+    };
+
+    ResolveError::InvalidSchema {
+        message: err.to_string(),
+        path,
+    }
+}
+
+/// Keywords every draft from 4 through 2020-12 defines. `definitions` and
+/// `dependencies` stay in the 2019-09 and 2020-12 meta-schemas for
+/// compatibility.
+const CORE_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$ref",
+    "definitions",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "items",
+    "properties",
+    "patternProperties",
+    "additionalProperties",
+    "dependencies",
+    "type",
+    "enum",
+    "multipleOf",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "maxProperties",
+    "minProperties",
+    "required",
+    "title",
+    "description",
+    "default",
+    "format",
+];
+
+/// Keywords added by draft 6.
+const DRAFT6_KEYWORDS: &[&str] = &["$id", "const", "contains", "propertyNames", "examples"];
+
+/// Keywords added by draft 7.
+const DRAFT7_KEYWORDS: &[&str] = &[
+    "$comment",
+    "if",
+    "then",
+    "else",
+    "readOnly",
+    "writeOnly",
+    "contentEncoding",
+    "contentMediaType",
+];
+
+/// Keywords added by 2019-09 and kept by 2020-12.
+const DRAFT2019_KEYWORDS: &[&str] = &[
+    "$anchor",
+    "$vocabulary",
+    "$defs",
+    "dependentSchemas",
+    "dependentRequired",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "maxContains",
+    "minContains",
+    "deprecated",
+    "contentSchema",
+];
+
+/// The JSON Schema draft a schema is written against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Draft {
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft201909,
+    Draft202012,
+}
+
+impl Draft {
+    /// The draft a root's `$schema` names; 2020-12 when it is absent or
+    /// unrecognized, as for validation.
+    fn of(schema: &Value) -> Self {
+        let uri = schema.get("$schema").and_then(Value::as_str).unwrap_or("");
+        if uri.contains("draft-04") {
+            Draft::Draft4
+        } else if uri.contains("draft-06") {
+            Draft::Draft6
+        } else if uri.contains("draft-07") {
+            Draft::Draft7
+        } else if uri.contains("2019-09") {
+            Draft::Draft201909
+        } else {
+            Draft::Draft202012
+        }
+    }
+
+    /// Whether this draft's vocabulary defines `keyword`.
+    fn defines(self, keyword: &str) -> bool {
+        match keyword {
+            "id" => self == Draft::Draft4,
+            "additionalItems" => self <= Draft::Draft201909,
+            "$recursiveRef" | "$recursiveAnchor" => self == Draft::Draft201909,
+            "$dynamicRef" | "$dynamicAnchor" | "prefixItems" => self == Draft::Draft202012,
+            k if DRAFT6_KEYWORDS.contains(&k) => self >= Draft::Draft6,
+            k if DRAFT7_KEYWORDS.contains(&k) => self >= Draft::Draft7,
+            k if DRAFT2019_KEYWORDS.contains(&k) => self >= Draft::Draft201909,
+            k => CORE_KEYWORDS.contains(&k),
+        }
+    }
+}
+
+/// UCP capability metadata allowed at a schema's root.
+const UCP_ROOT_KEYWORDS: &[&str] = &["name", "version", "requires"];
+
+/// Find keywords the schema's JSON Schema draft does not define.
+///
+/// Validators ignore unknown keywords, so a typo such as `requird` silently
+/// drops the constraint it meant to add. Only schema positions are walked
+/// (property names are data), and `x-*` extensions, `ucp_*` annotations, and
+/// root-level UCP metadata (`name`, `version`, `requires`) are allowed.
+/// Keywords are checked against the vocabulary of the draft the root's
+/// `$schema` declares (2020-12 when absent), so `$defs` is reported in a
+/// draft-07 schema and `additionalItems` in a 2020-12 one.
+///
+/// Each result's `path` is the JSON Pointer of the unknown keyword.
+pub fn unknown_keywords(schema: &Value) -> Vec<SchemaError> {
+    let mut found = Vec::new();
+    collect_unknown_keywords(schema, Draft::of(schema), "", &mut found);
+    found
+}
+
+fn collect_unknown_keywords(
+    schema: &Value,
+    draft: Draft,
+    path: &str,
+    found: &mut Vec<SchemaError>,
+) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+
+    for (key, value) in map {
+        let child = format!("{}/{}", path, escape_pointer_token(key));
+        // A keyword the draft doesn't define is not descended into: its value
+        // is not a schema to validators
+        if !draft.defines(key) {
+            let allowed = key.starts_with("x-")
+                || key.starts_with("ucp_")
+                || (path.is_empty() && UCP_ROOT_KEYWORDS.contains(&key.as_str()));
+            if !allowed {
+                found.push(SchemaError {
+                    path: child,
+                    message: format!("unknown keyword `{}` is ignored by validators", key),
+                });
+            }
+            continue;
+        }
+        match key.as_str() {
+            "properties" | "patternProperties" | "$defs" | "definitions" | "dependentSchemas" => {
+                if let Value::Object(subschemas) = value {
+                    for (name, sub) in subschemas {
+                        let escaped = escape_pointer_token(name);
+                        collect_unknown_keywords(
+                            sub,
+                            draft,
+                            &format!("{}/{}", child, escaped),
+                            found,
+                        );
+                    }
+                }
+            }
+            "allOf" | "anyOf" | "oneOf" | "prefixItems" => {
+                if let Value::Array(subschemas) = value {
+                    for (i, sub) in subschemas.iter().enumerate() {
+                        collect_unknown_keywords(sub, draft, &format!("{}/{}", child, i), found);
+                    }
+                }
+            }
+            "items"
+            | "additionalItems"
+            | "additionalProperties"
+            | "not"
+            | "if"
+            | "then"
+            | "else"
+            | "contains"
+            | "propertyNames"
+            | "unevaluatedItems"
+            | "unevaluatedProperties"
+            | "contentSchema" => match value {
+                Value::Array(subschemas) => {
+                    for (i, sub) in subschemas.iter().enumerate() {
+                        collect_unknown_keywords(sub, draft, &format!("{}/{}", child, i), found);
+                    }
+                }
+                sub => collect_unknown_keywords(sub, draft, &child, found),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// JSON Pointer to the first `$ref` whose value satisfies `matches`.
+fn find_ref(value: &Value, path: String, matches: &dyn Fn(&str) -> bool) -> Option<String> {
+    match value {
+        Value::Object(map) => {
+            if map
+                .get("$ref")
+                .and_then(|r| r.as_str())
+                .is_some_and(matches)
+            {
+                return Some(format!("{}/$ref", path));
+            }
+            map.iter().find_map(|(key, child)| {
+                let escaped = escape_pointer_token(key);
+                find_ref(child, format!("{}/{}", path, escaped), matches)
+            })
+        }
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .find_map(|(i, child)| find_ref(child, format!("{}/{}", path, i), matches)),
+        _ => None,
     }
 }
 
@@ -165,6 +1138,335 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn unknown_keywords_reports_typos_in_schema_positions() {
+        let schema = json!({
+            "name": "dev.ucp.shopping.checkout",
+            "type": "object",
+            "requird": ["id"],
+            "x-internal": true,
+            "properties": {
+                "requird": { "type": "string", "ucp_request": "omit" },
+                "items": {
+                    "type": "array",
+                    "items": { "type": "object", "additionalProperties": { "maxLenght": 3 } }
+                },
+                "name": { "type": "string", "name": "shadow" }
+            }
+        });
+
+        let found = unknown_keywords(&schema);
+        let paths: Vec<_> = found.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/requird",
+                "/properties/items/items/additionalProperties/maxLenght",
+                "/properties/name/name"
+            ]
+        );
+        assert!(found[0].message.contains("`requird`"));
+    }
+
+    #[test]
+    fn unknown_keywords_follow_declared_draft() {
+        let paths = |schema: &Value| {
+            unknown_keywords(schema)
+                .into_iter()
+                .map(|e| e.path)
+                .collect::<Vec<_>>()
+        };
+        let draft7 = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "additionalItems": false,
+            "$defs": { "a": { "dependentRequired": {} } },
+            "definitions": { "b": { "if": { "const": 1 } } }
+        });
+        assert_eq!(paths(&draft7), ["/$defs"]);
+
+        let draft2020 = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "additionalItems": false,
+            "prefixItems": [{ "$recursiveRef": "#" }],
+            "$defs": { "a": { "dependentRequired": {} } }
+        });
+        assert_eq!(
+            paths(&draft2020),
+            ["/additionalItems", "/prefixItems/0/$recursiveRef"]
+        );
+
+        let draft4 = json!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "id": "https://example.com/a.json",
+            "const": 1
+        });
+        assert_eq!(paths(&draft4), ["/const"]);
+    }
+
+    #[test]
+    fn select_schema_by_id_roots_bundle_member() {
+        let bundle = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$defs": {
+                "checkout": {
+                    "$id": "https://ucp.dev/schemas/checkout.json",
+                    "type": "object",
+                    "properties": { "buyer": { "$ref": "https://ucp.dev/schemas/buyer.json" } },
+                    "required": ["buyer"]
+                },
+                "buyer": {
+                    "$id": "https://ucp.dev/schemas/buyer.json",
+                    "type": "object",
+                    "required": ["email"]
+                },
+                "dup_a": { "$id": "https://ucp.dev/schemas/dup.json" },
+                "dup_b": { "$id": "https://ucp.dev/schemas/dup.json#" }
+            }
+        });
+
+        let checkout =
+            select_schema_by_id(&bundle, "https://ucp.dev/schemas/checkout.json").unwrap();
+        assert_eq!(checkout["$ref"], "#/$defs/checkout");
+        assert!(
+            validate_against_schema(&checkout, &json!({ "buyer": { "email": "a@b.c" } })).is_ok()
+        );
+        assert!(validate_against_schema(&checkout, &json!({ "buyer": {} })).is_err());
+
+        let missing =
+            select_schema_by_id(&bundle, "https://ucp.dev/schemas/cart.json").unwrap_err();
+        assert!(matches!(missing, ResolveError::SchemaIdNotFound { .. }));
+        assert!(missing
+            .to_string()
+            .contains("https://ucp.dev/schemas/buyer.json"));
+
+        let ambiguous =
+            select_schema_by_id(&bundle, "https://ucp.dev/schemas/dup.json").unwrap_err();
+        assert_eq!(
+            ambiguous.to_string(),
+            "$id 'https://ucp.dev/schemas/dup.json' is ambiguous; \
+             declared at: [/$defs/dup_a, /$defs/dup_b]"
+        );
+    }
+
+    #[test]
+    fn validate_and_normalize_fills_defaults() {
+        let schema = json!({
+            "type": "object",
+            "$defs": {
+                "currency": { "type": "string", "default": "USD" }
+            },
+            "properties": {
+                "status": { "type": "string", "default": "open" },
+                "id": { "type": "string", "default": "tmp", "ucp_request": { "create": "omit" } },
+                "currency": { "$ref": "#/$defs/currency" },
+                "line_items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "quantity": { "type": "integer", "default": 1 } }
+                    }
+                }
+            }
+        });
+        let payload = json!({ "status": "closed", "line_items": [{}, { "quantity": 3 }] });
+        let options = ResolveOptions::new(Direction::Request, "create");
+
+        let normalized = validate_and_normalize(&schema, &payload, &options).unwrap();
+        assert_eq!(
+            normalized,
+            json!({
+                "status": "closed",
+                "line_items": [{ "quantity": 1 }, { "quantity": 3 }],
+                "currency": "USD"
+            })
+        );
+
+        let invalid = json!({ "status": 5 });
+        assert!(matches!(
+            validate_and_normalize(&schema, &invalid, &options),
+            Err(ValidateError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn discriminated_one_of_reports_selected_branch() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "payment": {
+                    "oneOf": [
+                        { "$ref": "#/$defs/card" },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "type": { "const": "wallet" },
+                                "wallet_id": { "type": "string" }
+                            },
+                            "required": ["type", "wallet_id"]
+                        }
+                    ]
+                }
+            },
+            "$defs": {
+                "card": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "const": "credit_card" },
+                        "number": { "type": "string" }
+                    },
+                    "required": ["type", "number"]
+                }
+            }
+        });
+        let payload = json!({ "payment": { "type": "credit_card", "number": 42 } });
+
+        let Err(ValidateError::Invalid { errors }) = validate_against_schema(&schema, &payload)
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].path, "/payment/number");
+
+        // Unknown discriminator values keep the generic oneOf error.
+        let payload = json!({ "payment": { "type": "cash" } });
+        let Err(ValidateError::Invalid { errors }) = validate_against_schema(&schema, &payload)
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/payment");
+        assert!(errors[0].message.contains("oneOf"));
+    }
+
+    #[test]
+    fn validate_with_refs_fetches_external_refs_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("types")).unwrap();
+        std::fs::write(
+            dir.path().join("types/buyer.json"),
+            r#"{"type": "object", "required": ["email"],
+                "properties": {"email": {"type": "string"}}}"#,
+        )
+        .unwrap();
+        let config = SchemaBaseConfig {
+            local_base: Some(dir.path()),
+            remote_base: Some("https://example.com/schemas"),
+            ..Default::default()
+        };
+        let schema = json!({
+            "$id": "https://example.com/schemas/checkout.json",
+            "type": "object",
+            "properties": { "buyer": { "$ref": "types/buyer.json" } }
+        });
+
+        assert!(validate_against_schema_with_refs(
+            &schema,
+            &json!({ "buyer": { "email": "a@example.com" } }),
+            &config
+        )
+        .is_ok());
+        match validate_against_schema_with_refs(&schema, &json!({ "buyer": {} }), &config) {
+            Err(ValidateError::Invalid { errors }) => assert_eq!(errors[0].path, "/buyer"),
+            other => panic!("expected Invalid, got {:?}", other),
+        }
+
+        // Without `$id`, relative refs are paths under the local base
+        let unanchored = json!({ "properties": { "buyer": { "$ref": "types/buyer.json" } } });
+        assert!(matches!(
+            validate_against_schema_with_refs(&unanchored, &json!({ "buyer": {} }), &config),
+            Err(ValidateError::Invalid { .. })
+        ));
+
+        // A ref that maps to no file is a schema error, not a silent pass
+        let missing = json!({
+            "$id": "https://example.com/schemas/checkout.json",
+            "properties": { "buyer": { "$ref": "types/missing.json" } }
+        });
+        assert!(matches!(
+            validate_against_schema_with_refs(&missing, &json!({ "buyer": {} }), &config),
+            Err(ValidateError::Resolve(ResolveError::InvalidSchema { .. }))
+        ));
+    }
+
+    #[test]
+    fn json_path_converts_pointers() {
+        let payload = json!({
+            "items": [{ "sku": "a" }],
+            "0": { "a/b": 1, "it's": 2, "naïve": 3, "a\tb\u{1}": 4 }
+        });
+        assert_eq!(json_path("", &payload), "$");
+        assert_eq!(json_path("/items/0/sku", &payload), "$['items'][0]['sku']");
+        // A numeric key on an object is a name, not an index
+        assert_eq!(json_path("/0/naïve", &payload), "$['0']['naïve']");
+        assert_eq!(json_path("/0/a~1b", &payload), "$['0']['a/b']");
+        assert_eq!(json_path("/0/it's", &payload), r"$['0']['it\'s']");
+        assert_eq!(json_path("/0/a\tb\u{1}", &payload), r"$['0']['a\tb\u0001']");
+    }
+
+    #[test]
+    fn validate_with_options_reports_json_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": { "properties": { "qty": { "type": "integer" } } }
+                }
+            }
+        });
+        let payload = json!({ "items": [{ "qty": 1 }, { "qty": "two" }] });
+        let options = ResolveOptions::new(Direction::Request, "create");
+
+        let errors = |validate_options: &ValidateOptions| match validate_with_options(
+            &schema,
+            &payload,
+            &options,
+            validate_options,
+        ) {
+            Err(ValidateError::Invalid { errors }) => errors,
+            other => panic!("expected Invalid, got {:?}", other),
+        };
+        assert_eq!(errors(&ValidateOptions::new())[0].path, "/items/1/qty");
+        assert_eq!(
+            errors(&ValidateOptions::new().path_format(PathFormat::JsonPath))[0].path,
+            "$['items'][1]['qty']"
+        );
+    }
+
+    #[test]
+    fn validate_layered_is_conjunctive() {
+        let base = json!({
+            "type": "object",
+            "properties": {
+                "id": { "$ref": "#/$defs/id", "ucp_request": { "create": "omit" } },
+                "total": { "type": "integer" }
+            },
+            "$defs": { "id": { "type": "string" } }
+        });
+        let overlay = json!({
+            "type": "object",
+            "properties": { "total": { "$ref": "#/$defs/cap" } },
+            "$defs": { "cap": { "maximum": 100 } }
+        });
+        let schemas = [base, overlay];
+
+        let create = ResolveOptions::new(Direction::Request, "create");
+        assert!(validate_layered(&schemas, &json!({ "total": 50 }), &create).is_ok());
+
+        let Err(ValidateError::Invalid { errors }) =
+            validate_layered(&schemas, &json!({ "total": 500 }), &create)
+        else {
+            panic!("overlay constraint should apply");
+        };
+        assert_eq!(errors[0].path, "/total");
+
+        let update = ResolveOptions::new(Direction::Request, "update");
+        assert!(matches!(
+            validate_layered(&schemas, &json!({ "id": 5, "total": 1 }), &update),
+            Err(ValidateError::Invalid { .. })
+        ));
+    }
+
     #[test]
     fn validate_missing_required_field() {
         let schema = json!({
@@ -325,4 +1627,29 @@ mod tests {
             "should allow unknown properties in non-strict mode"
         );
     }
+
+    #[test]
+    fn invalid_schema_reports_keyword_path() {
+        let schema = json!({ "properties": { "a": { "minLength": -1 } } });
+        let err = validate_against_schema(&schema, &json!({})).unwrap_err();
+        assert!(matches!(
+            err,
+            ValidateError::Resolve(ResolveError::InvalidSchema { path: Some(ref p), .. })
+                if p == "/properties/a/minLength"
+        ));
+    }
+
+    #[test]
+    fn invalid_schema_locates_dangling_ref() {
+        let schema = json!({
+            "properties": { "buyer": { "$ref": "#/$defs/missing" } }
+        });
+        let err = validate_against_schema(&schema, &json!({})).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid schema at /properties/buyer/$ref:"),
+            "got: {}",
+            err
+        );
+    }
 }
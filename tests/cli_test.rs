@@ -1,5 +1,7 @@
 //! CLI integration tests for ucp-schema binary.
 
+#![cfg(feature = "full")]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
@@ -47,6 +49,66 @@ mod resolve_command {
             .stdout(predicate::str::contains(r#""required":["id"]"#));
     }
 
+    #[test]
+    fn resolve_boolean_shorthand_is_opt_in() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": false },
+                    "name": { "type": "string" }
+                }
+            }"#,
+        );
+        let args = [
+            "resolve",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+        ];
+
+        cmd().args(args).assert().code(2);
+
+        cmd()
+            .args(args)
+            .arg("--boolean-shorthand")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""name""#))
+            .stdout(predicate::str::contains(r#""id""#).not());
+    }
+
+    #[test]
+    fn resolve_forced_yaml_input() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.txt",
+            "type: object\nproperties:\n  id:\n    type: string\n    ucp_request: omit\n",
+        );
+        let args = [
+            "resolve",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+        ];
+
+        cmd().args(args).assert().code(2);
+
+        cmd()
+            .args(args)
+            .args(["--input-format", "yaml"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""type":"object""#))
+            .stdout(predicate::str::contains(r#""id""#).not());
+    }
+
     #[test]
     fn resolve_with_pretty() {
         let dir = TempDir::new().unwrap();
@@ -159,7 +221,7 @@ mod resolve_command {
     }
 
     #[test]
-    fn resolve_response_direction() {
+    fn resolve_explain_omit_reports_annotation() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -167,7 +229,8 @@ mod resolve_command {
             r#"{
                 "type": "object",
                 "properties": {
-                    "id": { "type": "string", "ucp_response": "required" }
+                    "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                    "name": { "type": "string" }
                 }
             }"#,
         );
@@ -176,17 +239,22 @@ mod resolve_command {
             .args([
                 "resolve",
                 schema.to_str().unwrap(),
-                "--response",
+                "--request",
                 "--op",
-                "read",
+                "create",
+                "--explain-omit",
             ])
             .assert()
             .success()
-            .stdout(predicate::str::contains(r#""required":["id"]"#));
+            .stdout(predicate::str::contains(r#""id""#).not())
+            .stderr(predicate::str::contains(
+                r#"[omit] /properties/id: /properties/id/ucp_request via operation "create""#,
+            ))
+            .stderr(predicate::str::contains("/properties/name").not());
     }
 
     #[test]
-    fn resolve_operation_case_insensitive() {
+    fn resolve_multiple_operations() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -194,34 +262,41 @@ mod resolve_command {
             r#"{
                 "type": "object",
                 "properties": {
-                    "id": {
-                        "type": "string",
-                        "ucp_request": { "create": "required", "update": "omit" }
-                    }
+                    "id": { "type": "string", "ucp_request": { "create": "omit", "update": "required" } },
+                    "secret": { "type": "string", "ucp_request": "omit" }
                 }
             }"#,
         );
-
-        // Using uppercase CREATE should work and match "create"
-        cmd()
-            .args([
-                "resolve",
-                schema.to_str().unwrap(),
-                "--request",
-                "--op",
-                "CREATE",
-            ])
-            .assert()
-            .success()
-            .stdout(predicate::str::contains(r#""required":["id"]"#));
-    }
-}
-
-mod validate_command {
-    use super::*;
-
-    #[test]
-    fn validate_valid_payload() {
+        let resolve = |policy: &str| {
+            let assert = cmd()
+                .args([
+                    "resolve",
+                    schema.to_str().unwrap(),
+                    "--request",
+                    "--op",
+                    "create",
+                    "--op",
+                    "update",
+                    "--required-policy",
+                    policy,
+                ])
+                .assert()
+                .success();
+            let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+            serde_json::from_str::<serde_json::Value>(&stdout).unwrap()
+        };
+
+        let all = resolve("all");
+        assert!(all["properties"]["id"].is_object());
+        assert!(all["properties"].get("secret").is_none());
+        assert!(all.get("required").is_none());
+
+        let any = resolve("any");
+        assert_eq!(any["required"], serde_json::json!(["id"]));
+    }
+
+    #[test]
+    fn resolve_multiple_operations_loosest_min_items() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -229,29 +304,41 @@ mod validate_command {
             r#"{
                 "type": "object",
                 "properties": {
-                    "name": { "type": "string", "ucp_request": "required" }
+                    "items": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "ucp_request": { "create": { "min_items": 2 }, "update": { "min_items": 1 } }
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "ucp_request": { "create": { "min_items": 1 } }
+                    }
                 }
             }"#,
         );
-        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "test"}"#);
-
-        cmd()
+        let assert = cmd()
             .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
+                "resolve",
                 schema.to_str().unwrap(),
                 "--request",
                 "--op",
                 "create",
+                "--op",
+                "update",
             ])
             .assert()
-            .success()
-            .stdout(predicate::str::contains("Valid"));
+            .success();
+        let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+        let resolved: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        // Both set it: the looser one holds for either operation
+        assert_eq!(resolved["properties"]["items"]["minItems"], 1);
+        // Update leaves it unset, so an empty array stays valid
+        assert!(resolved["properties"]["tags"].get("minItems").is_none());
     }
 
     #[test]
-    fn validate_missing_required_field() {
+    fn resolve_stats_summary_and_json() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -259,29 +346,47 @@ mod validate_command {
             r#"{
                 "type": "object",
                 "properties": {
+                    "id": { "type": "string", "ucp_request": { "create": "omit" } },
                     "name": { "type": "string", "ucp_request": "required" }
                 }
             }"#,
         );
-        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+        let args = [
+            "resolve",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+            "--stats",
+        ];
 
+        cmd().args(args).assert().success().stderr(predicate::str::contains(
+            "[stats] 2 properties: 1 omitted, 1 required, 0 optional, 0 included; 1 annotations stripped",
+        ));
+
+        let stats_file = dir.path().join("stats.json");
         cmd()
-            .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
-                schema.to_str().unwrap(),
-                "--request",
-                "--op",
-                "create",
-            ])
+            .args(args)
+            .arg("--json-stats")
+            .arg(&stats_file)
             .assert()
-            .code(1)
-            .stderr(predicate::str::contains("Validation failed"));
+            .success()
+            .stderr(predicate::str::contains("[stats]").not());
+        let stats: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&stats_file).unwrap()).unwrap();
+        assert_eq!(stats["omitted"], 1);
+        assert_eq!(stats["properties_total"], 2);
+
+        cmd()
+            .args(args)
+            .args(["--json-stats", "-"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(r#""annotations_stripped":1"#));
     }
 
     #[test]
-    fn validate_wrong_type() {
+    fn resolve_response_direction() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -289,61 +394,61 @@ mod validate_command {
             r#"{
                 "type": "object",
                 "properties": {
-                    "age": { "type": "number" }
+                    "id": { "type": "string", "ucp_response": "required" }
                 }
             }"#,
         );
-        let payload = write_temp_file(&dir, "payload.json", r#"{"age": "not-a-number"}"#);
 
         cmd()
             .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
+                "resolve",
                 schema.to_str().unwrap(),
-                "--request",
+                "--response",
                 "--op",
-                "create",
+                "read",
             ])
             .assert()
-            .code(1)
-            .stderr(predicate::str::contains("Validation failed"));
+            .success()
+            .stdout(predicate::str::contains(r#""required":["id"]"#));
     }
 
     #[test]
-    fn validate_additional_property_rejected() {
+    fn resolve_operation_case_insensitive() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
             "schema.json",
             r#"{
                 "type": "object",
-                "additionalProperties": false,
                 "properties": {
-                    "id": { "type": "string", "ucp_request": "omit" },
-                    "name": { "type": "string" }
+                    "id": {
+                        "type": "string",
+                        "ucp_request": { "create": "required", "update": "omit" }
+                    }
                 }
             }"#,
         );
-        // Try to send "id" which is omitted - should be rejected as additional property
-        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "test", "id": "123"}"#);
 
+        // Using uppercase CREATE should work and match "create"
         cmd()
             .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
+                "resolve",
                 schema.to_str().unwrap(),
                 "--request",
                 "--op",
-                "create",
+                "CREATE",
             ])
             .assert()
-            .code(1);
+            .success()
+            .stdout(predicate::str::contains(r#""required":["id"]"#));
     }
+}
+
+mod validate_command {
+    use super::*;
 
     #[test]
-    fn validate_json_output_valid() {
+    fn validate_valid_payload() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -351,7 +456,7 @@ mod validate_command {
             r#"{
                 "type": "object",
                 "properties": {
-                    "name": { "type": "string" }
+                    "name": { "type": "string", "ucp_request": "required" }
                 }
             }"#,
         );
@@ -366,107 +471,132 @@ mod validate_command {
                 "--request",
                 "--op",
                 "create",
-                "--json",
             ])
             .assert()
             .success()
-            .stdout(predicate::str::contains(r#"{"valid":true}"#));
+            .stdout(predicate::str::contains("Valid"));
     }
 
     #[test]
-    fn validate_json_output_invalid() {
+    fn validate_combined_document() {
         let dir = TempDir::new().unwrap();
-        let schema = write_temp_file(
+        write_temp_file(
             &dir,
-            "schema.json",
+            "types.json",
+            r#"{ "$defs": { "name": { "type": "string", "minLength": 2 } } }"#,
+        );
+        let combined = write_temp_file(
+            &dir,
+            "combined.json",
             r#"{
-                "type": "object",
-                "properties": {
-                    "name": { "type": "string", "ucp_request": "required" }
-                }
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                        "name": { "$ref": "types.json#/$defs/name", "ucp_request": "required" }
+                    }
+                },
+                "payload": { "name": "x" }
             }"#,
         );
-        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+        let args = [
+            "validate",
+            combined.to_str().unwrap(),
+            "--combined",
+            "--request",
+            "--op",
+            "create",
+            "--strict",
+            "true",
+        ];
 
+        // The $ref resolves next to the combined file and applies to `payload`
         cmd()
-            .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
-                schema.to_str().unwrap(),
-                "--request",
-                "--op",
-                "create",
-                "--json",
-            ])
+            .args(args)
             .assert()
             .code(1)
-            .stdout(predicate::str::contains(r#""valid":false"#))
-            .stdout(predicate::str::contains(r#""errors":"#));
-    }
+            .stderr(predicate::str::contains("Validation failed"));
 
-    #[test]
-    fn validate_json_output_file_error() {
-        let dir = TempDir::new().unwrap();
-        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+        let valid = fs::read_to_string(&combined)
+            .unwrap()
+            .replace(r#"{ "name": "x" }"#, r#"{ "name": "xy" }"#);
+        fs::write(&combined, valid).unwrap();
+        cmd()
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
 
+        let missing = write_temp_file(&dir, "missing.json", r#"{ "schema": {} }"#);
         cmd()
-            .args([
-                "validate",
-                payload.to_str().unwrap(),
-                "--schema",
-                "/nonexistent/schema.json",
-                "--request",
-                "--op",
-                "create",
-                "--json",
-            ])
+            .args(["validate", missing.to_str().unwrap(), "--combined"])
+            .args(["--request", "--op", "create"])
             .assert()
-            .code(3)
-            .stdout(predicate::str::contains(r#""valid":false"#))
-            .stdout(predicate::str::contains(r#""errors":"#));
+            .code(2)
+            .stderr(predicate::str::contains(r#"no "payload" member"#));
     }
-}
-
-mod error_handling {
-    use super::*;
 
     #[test]
-    fn file_not_found() {
+    fn validate_max_payload_size_rejects_large_file() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
+        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "widget"}"#);
+        let args = [
+            "validate",
+            payload.to_str().unwrap(),
+            "--schema",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+            "--max-payload-size",
+        ];
+
+        cmd()
+            .args(args)
+            .arg("10")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(
+                "payload is over the --max-payload-size limit of 10 bytes",
+            ));
+
         cmd()
-            .args([
-                "resolve",
-                "/nonexistent/schema.json",
-                "--request",
-                "--op",
-                "create",
-            ])
+            .args(args)
+            .arg("18")
             .assert()
-            .code(3)
-            .stderr(
-                predicate::str::contains("not found").or(predicate::str::contains("No such file")),
-            );
+            .success()
+            .stdout(predicate::str::contains("Valid"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn invalid_json_schema() {
+    fn validate_max_payload_size_limits_streams() {
         let dir = TempDir::new().unwrap();
-        let schema = write_temp_file(&dir, "bad.json", r#"{ not valid json"#);
+        let schema = write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
 
         cmd()
             .args([
-                "resolve",
+                "validate",
+                "/dev/stdin",
+                "--schema",
                 schema.to_str().unwrap(),
                 "--request",
                 "--op",
                 "create",
+                "--max-payload-size",
+                "10",
             ])
+            .write_stdin(r#"{"name": "widget"}"#)
             .assert()
-            .code(2);
+            .code(2)
+            .stderr(predicate::str::contains(
+                "payload is over the --max-payload-size limit of 10 bytes",
+            ));
     }
 
     #[test]
-    fn invalid_annotation_type() {
+    fn validate_both_reports_each_direction() {
         let dir = TempDir::new().unwrap();
         let schema = write_temp_file(
             &dir,
@@ -474,35 +604,622 @@ mod error_handling {
             r#"{
                 "type": "object",
                 "properties": {
-                    "id": { "type": "string", "ucp_request": 123 }
+                    "id": { "type": "string", "ucp_request": "omit", "ucp_response": "required" },
+                    "name": { "type": "string", "ucp_request": "required" }
                 }
             }"#,
         );
+        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "test"}"#);
+        let args = [
+            "validate",
+            payload.to_str().unwrap(),
+            "--schema",
+            schema.to_str().unwrap(),
+            "--op",
+            "create",
+            "--both",
+        ];
 
         cmd()
-            .args([
-                "resolve",
-                schema.to_str().unwrap(),
-                "--request",
-                "--op",
-                "create",
-            ])
+            .args(args)
             .assert()
-            .code(2)
-            .stderr(predicate::str::contains("annotation"));
+            .code(1)
+            .stdout(predicate::str::contains("request: Valid"))
+            .stderr(predicate::str::contains("response: Validation failed:"));
+        cmd().args(args).arg("--accept-either").assert().success();
+
+        let output = cmd().args(args).arg("--json").output().unwrap();
+        let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["outcome"], "invalid");
+        assert_eq!(result["request"]["valid"], true);
+        assert_eq!(result["request"]["outcome"], "valid");
+        assert_eq!(result["response"]["valid"], false);
+        assert_eq!(result["response"]["outcome"], "invalid");
+        assert!(!result["response"]["errors"].as_array().unwrap().is_empty());
     }
 
     #[test]
-    fn unknown_visibility_value() {
+    fn validate_selects_bundle_member_by_schema_id() {
         let dir = TempDir::new().unwrap();
-        let schema = write_temp_file(
+        let bundle = write_temp_file(
             &dir,
-            "schema.json",
+            "bundle.json",
             r#"{
-                "type": "object",
-                "properties": {
-                    "id": { "type": "string", "ucp_request": "readonly" }
-                }
+                "$defs": {
+                    "checkout": {
+                        "$id": "https://ucp.dev/schemas/checkout.json",
+                        "type": "object",
+                        "required": ["id"]
+                    },
+                    "cart": {
+                        "$id": "https://ucp.dev/schemas/cart.json",
+                        "type": "object",
+                        "required": ["line_items"]
+                    }
+                }
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{"id": "chk_1"}"#);
+
+        let args = [
+            "validate",
+            payload.to_str().unwrap(),
+            "--schema",
+            bundle.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+            "--schema-id",
+        ];
+
+        cmd()
+            .args(args)
+            .arg("https://ucp.dev/schemas/checkout.json")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
+        cmd()
+            .args(args)
+            .arg("https://ucp.dev/schemas/cart.json")
+            .assert()
+            .code(1);
+        cmd()
+            .args(args)
+            .arg("https://ucp.dev/schemas/order.json")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(
+                "no schema with $id 'https://ucp.dev/schemas/order.json'",
+            ))
+            .stderr(predicate::str::contains(
+                "https://ucp.dev/schemas/cart.json",
+            ));
+    }
+
+    #[cfg(feature = "patch")]
+    #[test]
+    fn validate_applies_patch_before_resolving() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "sku": { "type": "string", "pattern": "^[A-Z]+$" }
+                }
+            }"#,
+        );
+        let patch = write_temp_file(
+            &dir,
+            "relax.json",
+            r#"[{ "op": "remove", "path": "/properties/sku/pattern" }]"#,
+        );
+        let bad_patch = write_temp_file(
+            &dir,
+            "bad.json",
+            r#"[{ "op": "remove", "path": "/properties/missing" }]"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{"sku": "lower"}"#);
+
+        let args = [
+            "validate",
+            payload.to_str().unwrap(),
+            "--schema",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+        ];
+
+        cmd().args(args).assert().code(1);
+        cmd()
+            .args(args)
+            .args(["--patch", patch.to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
+        cmd()
+            .args(args)
+            .args(["--patch", bad_patch.to_str().unwrap()])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("invalid patch"));
+    }
+
+    #[test]
+    fn warn_unknown_keywords_flags_typos() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": { "id": { "type": "string" } },
+                "requird": ["id"]
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", "{}");
+        let args = [
+            "validate",
+            payload.to_str().unwrap(),
+            "--schema",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+        ];
+
+        cmd()
+            .args(args)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("requird").not());
+        cmd()
+            .args(args)
+            .arg("--warn-unknown-keywords")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Warning: /requird: unknown keyword `requird`",
+            ));
+    }
+
+    #[test]
+    fn validate_layers_repeated_schemas() {
+        let dir = TempDir::new().unwrap();
+        let base = write_temp_file(
+            &dir,
+            "base.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "ucp_request": "required" }
+                }
+            }"#,
+        );
+        let overlay = write_temp_file(
+            &dir,
+            "overlay.json",
+            r#"{
+                "type": "object",
+                "properties": { "name": { "maxLength": 4 } }
+            }"#,
+        );
+        let short = write_temp_file(&dir, "short.json", r#"{"name": "test"}"#);
+        let long = write_temp_file(&dir, "long.json", r#"{"name": "too long"}"#);
+
+        let args = |payload: &std::path::Path| {
+            let mut c = cmd();
+            c.args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                base.to_str().unwrap(),
+                "--schema",
+                overlay.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ]);
+            c
+        };
+
+        args(&short)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
+        args(&long)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("/name"));
+    }
+
+    #[test]
+    fn validate_missing_required_field() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "ucp_request": "required" }
+                }
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("Validation failed"));
+    }
+
+    #[test]
+    fn validate_wrong_type() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "age": { "type": "number" }
+                }
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{"age": "not-a-number"}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("Validation failed"));
+    }
+
+    #[test]
+    fn validate_additional_property_rejected() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "id": { "type": "string", "ucp_request": "omit" },
+                    "name": { "type": "string" }
+                }
+            }"#,
+        );
+        // Try to send "id" which is omitted - should be rejected as additional property
+        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "test", "id": "123"}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(1);
+    }
+
+    #[test]
+    fn validate_json_output_valid() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" }
+                }
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{"name": "test"}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--json",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"{"valid":true,"outcome":"valid"}"#,
+            ));
+    }
+
+    #[test]
+    fn validate_json_output_invalid() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "ucp_request": "required" }
+                }
+            }"#,
+        );
+        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--json",
+            ])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains(r#""valid":false"#))
+            .stdout(predicate::str::contains(r#""outcome":"invalid""#))
+            .stdout(predicate::str::contains(r#""errors":"#));
+    }
+
+    #[test]
+    fn validate_json_output_file_error() {
+        let dir = TempDir::new().unwrap();
+        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                "/nonexistent/schema.json",
+                "--request",
+                "--op",
+                "create",
+                "--json",
+            ])
+            .assert()
+            .code(3)
+            .stdout(predicate::str::contains(r#""valid":false"#))
+            .stdout(predicate::str::contains(r#""outcome":"io_error""#))
+            .stdout(predicate::str::contains(r#""errors":"#));
+    }
+
+    #[test]
+    fn validate_ndjson_one_line_per_payload() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "ucp_request": "required" }
+                }
+            }"#,
+        );
+        let good = write_temp_file(&dir, "good.json", r#"{"name": "test"}"#);
+        let bad = write_temp_file(&dir, "bad.json", r#"{}"#);
+
+        let output = cmd()
+            .args([
+                "validate",
+                good.to_str().unwrap(),
+                bad.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--format",
+                "ndjson",
+            ])
+            .assert()
+            .code(1)
+            .get_output()
+            .stdout
+            .clone();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["file"], good.to_str().unwrap());
+        assert_eq!(lines[0]["valid"], true);
+        assert_eq!(lines[0]["errors"], serde_json::json!([]));
+        assert_eq!(lines[1]["file"], bad.to_str().unwrap());
+        assert_eq!(lines[1]["valid"], false);
+        assert!(!lines[1]["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn validate_multiple_payloads_text_prefixes_file() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
+        let a = write_temp_file(&dir, "a.json", r#"{}"#);
+        let b = write_temp_file(&dir, "b.json", r#"{}"#);
+
+        cmd()
+            .args([
+                "validate",
+                a.to_str().unwrap(),
+                b.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("{}: Valid", a.display())))
+            .stdout(predicate::str::contains(format!("{}: Valid", b.display())));
+    }
+
+    #[test]
+    fn validate_multiple_payloads_loads_schema_once() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
+        let a = write_temp_file(&dir, "a.json", r#"{}"#);
+        let b = write_temp_file(&dir, "b.json", r#"{}"#);
+
+        let output = cmd()
+            .args([
+                "validate",
+                a.to_str().unwrap(),
+                b.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--verbose",
+            ])
+            .assert()
+            .success()
+            .get_output()
+            .stderr
+            .clone();
+        let stderr = String::from_utf8(output).unwrap();
+        assert_eq!(stderr.matches("[load] using explicit schema").count(), 1);
+        assert_eq!(stderr.matches("[resolve] resolving for").count(), 1);
+        assert_eq!(stderr.matches("[load] reading payload").count(), 2);
+    }
+
+    #[test]
+    fn validate_json_conflicts_with_format() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
+        let payload = write_temp_file(&dir, "payload.json", r#"{}"#);
+
+        cmd()
+            .args([
+                "validate",
+                payload.to_str().unwrap(),
+                "--schema",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--json",
+                "--format",
+                "ndjson",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
+
+mod error_handling {
+    use super::*;
+
+    #[test]
+    fn file_not_found() {
+        cmd()
+            .args([
+                "resolve",
+                "/nonexistent/schema.json",
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(3)
+            .stderr(
+                predicate::str::contains("not found").or(predicate::str::contains("No such file")),
+            );
+    }
+
+    #[test]
+    fn invalid_json_schema() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(&dir, "bad.json", r#"{ not valid json"#);
+
+        cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(2);
+    }
+
+    #[test]
+    fn invalid_annotation_type() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": 123 }
+                }
+            }"#,
+        );
+
+        cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("annotation"));
+    }
+
+    #[test]
+    fn unknown_visibility_value() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": "readonly" }
+                }
             }"#,
         );
 
@@ -587,6 +1304,268 @@ mod required_args {
     }
 }
 
+mod lint_command {
+    use super::*;
+
+    #[test]
+    fn empty_directory_reports_no_files() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(&dir, "notes.txt", "not a schema");
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("no schema files found"))
+            .stdout(predicate::str::contains("all passed").not());
+    }
+
+    #[test]
+    fn input_format_yaml_lints_yaml_files() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(
+            &dir,
+            "schema.yml",
+            "$id: https://example.com/s.json\nproperties:\n  id:\n    ucp_request: bogus\n",
+        );
+
+        cmd()
+            .args([
+                "lint",
+                dir.path().to_str().unwrap(),
+                "--input-format",
+                "yaml",
+            ])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("E004"));
+    }
+
+    #[test]
+    fn only_filters_printed_diagnostics_by_severity() {
+        let dir = TempDir::new().unwrap();
+        // No $id (W002) and an unknown visibility (E004)
+        write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{"properties": {"id": {"type": "string", "ucp_request": "bogus"}}}"#,
+        );
+        let path = dir.path().to_str().unwrap();
+
+        cmd()
+            .args(["lint", path, "--only", "warning"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("W002"))
+            .stdout(predicate::str::contains("E004").not());
+        cmd()
+            .args(["lint", path, "--only", "error"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("E004"))
+            .stdout(predicate::str::contains("W002").not());
+
+        let output = cmd()
+            .args(["lint", path, "--only", "error", "--format", "json"])
+            .output()
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let diagnostics = result["results"][0]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["code"], "E004");
+        assert_eq!(result["warnings"], 1);
+    }
+
+    #[test]
+    fn coverage_reports_annotation_counts() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "$id": "https://example.com/s.json",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": "omit" },
+                    "note": { "type": "string" }
+                }
+            }"#,
+        );
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap(), "--coverage"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Annotation coverage: 1/2 properties annotated (50.0%)",
+            ));
+
+        let output = cmd()
+            .args([
+                "lint",
+                dir.path().to_str().unwrap(),
+                "--coverage",
+                "--format",
+                "json",
+            ])
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["coverage"]["annotated"], 1);
+        assert_eq!(json["results"][0]["coverage"]["shorthand"], 1);
+    }
+
+    #[test]
+    fn path_style_controls_reported_file_paths() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(&dir, "schema.json", r#"{"type": "object"}"#);
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(" schema.json"));
+
+        let absolute = dir.path().join("schema.json");
+        cmd()
+            .args([
+                "lint",
+                dir.path().to_str().unwrap(),
+                "--path-style",
+                "absolute",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(absolute.to_str().unwrap()));
+
+        cmd()
+            .current_dir(dir.path())
+            .args([
+                "lint",
+                ".",
+                "--path-style",
+                "cwd-relative",
+                "--format",
+                "json",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""file": "schema.json""#));
+    }
+
+    #[test]
+    fn exclude_skips_matching_files() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{"$id": "https://example.com/s.json"}"#,
+        );
+        write_temp_file(&dir, "broken.generated.json", "{ not json }");
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap()])
+            .assert()
+            .code(1);
+
+        cmd()
+            .args([
+                "lint",
+                dir.path().to_str().unwrap(),
+                "--exclude",
+                "*.generated.json",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("1 files checked"));
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap(), "--exclude", "[bad"])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("invalid glob"));
+    }
+
+    #[test]
+    fn config_file_is_discovered_above_the_linted_path() {
+        let dir = TempDir::new().unwrap();
+        write_temp_file(&dir, ".ucp-lint.json", r#"{"allow_missing_id": true}"#);
+        std::fs::create_dir(dir.path().join("schemas")).unwrap();
+        write_temp_file(&dir, "schemas/no_id.json", r#"{"type": "object"}"#);
+
+        // W002 is disabled by the config, so --strict passes
+        cmd()
+            .args([
+                "lint",
+                dir.path().join("schemas").to_str().unwrap(),
+                "--strict",
+            ])
+            .assert()
+            .success();
+
+        // The config itself is not linted as a schema
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("1 files checked"));
+    }
+
+    #[test]
+    fn config_flag_loads_toml_and_flags_override_it() {
+        let dir = TempDir::new().unwrap();
+        let config = write_temp_file(
+            &dir,
+            "lint.toml",
+            "strict = true\nexclude = [\"*.generated.json\"]\n",
+        );
+        std::fs::create_dir(dir.path().join("schemas")).unwrap();
+        write_temp_file(&dir, "schemas/no_id.json", r#"{"type": "object"}"#);
+        write_temp_file(&dir, "schemas/broken.generated.json", "{ not json }");
+        let schemas = dir.path().join("schemas");
+
+        // strict from the config turns the W002 warning into a failure
+        cmd()
+            .args(["lint", schemas.to_str().unwrap(), "--config"])
+            .arg(&config)
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("1 files checked"));
+
+        // --exclude replaces the config's patterns
+        cmd()
+            .args(["lint", schemas.to_str().unwrap(), "--config"])
+            .arg(&config)
+            .args(["--exclude", "no_id.json"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("E001"));
+    }
+
+    #[test]
+    fn invalid_config_file_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let config = write_temp_file(&dir, "lint.json", r#"{"stict": true}"#);
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap(), "--config"])
+            .arg(&config)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("invalid lint config"))
+            .stderr(predicate::str::contains("stict"));
+    }
+
+    #[test]
+    fn empty_directory_fails_with_error_on_empty() {
+        let dir = TempDir::new().unwrap();
+
+        cmd()
+            .args(["lint", dir.path().to_str().unwrap(), "--error-on-empty"])
+            .assert()
+            .code(1);
+    }
+}
+
 mod help_and_version {
     use super::*;
 
@@ -621,6 +1600,31 @@ mod help_and_version {
             .stdout(predicate::str::contains("--schema-remote-base"));
     }
 
+    #[test]
+    fn completions_bash() {
+        cmd()
+            .args(["completions", "bash"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("complete -F"))
+            .stdout(predicate::str::contains("--schema-local-base"));
+    }
+
+    #[test]
+    fn completions_unknown_shell() {
+        cmd().args(["completions", "tcsh"]).assert().code(2);
+    }
+
+    #[test]
+    fn selftest_passes() {
+        cmd()
+            .arg("selftest")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("checkout request/create"))
+            .stdout(predicate::str::contains("checks passed"));
+    }
+
     #[test]
     fn validate_help() {
         cmd()
@@ -715,28 +1719,131 @@ mod bundle {
     use super::*;
 
     #[test]
-    fn bundle_resolves_external_ref() {
+    fn bundle_resolves_external_ref() {
+        let dir = TempDir::new().unwrap();
+
+        // Create a referenced type schema
+        fs::create_dir_all(dir.path().join("types")).unwrap();
+        fs::write(
+            dir.path().join("types/buyer.json"),
+            r#"{"type":"object","properties":{"email":{"type":"string"}}}"#,
+        )
+        .unwrap();
+
+        // Create main schema with $ref
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{
+                "type": "object",
+                "properties": {
+                    "buyer": { "$ref": "types/buyer.json" }
+                }
+            }"#,
+        );
+
+        cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--bundle",
+            ])
+            .assert()
+            .success()
+            // $ref should be resolved, email property should be present
+            .stdout(predicate::str::contains(r#""email""#))
+            // No $ref should remain (except self-refs)
+            .stdout(predicate::str::contains(r#""$ref":"types/buyer.json""#).not());
+    }
+
+    #[test]
+    fn require_bundled_rejects_remaining_external_ref() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("types")).unwrap();
+        fs::write(
+            dir.path().join("types/buyer.json"),
+            r#"{"type":"object","properties":{"email":{"type":"string"}}}"#,
+        )
+        .unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r#"{"type":"object","properties":{"buyer":{"$ref":"types/buyer.json"}}}"#,
+        );
+        let args = [
+            "resolve",
+            schema.to_str().unwrap(),
+            "--request",
+            "--op",
+            "create",
+            "--require-bundled",
+        ];
+
+        cmd()
+            .args(args)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(
+                r#"$ref "types/buyer.json" at /properties/buyer/$ref was not bundled"#,
+            ));
+        cmd().args(args).arg("--bundle").assert().success();
+    }
+
+    #[test]
+    fn bundle_stdin_with_base_dir() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("types")).unwrap();
+        fs::write(
+            dir.path().join("types/buyer.json"),
+            r#"{"type":"object","properties":{"email":{"type":"string"}}}"#,
+        )
+        .unwrap();
+
+        cmd()
+            .args(["resolve", "-", "--request", "--op", "create", "--bundle"])
+            .arg("--base-dir")
+            .arg(dir.path())
+            .write_stdin(r#"{"type":"object","properties":{"buyer":{"$ref":"types/buyer.json"}}}"#)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""email""#))
+            .stdout(predicate::str::contains(r#""$ref""#).not());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_reads_non_regular_file() {
+        cmd()
+            .args(["resolve", "/dev/stdin", "--request", "--op", "create"])
+            .write_stdin(r#"{"type":"object","properties":{"id":{"type":"string"}}}"#)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""id""#));
+    }
+
+    #[test]
+    fn bundle_trace_ref_logs_resolved_targets() {
         let dir = TempDir::new().unwrap();
-
-        // Create a referenced type schema
         fs::create_dir_all(dir.path().join("types")).unwrap();
         fs::write(
-            dir.path().join("types/buyer.json"),
-            r#"{"type":"object","properties":{"email":{"type":"string"}}}"#,
+            dir.path().join("types/common.json"),
+            r#"{"$defs":{"email":{"type":"string","format":"email"}}}"#,
         )
         .unwrap();
-
-        // Create main schema with $ref
         let schema = write_temp_file(
             &dir,
             "schema.json",
             r#"{
                 "type": "object",
                 "properties": {
-                    "buyer": { "$ref": "types/buyer.json" }
+                    "email": { "$ref": "types/common.json#/$defs/email" }
                 }
             }"#,
         );
+        let resolved = dir.path().join("types/common.json").canonicalize().unwrap();
 
         cmd()
             .args([
@@ -746,13 +1853,42 @@ mod bundle {
                 "--op",
                 "create",
                 "--bundle",
+                "--trace-ref",
             ])
             .assert()
             .success()
-            // $ref should be resolved, email property should be present
-            .stdout(predicate::str::contains(r#""email""#))
-            // No $ref should remain (except self-refs)
-            .stdout(predicate::str::contains(r#""$ref":"types/buyer.json""#).not());
+            .stderr(predicate::str::contains(format!(
+                "[trace-ref] types/common.json#/$defs/email -> {}#/$defs/email",
+                resolved.display()
+            )));
+
+        // Tracing is only meaningful while bundling
+        cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--trace-ref",
+            ])
+            .assert()
+            .code(2);
+
+        // URL sources are not bundled from local files, so there is nothing to trace
+        cmd()
+            .args([
+                "resolve",
+                "https://example.com/schema.json",
+                "--request",
+                "--op",
+                "create",
+                "--bundle",
+                "--trace-ref",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("URL schema source"));
     }
 
     #[test]
@@ -1043,6 +2179,155 @@ mod bundle {
         let parsed: Result<serde_json::Value, _> = serde_json::from_str(&content);
         assert!(parsed.is_ok(), "Bundle output should be valid JSON");
     }
+
+    #[test]
+    fn inline_defs_expands_internal_refs() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r##"{
+                "type": "object",
+                "properties": {
+                    "price": { "$ref": "#/$defs/amount" },
+                    "node": { "$ref": "#/$defs/node" }
+                },
+                "$defs": {
+                    "amount": { "type": "integer", "minimum": 0 },
+                    "node": {
+                        "type": "object",
+                        "properties": { "child": { "$ref": "#/$defs/node" } }
+                    }
+                }
+            }"##,
+        );
+
+        let output = cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--inline-defs",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let resolved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(resolved["properties"]["price"]["type"], "integer");
+        assert!(resolved["properties"]["price"].get("$ref").is_none());
+        // Recursion stops at the first repeat
+        assert_eq!(
+            resolved["properties"]["node"]["properties"]["child"]["$ref"],
+            "#/$defs/node"
+        );
+    }
+
+    #[test]
+    fn target_openapi30_rewrites_output() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r##"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                    "note": { "type": ["string", "null"] },
+                    "price": { "$ref": "#/$defs/amount" },
+                    "tags": { "type": "array", "prefixItems": [{ "type": "string" }] }
+                },
+                "$defs": { "amount": { "type": "integer", "minimum": 0 } }
+            }"##,
+        );
+
+        let output = cmd()
+            .args([
+                "resolve",
+                schema.to_str().unwrap(),
+                "--request",
+                "--op",
+                "create",
+                "--target",
+                "openapi30",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("prefixItems"), "{}", stderr);
+
+        let resolved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(resolved.get("$defs").is_none());
+        assert!(resolved.get("$schema").is_none());
+        assert!(resolved["properties"].get("id").is_none());
+        assert_eq!(resolved["properties"]["note"]["nullable"], true);
+        assert_eq!(resolved["properties"]["note"]["type"], "string");
+        assert_eq!(resolved["properties"]["price"]["type"], "integer");
+    }
+
+    #[test]
+    fn flatten_for_codegen_merges_allof() {
+        let dir = TempDir::new().unwrap();
+        let schema = write_temp_file(
+            &dir,
+            "schema.json",
+            r##"{
+                "$defs": {
+                    "base": {
+                        "type": "object",
+                        "properties": { "id": { "type": "string", "ucp_request": "omit" } }
+                    }
+                },
+                "allOf": [
+                    { "$ref": "#/$defs/base" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "ucp_request": "required" },
+                            "kind": { "if": { "const": "a" }, "then": { "minLength": 1 } }
+                        }
+                    }
+                ]
+            }"##,
+        );
+
+        let output = cmd()
+            .args(["resolve", schema.to_str().unwrap()])
+            .args(["--request", "--op", "create", "--flatten-for-codegen"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("/properties/kind: dropped unsupported keyword `if`"),
+            "{}",
+            stderr
+        );
+
+        let resolved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            resolved,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "kind": {}
+                },
+                "required": ["name"]
+            })
+        );
+
+        cmd()
+            .args(["resolve", schema.to_str().unwrap()])
+            .args(["--request", "--op", "create", "--flatten-for-codegen"])
+            .args(["--target", "openapi30"])
+            .assert()
+            .failure();
+    }
 }
 
 /// Remote schema loading tests — use local mock server (no external dependencies)
@@ -1144,6 +2429,52 @@ mod remote {
 mod compose {
     use super::*;
 
+    #[test]
+    fn schema_override_replaces_capability_schema() {
+        // The draft checkout requires a field the payload lacks, so validation
+        // only fails if the override was used instead of the local-base mapping.
+        let dir = TempDir::new().unwrap();
+        let draft = write_temp_file(
+            &dir,
+            "checkout_draft.json",
+            r#"{
+                "type": "object",
+                "required": ["draft_only"],
+                "properties": { "draft_only": { "type": "string" } }
+            }"#,
+        );
+
+        cmd()
+            .args([
+                "validate",
+                "tests/fixtures/compose/response_checkout_only.json",
+                "--schema-local-base",
+                "tests/fixtures/compose",
+                "--schema-override",
+                &format!("dev.ucp.shopping.checkout={}", draft.display()),
+                "--response",
+                "--op",
+                "read",
+            ])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("draft_only"));
+    }
+
+    #[test]
+    fn schema_override_requires_name_and_path() {
+        cmd()
+            .args([
+                "compose",
+                "tests/fixtures/compose/response_checkout_only.json",
+                "--schema-override",
+                "no-equals-sign",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("NAME=PATH"));
+    }
+
     #[test]
     fn self_describing_checkout_only() {
         // Validate a self-describing response against local schemas
@@ -1184,6 +2515,114 @@ mod compose {
             .stdout(predicate::str::contains("Valid"));
     }
 
+    #[test]
+    fn strict_with_ignored_ucp_envelope() {
+        let args = [
+            "validate",
+            "tests/fixtures/compose/response_checkout_only.json",
+            "--schema-local-base",
+            "tests/fixtures/compose",
+            "--op",
+            "read",
+            "--strict=true",
+        ];
+
+        cmd()
+            .args(args)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("/ucp"));
+
+        cmd()
+            .args(args)
+            .arg("--ignore-ucp-envelope")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
+    }
+
+    #[test]
+    fn strict_with_envelope_schema() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("schemas/shopping")).unwrap();
+        write_temp_file(
+            &dir,
+            "schemas/shopping/checkout.json",
+            r#"{
+                "type": "object",
+                "properties": { "status": { "type": "string" } }
+            }"#,
+        );
+        let envelope = write_temp_file(
+            &dir,
+            "envelope.json",
+            r##"{
+                "$defs": {
+                    "meta": {
+                        "type": "object",
+                        "properties": {
+                            "capabilities": {
+                                "type": "object",
+                                "additionalProperties": { "type": "array" }
+                            }
+                        }
+                    }
+                },
+                "properties": { "ucp": { "$ref": "#/$defs/meta" }, "id": { "type": "string" } }
+            }"##,
+        );
+        let payload = |extra: &str| {
+            format!(
+                r#"{{
+                    "ucp": {{ "capabilities": {{ "dev.ucp.shopping.checkout": [
+                        {{ "version": "2026-01-11", "schema": "https://ucp.dev/schemas/shopping/checkout.json" }}
+                    ] }} }},
+                    "id": "chk_1",
+                    "status": "incomplete"{}
+                }}"#,
+                extra
+            )
+        };
+        let valid = write_temp_file(&dir, "valid.json", &payload(""));
+        let unknown = write_temp_file(&dir, "unknown.json", &payload(r#", "bogus": 1"#));
+        let args = |file: &std::path::Path| {
+            vec![
+                "validate".to_string(),
+                file.display().to_string(),
+                "--schema-local-base".to_string(),
+                dir.path().join("schemas").display().to_string(),
+                "--schema-remote-base".to_string(),
+                "https://ucp.dev/schemas".to_string(),
+                "--op".to_string(),
+                "read".to_string(),
+                "--strict=true".to_string(),
+            ]
+        };
+
+        cmd()
+            .args(args(&valid))
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("'ucp'"));
+
+        cmd()
+            .args(args(&valid))
+            .arg("--envelope-schema")
+            .arg(&envelope)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Valid"));
+
+        // Strict still rejects fields neither the capability nor the envelope declares
+        cmd()
+            .args(args(&unknown))
+            .arg("--envelope-schema")
+            .arg(&envelope)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("bogus"));
+    }
+
     #[test]
     fn direction_auto_inferred_response() {
         // Direction should be auto-inferred from ucp.capabilities
@@ -1420,6 +2859,7 @@ mod compose {
             .assert()
             .code(2)
             .stdout(predicate::str::contains(r#""valid":false"#))
+            .stdout(predicate::str::contains(r#""outcome":"schema_error""#))
             .stdout(predicate::str::contains(r#""errors":"#));
     }
 }
@@ -1531,6 +2971,71 @@ mod compose_command {
             .stderr(predicate::str::contains("no capabilities"));
     }
 
+    #[test]
+    fn compose_capability_id_pointer_catches_miswired_schema() {
+        let args = [
+            "compose",
+            "tests/fixtures/compose/response_with_extensions.json",
+            "--schema-local-base",
+            "tests/fixtures/compose",
+            "--capability-id-pointer",
+            "/name",
+        ];
+
+        cmd().args(args).assert().success();
+
+        cmd()
+            .args(args)
+            .args([
+                "--schema-override",
+                "dev.ucp.shopping.discount=tests/fixtures/compose/schemas/shopping/fulfillment.json",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(
+                "capability 'dev.ucp.shopping.discount' schema declares capability id \
+                 'dev.ucp.shopping.fulfillment'",
+            ));
+
+        cmd()
+            .args(&args[..4])
+            .args(["--capability-id-pointer", "/x-ucp-capability"])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("declares no capability id"));
+    }
+
+    #[test]
+    fn compose_top_level_allof_extension() {
+        let args = [
+            "compose",
+            "tests/fixtures/compose/response_with_allof_extension.json",
+            "--schema-local-base",
+            "tests/fixtures/compose",
+        ];
+
+        // Strict by default: no $defs entry for the extended capability
+        cmd()
+            .args(args)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("dev.ucp.shopping.checkout"));
+
+        let assert = cmd()
+            .args(args)
+            .arg("--top-level-extensions")
+            .assert()
+            .success();
+        let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+        let branch = &schema["allOf"][0];
+        assert!(branch.get("$defs").is_none(), "extension $defs dropped");
+        let gift = &branch["allOf"][1]["properties"]["gift_message"];
+        assert_eq!(gift["required"], serde_json::json!(["text"]));
+        assert_eq!(gift["ucp_request"]["create"], "optional");
+    }
+
     #[test]
     fn compose_missing_schema_base_error() {
         cmd()
@@ -1545,6 +3050,42 @@ mod compose_command {
             .stderr(predicate::str::contains("failed to fetch schema"));
     }
 
+    #[test]
+    fn compose_explain_shows_attempted_path() {
+        cmd()
+            .args([
+                "compose",
+                "tests/fixtures/compose/response_checkout_only.json",
+                "--schema-local-base",
+                "/nonexistent/schemas",
+                "--explain",
+            ])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "[explain] dev.ucp.shopping.checkout",
+            ))
+            .stderr(predicate::str::contains(
+                "URL path joined onto /nonexistent/schemas",
+            ))
+            .stderr(predicate::str::contains("(not found)"));
+
+        cmd()
+            .args([
+                "validate",
+                "tests/fixtures/compose/response_checkout_only.json",
+                "--schema-local-base",
+                "tests/fixtures/compose",
+                "--op",
+                "read",
+                "--explain-compose",
+            ])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("[explain]   source:"))
+            .stderr(predicate::str::contains("(not found)").not());
+    }
+
     #[test]
     fn compose_with_output_file() {
         let dir = TempDir::new().unwrap();
@@ -1568,6 +3109,38 @@ mod compose_command {
         assert!(schema["properties"]["id"].is_object());
     }
 
+    #[test]
+    fn compose_dry_run_does_not_write() {
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("composed.json");
+        let args = [
+            "compose",
+            "tests/fixtures/compose/response_checkout_only.json",
+            "--schema-local-base",
+            "tests/fixtures/compose",
+            "--output",
+            output.to_str().unwrap(),
+        ];
+
+        cmd()
+            .args(args)
+            .arg("--dry-run")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("[dry-run] would write"))
+            .stdout(predicate::str::contains("(new file)"));
+        assert!(!output.exists());
+
+        // Same content on disk is reported as unchanged
+        cmd().args(args).assert().success();
+        cmd()
+            .arg("--dry-run")
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("(unchanged)"));
+    }
+
     #[test]
     fn compose_with_pretty() {
         cmd()
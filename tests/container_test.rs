@@ -14,12 +14,14 @@
 //!   5. Single-object capabilities (checkout) are unaffected.
 //!   6. An extension that does not mirror the container is rejected.
 
+#![cfg(feature = "full")]
+
 use std::path::Path;
 
 use serde_json::{json, Value};
 use ucp_schema::{
-    compose_from_payload, is_container_schema, select_operation_schema, validate, Direction,
-    ResolveOptions, SchemaBaseConfig, ValidateError,
+    compose_from_payload, compose_from_payload_detailed, is_container_schema,
+    select_operation_schema, validate, Direction, ResolveOptions, SchemaBaseConfig, ValidateError,
 };
 
 /// Write the fixture schema tree under `<dir>/schemas/shopping/` and return the
@@ -173,10 +175,10 @@ fn write_fixtures(dir: &Path) {
 fn config(dir: &Path) -> SchemaBaseConfig<'static> {
     // Leak the base path so the borrow is 'static for test convenience.
     let base: &'static Path = Box::leak(dir.join("schemas").into_boxed_path());
-    SchemaBaseConfig {
-        local_base: Some(base),
-        remote_base: Some("https://ucp.dev/schemas"),
-    }
+    let mut config = SchemaBaseConfig::default();
+    config.local_base = Some(base);
+    config.remote_base = Some("https://ucp.dev/schemas");
+    config
 }
 
 fn search_payload(products: Value) -> Value {
@@ -347,6 +349,26 @@ fn extension_constraint_applies() {
     );
 }
 
+#[test]
+fn detailed_compose_reports_contributing_capabilities() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let cfg = config(dir.path());
+
+    let payload = search_payload_with_fulfillment(json!([]));
+    let (schema, capabilities) = compose_from_payload_detailed(&payload, &cfg).unwrap();
+    assert_eq!(schema, compose_from_payload(&payload, &cfg).unwrap());
+
+    let names: Vec<&str> = capabilities.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "dev.ucp.shopping.catalog.search",
+            "dev.ucp.shopping.fulfillment"
+        ]
+    );
+}
+
 // --- 5. Single-object capability is unaffected ---
 
 #[test]
@@ -509,3 +531,171 @@ fn explicit_def_validates_fragment_end_to_end() {
         ResolveOptions::new(Direction::Request, "create").def_name(Some("checkout".to_string()));
     assert!(validate(&schema, &payload, &def_opts).is_ok());
 }
+
+// --- Async composition matches the blocking path ---
+
+#[cfg(feature = "async")]
+#[test]
+fn async_compose_matches_blocking() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let cfg = config(dir.path());
+
+    let payload = search_payload_with_fulfillment(json!([]));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let composed = runtime
+        .block_on(ucp_schema::compose_from_payload_async(&payload, &cfg))
+        .unwrap();
+
+    assert_eq!(composed, compose_from_payload(&payload, &cfg).unwrap());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_compose_honors_payload_pointers() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let mut cfg = config(dir.path());
+    cfg.payload_pointers = ucp_schema::PayloadPointers {
+        capabilities: "/meta/capabilities",
+        profile: "/meta/profile",
+    };
+
+    let mut payload = search_payload(json!([]));
+    let caps = payload["ucp"]["capabilities"].take();
+    payload.as_object_mut().unwrap().remove("ucp");
+    payload["meta"] = json!({ "capabilities": caps });
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let composed = runtime
+        .block_on(ucp_schema::compose_from_payload_async(&payload, &cfg))
+        .unwrap();
+
+    assert_eq!(composed, compose_from_payload(&payload, &cfg).unwrap());
+}
+
+// --- Batch validation composes once per distinct declaration ---
+
+#[test]
+fn validate_many_groups_by_declaration() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let cfg = config(dir.path());
+
+    let payloads = vec![
+        search_payload(json!([{ "id": "p1", "title": "Widget" }])),
+        search_payload_with_fulfillment(json!([{
+            "id": "p2", "title": "Gadget", "fulfillment_methods": "NOT_AN_ARRAY"
+        }])),
+        search_payload(json!([{ "id": "p3" }])),
+        json!({ "products": [] }),
+    ];
+    let opts = ResolveOptions::new(Direction::Response, "search");
+    let results = ucp_schema::validate_many(&payloads, &cfg, &opts);
+
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok());
+    // Extension constraint applies to the second group only.
+    assert!(matches!(results[1], Err(ValidateError::Invalid { .. })));
+    assert!(matches!(results[2], Err(ValidateError::Invalid { .. })));
+    assert!(matches!(
+        results[3],
+        Err(ValidateError::Compose(
+            ucp_schema::ComposeError::NotSelfDescribing
+        ))
+    ));
+
+    // Same verdicts as validating one at a time.
+    for (payload, batched) in payloads.iter().zip(&results).take(3) {
+        let schema = compose_from_payload(payload, &cfg).unwrap();
+        assert_eq!(validate(&schema, payload, &opts).is_ok(), batched.is_ok());
+    }
+}
+
+#[test]
+fn validate_many_takes_direction_from_each_declaration() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    std::fs::write(
+        dir.path().join("schemas/profile.json"),
+        r#"{ "ucp": { "capabilities": { "dev.ucp.shopping.catalog.search": [
+            { "version": "2026-04-08", "schema": "https://ucp.dev/schemas/shopping/catalog_search.json" }
+        ] } } }"#,
+    )
+    .unwrap();
+    let cfg = config(dir.path());
+
+    let request = |search: Value| json!({ "meta": { "profile": "https://ucp.dev/schemas/profile.json" }, "search": search });
+    let payloads = vec![
+        search_payload(json!([{ "id": "p1", "title": "Widget" }])),
+        request(json!({ "query": "shoes" })),
+        request(json!({})),
+        search_payload(json!([{ "id": "p2" }])),
+    ];
+    // The options' direction doesn't matter: each group uses its own
+    let opts = ResolveOptions::new(Direction::Response, "search");
+    let results = ucp_schema::validate_many(&payloads, &cfg, &opts);
+
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok(), "{:?}", results[1]);
+    assert!(matches!(results[2], Err(ValidateError::Invalid { .. })));
+    assert!(matches!(results[3], Err(ValidateError::Invalid { .. })));
+}
+
+#[test]
+fn validate_many_honors_payload_pointers() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let mut cfg = config(dir.path());
+    cfg.payload_pointers = ucp_schema::PayloadPointers {
+        capabilities: "/meta/capabilities",
+        profile: "/meta/profile",
+    };
+
+    let nested = |products: Value| {
+        let mut payload = search_payload(products);
+        let caps = payload["ucp"]["capabilities"].take();
+        payload.as_object_mut().unwrap().remove("ucp");
+        payload["meta"] = json!({ "capabilities": caps });
+        payload
+    };
+    let payloads = vec![
+        nested(json!([{ "id": "p1", "title": "Widget" }])),
+        nested(json!([{ "id": "p2" }])),
+    ];
+    let opts = ResolveOptions::new(Direction::Response, "search");
+    let results = ucp_schema::validate_many(&payloads, &cfg, &opts);
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(ValidateError::Invalid { .. })));
+}
+
+#[test]
+fn validate_many_reports_compose_failure_per_payload() {
+    let dir = tempfile::tempdir().unwrap();
+    write_fixtures(dir.path());
+    let cfg = config(dir.path());
+
+    let missing = json!({
+        "ucp": { "capabilities": { "dev.ucp.shopping.catalog.search": [
+            { "version": "2026-04-08", "schema": "https://ucp.dev/schemas/shopping/nope.json" } ] } }
+    });
+    let opts = ResolveOptions::new(Direction::Response, "search");
+    let results = ucp_schema::validate_many(&[missing.clone(), missing], &cfg, &opts);
+
+    // Composed once: both members hold the same error
+    let shared: Vec<_> = results
+        .iter()
+        .map(|r| match r {
+            Err(ValidateError::Group(err)) => err.clone(),
+            other => panic!("expected a group error, got {:?}", other),
+        })
+        .collect();
+    assert!(std::sync::Arc::ptr_eq(&shared[0], &shared[1]));
+    assert!(matches!(*shared[0], ValidateError::Compose(_)));
+}
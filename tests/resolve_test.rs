@@ -1,13 +1,45 @@
 //! Integration tests for schema resolution.
 
 use serde_json::{json, Value};
-use ucp_schema::{resolve, Direction, ResolveError, ResolveOptions};
+use ucp_schema::{
+    resolve, resolve_detailed, resolve_explained, resolve_with_stats, Direction, Omission,
+    OmitMatch, RequiredPolicy, ResolveError, ResolveOptions, ResolveStats,
+};
 
 // === Visibility Parsing Tests ===
 
 mod visibility_parsing {
     use super::*;
 
+    #[test]
+    fn deprecate_keeps_field_marked_deprecated() {
+        let schema = json!({
+            "type": "object",
+            "required": ["legacy_id", "name"],
+            "properties": {
+                "legacy_id": {
+                    "type": "object",
+                    "properties": { "value": { "type": "string" } },
+                    "ucp_request": { "update": "deprecate", "create": "omit" }
+                },
+                "name": { "type": "string" }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "update").strict(true);
+        let result = resolve(&schema, &options).unwrap();
+        let legacy = &result["properties"]["legacy_id"];
+        assert_eq!(legacy["deprecated"], json!(true));
+        assert_eq!(legacy["additionalProperties"], json!(false));
+        assert!(legacy.get("ucp_request").is_none());
+        assert_eq!(result["required"], json!(["name"]));
+
+        // Other operations are unaffected
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert!(result["properties"].get("legacy_id").is_none());
+    }
+
     #[test]
     fn shorthand_string() {
         let schema = json!({
@@ -173,6 +205,33 @@ mod error_handling {
             Err(ResolveError::UnknownVisibility { value, .. }) if value == "maybe"
         ));
     }
+
+    #[test]
+    fn unknown_operation_errors_when_validated() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "ucp_request": { "create": "omit" },
+                    "ucp_response": { "delete": "omit" }
+                }
+            }
+        });
+        let allowed = vec!["Create".to_string(), "read".to_string()];
+
+        // Ignored unless validation is requested
+        let options = ResolveOptions::new(Direction::Request, "create");
+        assert!(resolve(&schema, &options).is_ok());
+
+        let options = options.validate_operations(allowed);
+        let result = resolve(&schema, &options);
+        assert!(matches!(
+            result,
+            Err(ResolveError::UnknownOperation { path, op })
+                if path == "/properties/id/ucp_response" && op == "delete"
+        ));
+    }
 }
 
 // === Operation Normalization Tests ===
@@ -202,6 +261,94 @@ mod operation_normalization {
         let result = resolve(&schema, &options).unwrap();
         assert!(result["properties"].get("id").is_none());
     }
+
+    fn write_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "ucp_request": { "create": "omit", "update": "required" }
+                },
+                "name": { "type": "string" },
+                "token": {
+                    "type": "string",
+                    "ucp_request": { "create": "required", "update": "required" }
+                },
+                "server_only": { "type": "string", "ucp_request": "omit" }
+            }
+        })
+    }
+
+    #[test]
+    fn multiple_operations_resolve_superset() {
+        let options = ResolveOptions::new(Direction::Request, "create")
+            .also_operations(vec!["update".to_string()]);
+        let result = resolve(&write_schema(), &options).unwrap();
+
+        // Kept if any operation keeps it, omitted only if all omit it
+        assert!(result["properties"].get("id").is_some());
+        assert!(result["properties"].get("server_only").is_none());
+        // Required only when every operation requires it
+        assert_eq!(result["required"], json!(["name", "token"]));
+    }
+
+    #[test]
+    fn multiple_operations_required_if_any() {
+        let options = ResolveOptions::new(Direction::Request, "create")
+            .also_operations(vec!["UPDATE".to_string()])
+            .required_policy(RequiredPolicy::Any);
+        let result = resolve(&write_schema(), &options).unwrap();
+        assert_eq!(result["required"], json!(["name", "id", "token"]));
+    }
+
+    #[test]
+    fn multiple_operations_combine_constraints_loosest_wins() {
+        let schema = json!({
+            "type": "object",
+            "ucp_strict": { "create": true },
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "ucp_request": {
+                        "create": { "maxLength": 4, "pattern": "^[A-Z]+$" },
+                        "update": { "maxLength": 8, "pattern": "^[a-z]+$" }
+                    }
+                },
+                "note": {
+                    "type": "string",
+                    "ucp_request": { "create": "omit", "update": { "transition": {
+                        "from": "omit", "to": "optional", "description": "coming"
+                    } } }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create")
+            .also_operations(vec!["update".to_string()]);
+        let (result, omissions) = resolve_explained(&schema, &options).unwrap();
+
+        // Update leaves strictness unset, so the object stays open
+        assert!(result.get("additionalProperties").is_none());
+        assert_eq!(result["properties"]["code"]["maxLength"], 8);
+        assert!(result["properties"]["code"].get("pattern").is_none());
+        // Every operation that omitted the field is reported
+        assert_eq!(
+            omissions,
+            vec![
+                Omission {
+                    path: "/properties/note".to_string(),
+                    annotation: "/properties/note/ucp_request".to_string(),
+                    matched: OmitMatch::Operation("create".to_string()),
+                },
+                Omission {
+                    path: "/properties/note".to_string(),
+                    annotation: "/properties/note/ucp_request".to_string(),
+                    matched: OmitMatch::Operation("update".to_string()),
+                },
+            ]
+        );
+    }
 }
 
 // === Transformation Tests ===
@@ -209,6 +356,471 @@ mod operation_normalization {
 mod transformation {
     use super::*;
 
+    #[test]
+    fn ucp_defaults_apply_to_unannotated_fields() {
+        let schema = json!({
+            "type": "object",
+            "ucp_defaults": { "ucp_request": { "read": "omit" } },
+            "required": ["id", "name"],
+            "properties": {
+                "id": { "type": "string", "ucp_request": { "read": "required" } },
+                "name": { "type": "string" },
+                "note": { "type": "string", "ucp_request": { "create": "optional" } },
+                "address": {
+                    "type": "object",
+                    "properties": { "line1": { "type": "string" } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "read");
+        let result = resolve(&schema, &options).unwrap();
+        // Only the field annotated for read survives; defaults aren't output
+        assert_eq!(
+            result["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["id"]
+        );
+        assert_eq!(result["required"], json!(["id"]));
+        assert!(result.get("ucp_defaults").is_none());
+
+        // Other operations and the other direction are unaffected
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["properties"].as_object().unwrap().len(), 4);
+        let options = ResolveOptions::new(Direction::Response, "read");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["properties"].as_object().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn min_items_annotation_sets_min_items_per_operation() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "line_items": {
+                    "type": "array",
+                    "items": { "type": "object" },
+                    "ucp_request": {
+                        "create": { "min_items": 1, "visibility": "required" },
+                        "update": { "min_items": 0 }
+                    }
+                },
+                "tags": {
+                    "type": "array",
+                    "minItems": 2,
+                    "ucp_request": { "create": { "min_items": 1 } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["properties"]["line_items"]["minItems"], json!(1));
+        assert_eq!(result["required"], json!(["line_items"]));
+        assert!(result["properties"]["line_items"]
+            .get("ucp_request")
+            .is_none());
+        // A stricter declared minItems is kept
+        assert_eq!(result["properties"]["tags"]["minItems"], json!(2));
+
+        let options = ResolveOptions::new(Direction::Request, "update");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["properties"]["line_items"]["minItems"], json!(0));
+        assert!(result.get("required").is_none());
+
+        let options = ResolveOptions::new(Direction::Response, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert!(result["properties"]["line_items"].get("minItems").is_none());
+    }
+
+    #[test]
+    fn min_items_annotation_must_be_non_negative_integer() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "line_items": {
+                    "type": "array",
+                    "ucp_request": { "create": { "min_items": -1 } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(err, ResolveError::InvalidMinItems { .. }));
+        assert!(err
+            .to_string()
+            .contains("/properties/line_items/ucp_request/create/min_items"));
+    }
+
+    #[test]
+    fn keyword_overrides_apply_per_operation() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "coupon_code": {
+                    "type": "string",
+                    "pattern": "^[A-Za-z0-9]+$",
+                    "ucp_request": {
+                        "create": { "visibility": "required", "pattern": "^[A-Z0-9]{8}$" },
+                        "update": { "maxLength": 16 }
+                    }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        let coupon = &result["properties"]["coupon_code"];
+        // Both patterns hold: the override can't replace the declared one
+        assert_eq!(coupon["pattern"], "^[A-Za-z0-9]+$");
+        assert_eq!(coupon["allOf"], json!([{ "pattern": "^[A-Z0-9]{8}$" }]));
+        assert_eq!(result["required"], json!(["coupon_code"]));
+
+        let options = ResolveOptions::new(Direction::Request, "update");
+        let result = resolve(&schema, &options).unwrap();
+        let coupon = &result["properties"]["coupon_code"];
+        assert_eq!(coupon["pattern"], "^[A-Za-z0-9]+$");
+        assert!(coupon.get("allOf").is_none());
+        assert_eq!(coupon["maxLength"], json!(16));
+        assert!(result.get("required").is_none());
+
+        let options = ResolveOptions::new(Direction::Request, "read");
+        let result = resolve(&schema, &options).unwrap();
+        assert!(result["properties"]["coupon_code"]
+            .get("maxLength")
+            .is_none());
+    }
+
+    #[test]
+    fn explained_omissions_name_their_annotation() {
+        let schema = json!({
+            "type": "object",
+            "ucp_defaults": { "ucp_request": "omit" },
+            "properties": {
+                "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                "status": { "type": "string", "ucp_request": { "update": "required" } },
+                "name": { "type": "string", "ucp_request": "optional" },
+                "meta": {
+                    "type": "object",
+                    "ucp_request": { "create": "optional" },
+                    "properties": {
+                        "internal": { "type": "string", "ucp_request": "omit" }
+                    }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let (resolved, omissions) = resolve_explained(&schema, &options).unwrap();
+        assert_eq!(resolved, resolve(&schema, &options).unwrap());
+        assert_eq!(
+            omissions,
+            vec![
+                Omission {
+                    path: "/properties/id".to_string(),
+                    annotation: "/properties/id/ucp_request".to_string(),
+                    matched: OmitMatch::Operation("create".to_string()),
+                },
+                Omission {
+                    path: "/properties/status".to_string(),
+                    annotation: "/ucp_defaults/ucp_request".to_string(),
+                    matched: OmitMatch::Shorthand,
+                },
+                Omission {
+                    path: "/properties/meta/properties/internal".to_string(),
+                    annotation: "/properties/meta/properties/internal/ucp_request".to_string(),
+                    matched: OmitMatch::Shorthand,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stats_count_visibility_decisions() {
+        let schema = json!({
+            "type": "object",
+            "required": ["sku"],
+            "properties": {
+                "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                "sku": { "type": "string" },
+                "name": { "type": "string", "ucp_request": "required" },
+                "note": { "type": "string", "ucp_request": { "create": "optional" } },
+                "legacy": { "type": "string", "ucp_request": "deprecate" }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let (resolved, stats) = resolve_with_stats(&schema, &options).unwrap();
+        assert_eq!(resolved, resolve(&schema, &options).unwrap());
+        assert_eq!(
+            stats,
+            ResolveStats {
+                omitted: 1,
+                required: 1,
+                optional: 2,
+                included: 1,
+                // The omitted `id` is never visited, so its annotation is not counted
+                annotations_stripped: 3,
+                properties_total: 5,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(stats).unwrap(),
+            json!({
+                "omitted": 1,
+                "required": 1,
+                "optional": 2,
+                "included": 1,
+                "annotations_stripped": 3,
+                "properties_total": 5
+            })
+        );
+
+        let (detailed, omissions, detailed_stats) = resolve_detailed(&schema, &options).unwrap();
+        assert_eq!(detailed, resolved);
+        assert_eq!(omissions, resolve_explained(&schema, &options).unwrap().1);
+        assert_eq!(detailed_stats, stats);
+    }
+
+    #[test]
+    fn normalize_types_dedupes_sorts_and_collapses() {
+        let schema = json!({
+            "type": ["object"],
+            "properties": {
+                "name": { "type": ["string", "null", "string"] },
+                "amount": { "type": ["null", "number", "integer"] },
+                "tags": { "type": "array", "items": { "type": ["string", "string"] } },
+                "odd": { "type": ["string", 1] }
+            }
+        });
+
+        let plain = ResolveOptions::new(Direction::Request, "create");
+        assert_eq!(resolve(&schema, &plain).unwrap(), schema);
+
+        let options = plain.normalize_types(true);
+        let result = resolve(&schema, &options).unwrap();
+        let props = &result["properties"];
+        assert_eq!(result["type"], "object");
+        assert_eq!(props["name"]["type"], json!(["string", "null"]));
+        assert_eq!(
+            props["amount"]["type"],
+            json!(["integer", "number", "null"])
+        );
+        assert_eq!(props["tags"]["items"]["type"], "string");
+        assert_eq!(props["odd"]["type"], json!(["string", 1]));
+    }
+
+    #[test]
+    fn property_names_override_per_direction() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "metadata": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "propertyNames": { "pattern": "^(sys_)?[a-z_]+$" },
+                    "ucp_request": {
+                        "create": { "propertyNames": { "pattern": "^[a-z_]+$" } }
+                    }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+        let result = resolve(&schema, &options).unwrap();
+        let metadata = &result["properties"]["metadata"];
+        assert_eq!(
+            metadata["propertyNames"],
+            json!({ "pattern": "^(sys_)?[a-z_]+$" })
+        );
+        assert_eq!(
+            metadata["allOf"],
+            json!([{ "propertyNames": { "pattern": "^[a-z_]+$" } }])
+        );
+        assert_eq!(
+            metadata["additionalProperties"],
+            json!({ "type": "string" })
+        );
+
+        let options = ResolveOptions::new(Direction::Response, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(
+            result["properties"]["metadata"]["propertyNames"],
+            json!({ "pattern": "^(sys_)?[a-z_]+$" })
+        );
+    }
+
+    #[test]
+    fn keyword_overrides_reject_unlisted_keywords() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "coupon_code": {
+                    "type": "string",
+                    "ucp_request": { "create": { "pattern": "^X", "type": "integer" } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::InvalidOverrideKeyword { ref keyword, .. } if keyword == "type"
+        ));
+    }
+
+    #[test]
+    fn keyword_overrides_never_loosen_bounds() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "note": {
+                    "type": "string",
+                    "maxLength": 100,
+                    "minLength": 2,
+                    "ucp_request": { "create": { "maxLength": 500, "minLength": 5 } }
+                },
+                "quantity": {
+                    "type": "integer",
+                    "minimum": 10,
+                    "ucp_request": { "create": { "minimum": 5, "maximum": 99 } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        let note = &result["properties"]["note"];
+        assert_eq!(
+            (&note["maxLength"], &note["minLength"]),
+            (&json!(100), &json!(5))
+        );
+        let quantity = &result["properties"]["quantity"];
+        assert_eq!(
+            (&quantity["minimum"], &quantity["maximum"]),
+            (&json!(10), &json!(99))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn keyword_overrides_never_accept_what_the_schema_rejects() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "pattern": "^[A-Z]{8}$",
+                    "ucp_request": { "create": { "pattern": ".*" } }
+                },
+                "size": {
+                    "type": "integer",
+                    "multipleOf": 4,
+                    "ucp_request": { "create": { "multipleOf": 2 } }
+                },
+                "tier": {
+                    "enum": ["gold", "silver"],
+                    "ucp_request": { "create": { "enum": ["gold"] } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        for payload in [json!({ "code": "lowercase" }), json!({ "size": 6 })] {
+            assert!(ucp_schema::validate_against_schema(&schema, &payload).is_err());
+            assert!(ucp_schema::validate_against_schema(&result, &payload).is_err());
+        }
+        let narrowed = json!({ "tier": "silver" });
+        assert!(ucp_schema::validate_against_schema(&result, &narrowed).is_err());
+        assert!(ucp_schema::validate_against_schema(
+            &result,
+            &json!({ "code": "ABCDEFGH", "size": 8, "tier": "gold" })
+        )
+        .is_ok());
+
+        // An enum override may only narrow the declared enum
+        let mut widened = schema.clone();
+        widened["properties"]["tier"]["ucp_request"]["create"]["enum"] =
+            json!(["gold", "silver", "bronze"]);
+        let err = resolve(&widened, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::InvalidOverrideKeyword { ref keyword, .. } if keyword == "enum"
+        ));
+        assert!(err.to_string().contains("\"bronze\""));
+    }
+
+    #[test]
+    fn keyword_overrides_reject_wrong_value_types() {
+        let schema = json!({
+            "properties": {
+                "note": {
+                    "type": "string",
+                    "ucp_request": { "create": { "maxLength": "x" } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::InvalidOverrideKeyword { ref keyword, .. } if keyword == "maxLength"
+        ));
+        assert!(err.to_string().contains("must be a non-negative integer"));
+    }
+
+    #[test]
+    fn ucp_defaults_apply_only_to_declaring_object() {
+        let schema = json!({
+            "type": "object",
+            "ucp_defaults": { "ucp_response": "optional" },
+            "required": ["buyer"],
+            "properties": {
+                "buyer": {
+                    "type": "object",
+                    "required": ["email"],
+                    "properties": { "email": { "type": "string" } }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Response, "read");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["required"], json!([]));
+        assert_eq!(result["properties"]["buyer"]["required"], json!(["email"]));
+    }
+
+    #[test]
+    fn ucp_defaults_must_be_an_object() {
+        let schema = json!({
+            "type": "object",
+            "ucp_defaults": "omit",
+            "properties": { "id": { "type": "string" } }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::InvalidAnnotationType { ref path, .. } if path == "/ucp_defaults"
+        ));
+
+        let schema = json!({
+            "type": "object",
+            "ucp_defaults": { "ucp_request": "hidden" },
+            "properties": { "id": { "type": "string" } }
+        });
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(err, ResolveError::UnknownVisibility { .. }));
+    }
+
     #[test]
     fn omit_removes_field_from_properties() {
         let schema = json!({
@@ -339,6 +951,49 @@ mod transformation {
 mod required_array {
     use super::*;
 
+    #[test]
+    fn constraint_only_required_left_intact() {
+        // `required` without `properties`: nothing to annotate, so the list is
+        // kept exactly as written for every direction and operation
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "minProperties": 2
+        });
+        for (direction, op) in [
+            (Direction::Request, "create"),
+            (Direction::Request, "update"),
+            (Direction::Response, "read"),
+        ] {
+            let options = ResolveOptions::new(direction, op).strict(true);
+            let result = resolve(&schema, &options).unwrap();
+            assert_eq!(result["required"], json!(["id", "name"]));
+            assert_eq!(result["minProperties"], json!(2));
+        }
+    }
+
+    #[test]
+    fn constraint_only_allof_branch_required_left_intact() {
+        // Visibility is decided where the property is declared; a sibling
+        // constraint-only branch keeps its `required` untouched
+        let schema = json!({
+            "allOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string", "ucp_request": { "update": "required" } }
+                    }
+                },
+                { "required": ["id"] }
+            ]
+        });
+        let options = ResolveOptions::new(Direction::Request, "update");
+        let result = resolve(&schema, &options).unwrap();
+
+        assert_eq!(result["allOf"][0]["required"], json!(["id"]));
+        assert_eq!(result["allOf"][1], json!({ "required": ["id"] }));
+    }
+
     #[test]
     fn omitted_field_removed_from_required() {
         let schema = json!({
@@ -395,6 +1050,30 @@ mod required_array {
         assert!(required.contains(&json!("name")));
         assert!(required.contains(&json!("email")));
     }
+
+    #[test]
+    fn emptied_required_kept_unless_dropped() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                "name": { "type": "string" }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["required"], json!([]));
+
+        let options = options.drop_empty_required(true);
+        let result = resolve(&schema, &options).unwrap();
+        assert!(result.get("required").is_none());
+
+        // A non-empty array is unaffected
+        let options = ResolveOptions::new(Direction::Request, "update").drop_empty_required(true);
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["required"], json!(["id"]));
+    }
 }
 
 // === Recursion Tests (Phase 2) ===
@@ -440,6 +1119,137 @@ mod recursion {
             .is_none());
     }
 
+    #[test]
+    fn inherit_follows_parent_through_nested_chain() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "buyer": {
+                    "type": "object",
+                    "ucp_request": { "create": "required", "update": "optional" },
+                    "properties": {
+                        "address": {
+                            "type": "object",
+                            "ucp_request": "inherit",
+                            "required": ["line1"],
+                            "properties": {
+                                "line1": { "type": "string", "ucp_request": "inherit" },
+                                "line2": { "type": "string" }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["required"], json!(["buyer"]));
+        let buyer = &result["properties"]["buyer"];
+        assert_eq!(buyer["required"], json!(["address"]));
+        let address = &buyer["properties"]["address"];
+        assert_eq!(address["required"], json!(["line1"]));
+        assert!(address["properties"].get("line2").is_some());
+        assert!(address.get("ucp_request").is_none());
+
+        // optional at the top propagates down, dropping line1 from required
+        let options = ResolveOptions::new(Direction::Request, "update");
+        let result = resolve(&schema, &options).unwrap();
+        let address = &result["properties"]["buyer"]["properties"]["address"];
+        assert!(result.get("required").is_none());
+        assert!(result["properties"]["buyer"].get("required").is_none());
+        assert_eq!(address["required"], json!([]));
+    }
+
+    #[test]
+    fn inherit_omit_omits_child_of_future_field() {
+        // include_future keeps the omitted parent; an inheriting child is
+        // still omitted because the parent's current visibility is omit
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "loyalty": {
+                    "type": "object",
+                    "ucp_response": {
+                        "transition": { "from": "omit", "to": "optional", "description": "Coming soon" }
+                    },
+                    "properties": {
+                        "points": { "type": "integer", "ucp_response": "inherit" },
+                        "tier": { "type": "string" }
+                    }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Response, "read").include_future(true);
+        let result = resolve(&schema, &options).unwrap();
+        let loyalty = &result["properties"]["loyalty"]["properties"];
+        assert!(loyalty.get("points").is_none());
+        assert!(loyalty.get("tier").is_some());
+    }
+
+    #[test]
+    fn inherit_without_parent_property_is_include() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "string", "ucp_request": "inherit" },
+                "tags": {
+                    "type": "array",
+                    "ucp_request": "omit",
+                    "items": { "type": "string" }
+                }
+            },
+            "$defs": {
+                "note": {
+                    "type": "object",
+                    "required": ["text"],
+                    "properties": { "text": { "type": "string", "ucp_request": "inherit" } }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["required"], json!(["id"]));
+        assert!(result["properties"].get("id").is_some());
+        // $defs start over: nothing encloses a definition
+        assert_eq!(result["$defs"]["note"]["required"], json!(["text"]));
+    }
+
+    #[test]
+    fn inherit_passes_through_array_items() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "line_items": {
+                    "type": "array",
+                    "ucp_request": { "create": "omit" },
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sku": { "type": "string", "ucp_request": "inherit" }
+                        }
+                    }
+                },
+                "discounts": {
+                    "type": "array",
+                    "ucp_request": "deprecate",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "code": { "type": "string", "ucp_request": "inherit" }
+                        }
+                    }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+        assert!(result["properties"].get("line_items").is_none());
+        let code = &result["properties"]["discounts"]["items"]["properties"]["code"];
+        assert_eq!(code["deprecated"], true);
+    }
+
     #[test]
     fn array_items() {
         let schema = json!({
@@ -676,31 +1486,103 @@ mod composition {
     }
 
     #[test]
-    fn oneof_transforms_each_branch() {
+    fn oneof_transforms_each_branch() {
+        let schema = json!({
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": { "const": "credit_card" },
+                        "number": { "type": "string", "ucp_request": "required" }
+                    }
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": { "const": "bank_account" },
+                        "routing": { "type": "string", "ucp_request": "required" }
+                    }
+                }
+            ]
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+
+        // Both branches transformed
+        assert!(result["oneOf"][0]["properties"].get("number").is_some());
+        assert!(result["oneOf"][1]["properties"].get("routing").is_some());
+    }
+
+    #[test]
+    fn composition_object_instead_of_array_does_not_leak_annotations() {
+        // Malformed: allOf/anyOf hold a single object. Lint reports E010; the
+        // resolver still applies and strips the annotations inside.
+        let schema = json!({
+            "allOf": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "ucp_request": "omit" },
+                    "name": { "type": "string", "ucp_request": "required" }
+                }
+            },
+            "anyOf": { "properties": { "note": { "ucp_request": "omit" } } }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+
+        assert!(result["allOf"]["properties"].get("id").is_none());
+        assert_eq!(result["allOf"]["required"], json!(["name"]));
+        assert!(result["anyOf"]["properties"].get("note").is_none());
+        assert!(!result.to_string().contains("ucp_request"));
+    }
+
+    #[test]
+    fn anyof_branch_omitted_for_operation() {
+        let schema = json!({
+            "anyOf": [
+                { "$ref": "card.json" },
+                { "$ref": "wallet.json", "ucp_request": { "create": "omit" } }
+            ]
+        });
+
+        // The omitted branch keeps its slot as `false` so indices still match
+        let create = ResolveOptions::new(Direction::Request, "create");
+        let (resolved, omissions) = resolve_explained(&schema, &create).unwrap();
+        assert_eq!(resolved["anyOf"], json!([{ "$ref": "card.json" }, false]));
+        assert_eq!(
+            omissions,
+            vec![Omission {
+                path: "/anyOf/1".to_string(),
+                annotation: "/anyOf/1/ucp_request".to_string(),
+                matched: OmitMatch::Operation("create".to_string()),
+            }]
+        );
+
+        let update = resolve(&schema, &ResolveOptions::new(Direction::Request, "update")).unwrap();
+        assert_eq!(
+            update["anyOf"],
+            json!([{ "$ref": "card.json" }, { "$ref": "wallet.json" }])
+        );
+    }
+
+    #[test]
+    fn oneof_with_every_branch_omitted_is_error() {
         let schema = json!({
-            "oneOf": [
-                {
-                    "type": "object",
-                    "properties": {
-                        "type": { "const": "credit_card" },
-                        "number": { "type": "string", "ucp_request": "required" }
-                    }
-                },
-                {
-                    "type": "object",
-                    "properties": {
-                        "type": { "const": "bank_account" },
-                        "routing": { "type": "string", "ucp_request": "required" }
-                    }
+            "properties": {
+                "method": {
+                    "oneOf": [
+                        { "type": "string", "ucp_request": "omit" },
+                        { "type": "integer", "ucp_request": { "create": "omit" } }
+                    ]
                 }
-            ]
+            }
         });
         let options = ResolveOptions::new(Direction::Request, "create");
-        let result = resolve(&schema, &options).unwrap();
-
-        // Both branches transformed
-        assert!(result["oneOf"][0]["properties"].get("number").is_some());
-        assert!(result["oneOf"][1]["properties"].get("routing").is_some());
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::EmptyComposition { ref path } if path == "/properties/method/oneOf"
+        ));
     }
 }
 
@@ -1460,6 +2342,49 @@ mod integration {
         assert!(result["properties"].get("totals").is_some());
     }
 
+    #[test]
+    fn draft7_tuple_additional_items() {
+        let schema = load_fixture("draft7_tuple.json");
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &options).unwrap();
+
+        let adjustments = &result["properties"]["adjustments"];
+        // Positional items are resolved
+        assert!(adjustments["items"][1]["properties"].get("id").is_none());
+        // additionalItems is resolved like additionalProperties
+        let extra = &adjustments["additionalItems"];
+        assert!(extra["properties"].get("internal_ref").is_none());
+        assert!(extra["properties"].get("code").is_some());
+        assert!(extra.get("ucp_request").is_none());
+
+        let options = ResolveOptions::new(Direction::Request, "update");
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(
+            result["properties"]["adjustments"]["items"][1]["required"],
+            json!(["id"])
+        );
+    }
+
+    #[test]
+    fn draft7_tuple_strict_closes_items() {
+        let schema = load_fixture("draft7_tuple.json");
+        let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+        let result = resolve(&schema, &options).unwrap();
+
+        let adjustments = &result["properties"]["adjustments"];
+        assert!(adjustments["items"][0]
+            .get("additionalProperties")
+            .is_none());
+        assert_eq!(
+            adjustments["items"][1]["additionalProperties"],
+            json!(false)
+        );
+        assert_eq!(
+            adjustments["additionalItems"]["additionalProperties"],
+            json!(false)
+        );
+    }
+
     #[test]
     fn invalid_annotation_type_from_file() {
         let schema = load_fixture("invalid/bad_annotation_type.json");
@@ -1506,6 +2431,87 @@ mod strict_mode {
         assert!(result.get("additionalProperties").is_none());
     }
 
+    #[test]
+    fn ucp_strict_sets_strictness_per_operation() {
+        let schema = json!({
+            "type": "object",
+            "ucp_strict": { "create": true, "update": false },
+            "properties": {
+                "buyer": {
+                    "type": "object",
+                    "properties": { "email": { "type": "string" } }
+                }
+            }
+        });
+
+        let create = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&schema, &create).unwrap();
+        assert_eq!(result["additionalProperties"], json!(false));
+        assert_eq!(
+            result["properties"]["buyer"]["additionalProperties"],
+            json!(false)
+        );
+        assert!(result.get("ucp_strict").is_none());
+
+        // false overrides the global flag
+        let update = ResolveOptions::new(Direction::Request, "update").strict(true);
+        let result = resolve(&schema, &update).unwrap();
+        assert!(result.get("additionalProperties").is_none());
+        assert!(result["properties"]["buyer"]
+            .get("additionalProperties")
+            .is_none());
+
+        // Unlisted operations keep the global flag
+        let read = ResolveOptions::new(Direction::Response, "read").strict(true);
+        let result = resolve(&schema, &read).unwrap();
+        assert_eq!(result["additionalProperties"], json!(false));
+    }
+
+    #[test]
+    fn nested_ucp_strict_overrides_ancestor() {
+        let schema = json!({
+            "type": "object",
+            "ucp_strict": true,
+            "properties": {
+                "metadata": {
+                    "type": "object",
+                    "ucp_strict": false,
+                    "properties": {
+                        "source": {
+                            "type": "object",
+                            "properties": { "name": { "type": "string" } }
+                        }
+                    }
+                }
+            },
+            "$defs": {
+                "note": { "type": "object", "ucp_strict": "yes" }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let err = resolve(&schema, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveError::InvalidStrictAnnotation { ref path, .. } if path == "/$defs/note/ucp_strict"
+        ));
+
+        let mut schema = schema;
+        schema["$defs"]["note"]["ucp_strict"] = json!({ "create": true });
+        let result = resolve(&schema, &options).unwrap();
+        assert_eq!(result["additionalProperties"], json!(false));
+        let metadata = &result["properties"]["metadata"];
+        assert!(metadata.get("additionalProperties").is_none());
+        assert!(metadata["properties"]["source"]
+            .get("additionalProperties")
+            .is_none());
+        assert!(metadata.get("ucp_strict").is_none());
+        assert_eq!(
+            result["$defs"]["note"]["additionalProperties"],
+            json!(false)
+        );
+        assert!(result["$defs"]["note"].get("ucp_strict").is_none());
+    }
+
     #[test]
     fn injects_additional_properties_false() {
         // Object schemas without additionalProperties get false added in strict mode
@@ -1690,6 +2696,102 @@ mod strict_mode {
         assert_eq!(result["additionalProperties"], json!(true));
     }
 
+    #[test]
+    fn closes_nested_objects_under_every_kept_visibility() {
+        // Whatever visibility keeps a field, its subschema is still recursed for
+        // closing: a required object nested under it must come out closed.
+        for annotation in [json!("required"), json!("optional"), Value::Null] {
+            let mut parent = json!({
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "type": "object",
+                        "properties": { "city": { "type": "string" } },
+                        "ucp_request": "required"
+                    }
+                }
+            });
+            if !annotation.is_null() {
+                parent["ucp_request"] = annotation.clone();
+            }
+            let schema = json!({
+                "type": "object",
+                "properties": { "buyer": parent }
+            });
+            let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+            let result = resolve(&schema, &options).unwrap();
+
+            let buyer = &result["properties"]["buyer"];
+            assert_eq!(
+                buyer["additionalProperties"],
+                json!(false),
+                "buyer kept via {annotation}"
+            );
+            assert_eq!(
+                buyer["properties"]["address"]["additionalProperties"],
+                json!(false),
+                "address under buyer kept via {annotation}"
+            );
+            assert_eq!(buyer["required"], json!(["address"]));
+        }
+    }
+
+    #[test]
+    fn closes_surfaced_omitted_fields() {
+        // include_future keeps an omitted field for schema transitions; it is
+        // still a kept field and gets closed like any other.
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "legacy": {
+                    "type": "object",
+                    "properties": { "code": { "type": "string" } },
+                    "ucp_request": {
+                        "create": { "transition": { "from": "optional", "to": "omit", "description": "Removed" } }
+                    }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create")
+            .strict(true)
+            .include_future(true);
+        let result = resolve(&schema, &options).unwrap();
+
+        assert_eq!(
+            result["properties"]["legacy"]["additionalProperties"],
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn closes_contains_subschema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "line_items": {
+                    "type": "array",
+                    "contains": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string", "ucp_request": { "create": "omit" } },
+                            "sku": { "type": "string", "ucp_request": "required" }
+                        }
+                    },
+                    "minContains": 1
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+        let result = resolve(&schema, &options).unwrap();
+
+        let contains = &result["properties"]["line_items"]["contains"];
+        assert_eq!(contains["additionalProperties"], json!(false));
+        assert!(contains["properties"].get("id").is_none());
+        assert_eq!(contains["required"], json!(["sku"]));
+        assert!(contains["properties"]["sku"].get("ucp_request").is_none());
+        assert_eq!(result["properties"]["line_items"]["minContains"], json!(1));
+    }
+
     #[test]
     fn detects_object_by_properties_key() {
         // Even without "type": "object", presence of "properties" should trigger strict mode
@@ -1703,4 +2805,340 @@ mod strict_mode {
 
         assert_eq!(result["additionalProperties"], json!(false));
     }
+
+    #[test]
+    fn detects_object_by_pattern_properties() {
+        // patternProperties-only object: closed, and matching keys stay allowed
+        // because additionalProperties only covers unmatched names.
+        let schema = json!({
+            "patternProperties": {
+                "^x-": {
+                    "type": "object",
+                    "properties": { "value": { "type": "string" } }
+                }
+            }
+        });
+        let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+        let result = resolve(&schema, &options).unwrap();
+
+        assert_eq!(result["additionalProperties"], json!(false));
+        assert_eq!(
+            result["patternProperties"]["^x-"]["additionalProperties"],
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn detects_object_by_other_object_keywords() {
+        for keyword in ["propertyNames", "minProperties", "maxProperties"] {
+            let value = if keyword == "propertyNames" {
+                json!({ "pattern": "^[a-z]+$" })
+            } else {
+                json!(1)
+            };
+            let schema = json!({ keyword: value });
+            let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+            let result = resolve(&schema, &options).unwrap();
+
+            assert_eq!(result["additionalProperties"], json!(false), "{keyword}");
+        }
+    }
+}
+
+// === Vendor Extension Tests ===
+
+mod vendor_extensions {
+    use super::*;
+    #[cfg(feature = "full")]
+    use ucp_schema::bundle_refs;
+
+    fn vendor_schema() -> Value {
+        json!({
+            "type": "object",
+            "x-ui-form": { "layout": "two-column" },
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "x-ui-widget": "text",
+                    "ucp_request": "required"
+                },
+                "internal": {
+                    "type": "object",
+                    "x-ui-widget": "hidden",
+                    "ucp_request": "omit",
+                    "properties": { "note": { "type": "string", "x-ui-widget": "textarea" } }
+                },
+                "payment": {
+                    "allOf": [
+                        { "$ref": "#/$defs/card" },
+                        { "x-ui-group": "billing", "properties": { "cvv": { "type": "string" } } }
+                    ]
+                }
+            },
+            "$defs": {
+                "card": {
+                    "type": "object",
+                    "x-ui-widget": "card-input",
+                    "properties": {
+                        "number": { "type": "string", "x-mask": "####", "ucp_response": "omit" }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn x_keywords_survive_resolve() {
+        let options = ResolveOptions::new(Direction::Request, "create").strict(true);
+        let result = resolve(&vendor_schema(), &options).unwrap();
+
+        assert_eq!(result["x-ui-form"], json!({ "layout": "two-column" }));
+        assert_eq!(result["properties"]["name"]["x-ui-widget"], "text");
+        assert!(result["properties"]["name"].get("ucp_request").is_none());
+        assert_eq!(
+            result["properties"]["payment"]["allOf"][1]["x-ui-group"],
+            "billing"
+        );
+        assert_eq!(result["$defs"]["card"]["x-ui-widget"], "card-input");
+        assert_eq!(
+            result["$defs"]["card"]["properties"]["number"]["x-mask"],
+            "####"
+        );
+        assert!(result["$defs"]["card"]["properties"]["number"]
+            .get("ucp_response")
+            .is_none());
+    }
+
+    #[test]
+    fn omitted_property_takes_its_x_keywords_with_it() {
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let result = resolve(&vendor_schema(), &options).unwrap();
+
+        // The omitted field disappears whole; nothing of it leaks elsewhere.
+        assert!(result["properties"].get("internal").is_none());
+        assert!(!result.to_string().contains("textarea"));
+
+        // Where the field is kept, its nested vendor keywords are intact.
+        let options = ResolveOptions::new(Direction::Response, "read");
+        let result = resolve(&vendor_schema(), &options).unwrap();
+        assert_eq!(result["properties"]["internal"]["x-ui-widget"], "hidden");
+        assert_eq!(
+            result["properties"]["internal"]["properties"]["note"]["x-ui-widget"],
+            "textarea"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn x_keywords_survive_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("types.json"),
+            r#"{
+                "$defs": {
+                    "money": { "type": "integer", "x-currency-field": true }
+                }
+            }"#,
+        )
+        .unwrap();
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "total": { "$ref": "types.json#/$defs/money", "x-ui-widget": "money" }
+            }
+        });
+
+        bundle_refs(&mut schema, dir.path()).unwrap();
+
+        let total = &schema["properties"]["total"];
+        assert_eq!(total["x-ui-widget"], "money");
+        assert_eq!(total["x-currency-field"], true);
+        assert_eq!(total["type"], "integer");
+    }
+
+    #[test]
+    fn meta_schema_declarations_survive_resolve() {
+        let mut schema = vendor_schema();
+        let vocabulary = json!({
+            "https://json-schema.org/draft/2020-12/vocab/core": true,
+            "https://json-schema.org/draft/2020-12/vocab/applicator": true,
+            "https://ucp.dev/vocab/annotations": false
+        });
+        schema["$schema"] = json!("https://ucp.dev/schemas/meta.json");
+        schema["$vocabulary"] = vocabulary.clone();
+
+        for strict in [false, true] {
+            for direction in [Direction::Request, Direction::Response] {
+                let options = ResolveOptions::new(direction, "create").strict(strict);
+                let result = resolve(&schema, &options).unwrap();
+                assert_eq!(result["$schema"], "https://ucp.dev/schemas/meta.json");
+                assert_eq!(result["$vocabulary"], vocabulary);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn meta_schema_declarations_survive_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("types.json"),
+            r#"{
+                "$schema": "https://json-schema.org/draft/07/schema#",
+                "$defs": { "money": { "type": "integer" } }
+            }"#,
+        )
+        .unwrap();
+        let vocabulary = json!({
+            "https://json-schema.org/draft/2020-12/vocab/core": true,
+            "https://ucp.dev/vocab/annotations": false
+        });
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$vocabulary": vocabulary,
+            "type": "object",
+            "properties": { "total": { "$ref": "types.json#/$defs/money" } }
+        });
+
+        bundle_refs(&mut schema, dir.path()).unwrap();
+
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(schema["$vocabulary"], vocabulary);
+        assert_eq!(schema["properties"]["total"]["type"], "integer");
+    }
+}
+
+// === Annotation Stripping Tests ===
+
+mod annotation_stripping {
+    use super::*;
+    use ucp_schema::strip_annotations;
+
+    /// An object subschema carrying every UCP annotation, on itself and on
+    /// its one property.
+    fn annotated(field: &str) -> Value {
+        json!({
+            "type": "object",
+            "ucp_request": "optional",
+            "ucp_defaults": { "ucp_response": "optional" },
+            "ucp_strict": { "create": true },
+            "properties": {
+                field: {
+                    "type": "string",
+                    "ucp_request": { "create": "required", "update": "optional" },
+                    "ucp_response": "required"
+                }
+            }
+        })
+    }
+
+    /// Annotated subschemas under every composition, conditional, and
+    /// applicator keyword.
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string" },
+                "tags": {
+                    "type": "array",
+                    "items": annotated("items_field"),
+                    "prefixItems": [annotated("prefix_field")],
+                    "contains": annotated("contains_field")
+                }
+            },
+            "allOf": [annotated("all_field")],
+            "anyOf": [annotated("any_field")],
+            "oneOf": [annotated("one_field")],
+            "not": annotated("not_field"),
+            "if": annotated("if_field"),
+            "then": annotated("then_field"),
+            "else": annotated("else_field"),
+            "dependentSchemas": { "kind": annotated("dependent_field") },
+            "patternProperties": { "^x-": annotated("pattern_field") },
+            "additionalProperties": annotated("additional_field"),
+            "unevaluatedProperties": annotated("unevaluated_field"),
+            "propertyNames": { "maxLength": 32, "ucp_response": "omit" },
+            "$defs": { "def": annotated("def_field") }
+        })
+    }
+
+    /// Pointers to every `ucp_*` key left in `value`.
+    fn leftover_annotations(value: &Value, pointer: &str, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let child_pointer = format!("{}/{}", pointer, key);
+                    if key.starts_with("ucp_") {
+                        found.push(child_pointer.clone());
+                    }
+                    leftover_annotations(child, &child_pointer, found);
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    leftover_annotations(item, &format!("{}/{}", pointer, i), found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn assert_no_annotations(value: &Value, context: &str) {
+        let mut found = Vec::new();
+        leftover_annotations(value, "", &mut found);
+        assert!(
+            found.is_empty(),
+            "{}: annotations survived at {:?}",
+            context,
+            found
+        );
+    }
+
+    #[test]
+    fn strip_annotations_reaches_every_keyword() {
+        let stripped = strip_annotations(&schema());
+        assert_no_annotations(&stripped, "strip_annotations");
+        // Only annotations go; the subschemas themselves stay
+        assert!(stripped["then"]["properties"]["then_field"].is_object());
+        assert!(stripped["dependentSchemas"]["kind"]["properties"]["dependent_field"].is_object());
+        assert_eq!(stripped["propertyNames"], json!({ "maxLength": 32 }));
+    }
+
+    #[test]
+    fn resolve_strips_annotations_under_every_keyword() {
+        let schema = schema();
+        for direction in [Direction::Request, Direction::Response] {
+            for op in ["create", "read", "update", "complete"] {
+                for (strict, include_future) in [(false, false), (true, false), (false, true)] {
+                    let options = ResolveOptions::new(direction, op)
+                        .strict(strict)
+                        .include_future(include_future);
+                    let resolved = resolve(&schema, &options).unwrap();
+                    assert_no_annotations(
+                        &resolved,
+                        &format!(
+                            "{:?}/{} strict={} include_future={}",
+                            direction, op, strict, include_future
+                        ),
+                    );
+                }
+            }
+        }
+
+        // Annotated fields inside conditionals are still resolved, not just dropped
+        let options = ResolveOptions::new(Direction::Request, "create");
+        let resolved = resolve(&schema, &options).unwrap();
+        assert_eq!(resolved["then"]["required"], json!(["then_field"]));
+        assert_eq!(
+            resolved["dependentSchemas"]["kind"]["required"],
+            json!(["dependent_field"])
+        );
+        assert_eq!(
+            resolved["properties"]["tags"]["contains"]["required"],
+            json!(["contains_field"])
+        );
+    }
 }